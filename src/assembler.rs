@@ -0,0 +1,350 @@
+use crate::instruction::Instruction;
+use bytes::Bytes;
+use ethereum_types::U256;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Reason source code can't be assembled.
+#[derive(Eq, PartialEq, Clone, Debug, Hash)]
+#[non_exhaustive]
+pub enum AssembleError {
+    /// A word that is neither a mnemonic nor a label definition.
+    UnknownMnemonic(String),
+    /// A `PUSH` at the end of the source, without a value.
+    MissingOperand(String),
+    /// A `PUSH` value that is neither a number nor a label name.
+    InvalidOperand(String),
+    /// A `PUSH` value wider than the `PUSH`.
+    OperandTooLarge(String),
+    /// A label defined more than once.
+    DuplicateLabel(String),
+    /// A label pushed but never defined.
+    UndefinedLabel(String),
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssembleError::UnknownMnemonic(word) => write!(f, "unknown mnemonic {}", word),
+            AssembleError::MissingOperand(word) => write!(f, "{} without a value", word),
+            AssembleError::InvalidOperand(word) => write!(f, "invalid value {}", word),
+            AssembleError::OperandTooLarge(word) => write!(f, "value {} too large", word),
+            AssembleError::DuplicateLabel(name) => write!(f, "label {} defined twice", name),
+            AssembleError::UndefinedLabel(name) => write!(f, "undefined label {}", name),
+        }
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+/// Value pushed by a `PUSH`.
+#[derive(Debug)]
+enum Operand {
+    Value(U256),
+    Label(String),
+}
+
+/// Piece of a program being assembled.
+#[derive(Debug)]
+enum Item {
+    Instruction(Instruction),
+
+    /// `PUSHn` of an operand, `width` is `None` for a bare `PUSH` sized to fit its operand.
+    Push {
+        width: Option<usize>,
+        operand: Operand,
+        source: String,
+    },
+
+    Label(String),
+}
+
+/// Assemble `source` into bytecode.
+///
+/// The source is a sequence of whitespace separated words:
+///
+/// - mnemonics, in any case, like `ADD` or `sstore`
+/// - `PUSH1` to `PUSH32` followed by a value, a decimal or `0x` prefixed hex number or a label
+/// - `PUSH` followed by a value, assembled as the narrowest `PUSHn` that fits it
+/// - label definitions like `loop:`, marking the position of the next instruction, which should
+///   be a `JUMPDEST` for the label to be a valid jump destination
+///
+/// Anything from `;` or `//` to the end of the line is a comment.
+pub fn assemble(source: &str) -> Result<Bytes, AssembleError> {
+    let items = parse(source)?;
+
+    let mut widths: Vec<usize> = items
+        .iter()
+        .map(|item| match item {
+            Item::Push {
+                width: Some(width), ..
+            } => *width,
+            Item::Push {
+                width: None,
+                operand: Operand::Value(value),
+                ..
+            } => value_width(*value),
+            _ => 1,
+        })
+        .collect();
+
+    // Widening a push to reach a label moves the labels after it, so widen until every label
+    // fits. Widths only grow, so this ends.
+    let labels = loop {
+        let labels = label_positions(&items, &widths);
+
+        let mut widened = false;
+        for (item, width) in items.iter().zip(&mut widths) {
+            if let Item::Push {
+                width: None,
+                operand: Operand::Label(name),
+                ..
+            } = item
+            {
+                let position = *labels
+                    .get(name)
+                    .ok_or_else(|| AssembleError::UndefinedLabel(name.clone()))?;
+                let needed = value_width(U256::from(position));
+                if needed > *width {
+                    *width = needed;
+                    widened = true;
+                }
+            }
+        }
+
+        if !widened {
+            break labels;
+        }
+    };
+
+    let mut code = Vec::new();
+    for (item, &width) in items.iter().zip(&widths) {
+        match item {
+            Item::Instruction(instruction) => code.push(*instruction as u8),
+            Item::Push {
+                operand, source, ..
+            } => {
+                let value = match operand {
+                    Operand::Value(value) => *value,
+                    Operand::Label(name) => match labels.get(name) {
+                        Some(&position) => U256::from(position),
+                        None => return Err(AssembleError::UndefinedLabel(name.clone())),
+                    },
+                };
+                if value_width(value) > width {
+                    return Err(AssembleError::OperandTooLarge(source.clone()));
+                }
+
+                let mut word = [0; 32];
+                value.to_big_endian(&mut word);
+                code.push(Instruction::Push1 as u8 + width as u8 - 1);
+                code.extend_from_slice(&word[32 - width..]);
+            }
+            Item::Label(_) => {}
+        }
+    }
+
+    Ok(Bytes::from(code))
+}
+
+/// Assemble the tokens given into bytecode, panicking if they aren't a valid program.
+///
+/// The tokens are assembled with `assemble`, so use `//` comments rather than `;` ones.
+#[macro_export]
+macro_rules! bytecode {
+    ($($token:tt)*) => {
+        match $crate::assemble(stringify!($($token)*)) {
+            Ok(code) => code,
+            Err(error) => panic!("invalid bytecode: {}", error),
+        }
+    };
+}
+
+/// Split `source` into instructions, pushes and label definitions.
+fn parse(source: &str) -> Result<Vec<Item>, AssembleError> {
+    let mut words = Vec::new();
+    for line in source.lines() {
+        let end = [line.find(';'), line.find("//")]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(line.len());
+        words.extend(line[..end].split_whitespace());
+    }
+
+    let mut items = Vec::new();
+    let mut defined = HashSet::new();
+    let mut words = words.into_iter().peekable();
+    while let Some(word) = words.next() {
+        // `stringify!` turns `name:` into `name :`.
+        let label = match word.strip_suffix(':') {
+            Some(name) if !name.is_empty() => Some(name),
+            _ if words.peek() == Some(&":") => {
+                words.next();
+                Some(word)
+            }
+            _ => None,
+        };
+        if let Some(name) = label {
+            if !defined.insert(name) {
+                return Err(AssembleError::DuplicateLabel(name.to_string()));
+            }
+            items.push(Item::Label(name.to_string()));
+            continue;
+        }
+
+        let width = if word.eq_ignore_ascii_case("PUSH") {
+            None
+        } else {
+            match mnemonic(word)? {
+                instruction if instruction.is_push() => instruction.push_bytes(),
+                instruction => {
+                    items.push(Item::Instruction(instruction));
+                    continue;
+                }
+            }
+        };
+
+        let operand = words
+            .next()
+            .ok_or_else(|| AssembleError::MissingOperand(word.to_string()))?;
+        items.push(Item::Push {
+            width,
+            operand: parse_operand(operand)?,
+            source: format!("{} {}", word, operand),
+        });
+    }
+
+    Ok(items)
+}
+
+/// The instruction named `word`, whatever its case.
+fn mnemonic(word: &str) -> Result<Instruction, AssembleError> {
+    word.parse()
+        .map_err(|_| AssembleError::UnknownMnemonic(word.to_string()))
+}
+
+fn parse_operand(word: &str) -> Result<Operand, AssembleError> {
+    let invalid = || AssembleError::InvalidOperand(word.to_string());
+
+    if word.starts_with(|c: char| c.is_ascii_digit()) {
+        let value = match word.strip_prefix("0x") {
+            Some(hex) if !hex.is_empty() && hex.len() <= 64 => {
+                U256::from_str_radix(hex, 16).map_err(|_| invalid())?
+            }
+            Some(_) => return Err(invalid()),
+            None => U256::from_dec_str(word).map_err(|_| invalid())?,
+        };
+        Ok(Operand::Value(value))
+    } else if word.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        Ok(Operand::Label(word.to_string()))
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Bytes needed to push `value`, at least one.
+fn value_width(value: U256) -> usize {
+    value.bits().div_ceil(8).max(1)
+}
+
+/// Position in the code of each label, with pushes of the widths given.
+fn label_positions(items: &[Item], widths: &[usize]) -> HashMap<String, usize> {
+    let mut labels = HashMap::new();
+    let mut pc = 0;
+    for (item, width) in items.iter().zip(widths) {
+        match item {
+            Item::Instruction(_) => pc += 1,
+            Item::Push { .. } => pc += 1 + width,
+            Item::Label(name) => {
+                labels.insert(name.clone(), pc);
+            }
+        }
+    }
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disassembler::disassemble;
+
+    #[test]
+    fn assembles_mnemonics_and_comments() {
+        let code = assemble(
+            "push1 0x2a PUSH1 0 SSTORE ; store 42
+             // push a full word
+             PUSH32 0xff
+             PUSH 256
+             STOP",
+        )
+        .unwrap();
+
+        let mut expected = vec![0x60, 0x2a, 0x60, 0x00, 0x55, 0x7f];
+        expected.extend([0; 31]);
+        expected.extend([0xff, 0x61, 0x01, 0x00, 0x00]);
+        assert_eq!(code.as_ref(), expected);
+    }
+
+    #[test]
+    fn labels_widen_pushes_to_fit() {
+        let mut source = String::from("PUSH end JUMP\n");
+        source.push_str(&"JUMPDEST\n".repeat(300));
+        source.push_str("end: JUMPDEST PUSH start JUMP start: JUMPDEST");
+
+        let code = assemble(&source).unwrap();
+        let disassembly = disassemble(&code);
+        let operations = disassembly.operations();
+
+        // The end label is past 0xff, so the first push takes two bytes.
+        assert_eq!(operations[0].to_string(), "0x0000: PUSH2 0x0130");
+        assert_eq!(operations[303].to_string(), "0x0131: PUSH2 0x0135");
+        assert!(operations[305].is_jump_destination());
+        assert_eq!(operations[305].pc, 0x135);
+    }
+
+    #[test]
+    fn bytecode_macro_assembles_tokens() {
+        let code = bytecode! {
+            PUSH1 0x00
+            top: JUMPDEST
+            PUSH1 0x01 ADD
+            DUP1 PUSH1 0x0a GT // loop while below 10
+            PUSH top JUMPI
+            STOP
+        };
+
+        assert_eq!(
+            code.as_ref(),
+            [0x60, 0x00, 0x5b, 0x60, 0x01, 0x01, 0x80, 0x60, 0x0a, 0x11, 0x60, 0x02, 0x57, 0x00]
+        );
+    }
+
+    #[test]
+    fn invalid_source_is_rejected() {
+        assert_eq!(
+            assemble("PUSH1 0x100"),
+            Err(AssembleError::OperandTooLarge("PUSH1 0x100".to_string()))
+        );
+        assert_eq!(
+            assemble("FOO"),
+            Err(AssembleError::UnknownMnemonic("FOO".to_string()))
+        );
+        assert_eq!(
+            assemble("PUSH2"),
+            Err(AssembleError::MissingOperand("PUSH2".to_string()))
+        );
+        assert_eq!(
+            assemble("PUSH nowhere JUMP"),
+            Err(AssembleError::UndefinedLabel("nowhere".to_string()))
+        );
+        assert_eq!(
+            assemble("a: a: STOP"),
+            Err(AssembleError::DuplicateLabel("a".to_string()))
+        );
+        assert_eq!(
+            assemble("PUSH 0xzz"),
+            Err(AssembleError::InvalidOperand("0xzz".to_string()))
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use crate::database::Database;
+use crate::env::Env;
+use crate::error::{BlockError, InvalidTransaction};
+use crate::executor::max_blobs_per_block;
+use crate::gas;
+use crate::journal::Log;
+use crate::precompile::Precompiles;
+use crate::result::ExecutionResult;
+use crate::rlp::{self, Encodable, Header};
+use crate::transaction::SignedTransaction;
+use crate::trie::Trie;
+use crate::vm::Vm;
+use ethereum_types::{Bloom, BloomInput, H256};
+
+/// Receipt of a transaction included in a block.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Receipt {
+    /// Type of the transaction, zero for a legacy transaction.
+    pub tx_type: u8,
+
+    /// False if the execution failed or reverted.
+    pub success: bool,
+
+    /// Gas used by the block up to and including this transaction.
+    pub cumulative_gas_used: u64,
+
+    /// Logs emitted by the transaction.
+    pub logs: Vec<Log>,
+
+    /// Bloom filter of the addresses and topics of the logs.
+    pub logs_bloom: Bloom,
+}
+
+impl Receipt {
+    /// Encode the receipt as it's stored in the receipts trie, prefixed by the transaction type
+    /// unless it's a legacy transaction (EIP-2718).
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        self.success.encode(&mut payload);
+        self.cumulative_gas_used.encode(&mut payload);
+        self.logs_bloom.encode(&mut payload);
+        self.logs.encode(&mut payload);
+
+        let mut out = Vec::with_capacity(payload.len() + 4);
+        if self.tx_type != 0 {
+            out.push(self.tx_type);
+        }
+        Header {
+            list: true,
+            payload_length: payload.len(),
+        }
+        .encode(&mut out);
+        out.extend(payload);
+        out
+    }
+}
+
+/// Bloom filter of the addresses and topics of a list of logs.
+pub fn logs_bloom<'a>(logs: impl IntoIterator<Item = &'a Log>) -> Bloom {
+    let mut bloom = Bloom::zero();
+    for log in logs {
+        bloom.accrue(BloomInput::Raw(log.address.as_bytes()));
+        for topic in &log.topics {
+            bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+        }
+    }
+    bloom
+}
+
+/// Outcome of the execution of a block.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct BlockResult {
+    /// Receipt of each transaction, in block order.
+    pub receipts: Vec<Receipt>,
+
+    /// Result of each transaction, in block order.
+    pub results: Vec<ExecutionResult>,
+
+    /// Gas used by all the transactions.
+    pub gas_used: u64,
+
+    /// Blob gas used by all the transactions (EIP-4844).
+    pub blob_gas_used: u64,
+
+    /// Root of the trie of receipts keyed by their index in the block.
+    pub receipts_root: H256,
+
+    /// Union of the blooms of all the receipts.
+    pub logs_bloom: Bloom,
+}
+
+/// Execute the transactions of a block in order, leaving their changes in the database.
+///
+/// A transaction that doesn't fit in the gas or blobs left in the block, or is invalid, makes the
+/// whole block invalid. The database then still holds the changes of the transactions before it,
+/// so callers that need to discard them should execute the block against a copy.
+pub fn execute_block<DB: Database>(
+    db: &mut DB,
+    env: &Env,
+    transactions: &[SignedTransaction],
+) -> Result<BlockResult, BlockError> {
+    execute_block_with_precompiles(db, env, &Precompiles::new(env.fork), transactions)
+}
+
+/// Execute the transactions of a block like `execute_block`, with custom precompiled contracts.
+pub fn execute_block_with_precompiles<DB: Database>(
+    db: &mut DB,
+    env: &Env,
+    precompiles: &Precompiles,
+    transactions: &[SignedTransaction],
+) -> Result<BlockResult, BlockError> {
+    let mut receipts = Vec::with_capacity(transactions.len());
+    let mut results = Vec::with_capacity(transactions.len());
+    let mut gas_used = 0;
+    let mut blob_count = 0;
+    let mut block_bloom = Bloom::zero();
+    let mut vm = Vm::with_precompiles(&mut *db, env.clone(), precompiles.clone());
+
+    for (index, transaction) in transactions.iter().enumerate() {
+        let tx = &transaction.transaction;
+        let error = |error| BlockError { index, error };
+
+        if tx.gas_limit() > env.block.gas_limit - gas_used {
+            return Err(error(InvalidTransaction::GasLimitExceedsBlock));
+        }
+        blob_count += tx.blob_versioned_hashes().len();
+        if blob_count > max_blobs_per_block(env.fork) {
+            return Err(error(InvalidTransaction::InvalidBlobCount));
+        }
+
+        let result = vm.transact(transaction).map_err(error)?;
+
+        gas_used += result.gas_used;
+        let logs_bloom = logs_bloom(&result.logs);
+        block_bloom.accrue_bloom(&logs_bloom);
+
+        receipts.push(Receipt {
+            tx_type: tx.tx_type(),
+            success: result.is_success(),
+            cumulative_gas_used: gas_used,
+            logs: result.logs.clone(),
+            logs_bloom,
+        });
+        results.push(result);
+    }
+
+    let mut trie = Trie::new();
+    for (index, receipt) in receipts.iter().enumerate() {
+        trie.insert(&rlp::encode(&index), &receipt.encode());
+    }
+
+    Ok(BlockResult {
+        receipts_root: trie.root(),
+        receipts,
+        results,
+        gas_used,
+        blob_gas_used: blob_count as u64 * gas::BLOB_GAS_PER_BLOB,
+        logs_bloom: block_bloom,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Account, InMemoryDatabase};
+    use crate::env::{BlockEnv, Fork};
+    use crate::signature::{Signature, SignatureError};
+    use crate::transaction::{BlobTransaction, Transaction};
+    use crate::trie::EMPTY_ROOT;
+    use bytes::Bytes;
+    use ethereum_types::{H160, U256};
+    use std::str::FromStr;
+
+    /// EIP-2930 transaction sending 5 wei to 0x3535..35 with nonce 1, a gas price of 10, data
+    /// `0xabcdef` and an access list of an address with two keys.
+    const ACCESS_LIST: &str = "01f8c001010a82c3509435353535353535353535353535353535353535350583abcdeff85bf859940000000000000000000000000000000000000100f842a00000000000000000000000000000000000000000000000000000000000000000a0000000000000000000000000000000000000000000000000000000000000000101a0562ccff02dbded0dcade4f378b439608f79acbf2b94bad362b195210d24bf0d4a061472fbb8701d67d02860c5d8038e0b9f0610816d9fad948eaf9e25c96ff8adc";
+
+    /// EIP-1559 transaction sending 7 wei to 0x3535..35 with nonce 2, a priority fee of 2 gwei,
+    /// a max fee of 100 gwei, data `0x01` and the same access list.
+    const FEE_MARKET: &str = "02f8c70102847735940085174876e80082ea609435353535353535353535353535353535353535350701f85bf859940000000000000000000000000000000000000100f842a00000000000000000000000000000000000000000000000000000000000000000a0000000000000000000000000000000000000000000000000000000000000000180a0091de516fe3fe3727db564c755dd0dfe126dab03229a1f34358d89d4e6b08fdfa05533899d1cf8309cc28afbe06a322254eaeea487b4f61bde3344a622f4cf90d0";
+
+    fn sender() -> H160 {
+        H160::from_str("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f").unwrap()
+    }
+
+    fn recipient() -> H160 {
+        H160::repeat_byte(0x35)
+    }
+
+    fn transactions() -> Vec<SignedTransaction> {
+        [ACCESS_LIST, FEE_MARKET]
+            .iter()
+            .map(|raw| SignedTransaction::decode(&hex::decode(raw).unwrap()).unwrap())
+            .collect()
+    }
+
+    /// Sender ready to send both transactions, to a contract that logs `LOG1(0, 0, 1)`.
+    fn database() -> InMemoryDatabase {
+        let mut db = InMemoryDatabase::new();
+        db.insert_account(sender(), Account::new(U256::exp10(18), 1, Bytes::new()));
+        db.insert_account(
+            recipient(),
+            Account::new(
+                U256::zero(),
+                0,
+                Bytes::from_static(&[0x60, 0x01, 0x60, 0x00, 0x60, 0x00, 0xa1]),
+            ),
+        );
+        db
+    }
+
+    fn env(gas_limit: u64) -> Env {
+        Env {
+            block: BlockEnv {
+                gas_limit,
+                ..BlockEnv::default()
+            },
+            ..Env::default()
+        }
+    }
+
+    #[test]
+    fn receipts_track_cumulative_gas_and_logs() {
+        let mut db = database();
+
+        let block = execute_block(&mut db, &env(30_000_000), &transactions()).unwrap();
+
+        let cumulative: Vec<_> = block
+            .receipts
+            .iter()
+            .map(|receipt| receipt.cumulative_gas_used)
+            .collect();
+        assert_eq!(cumulative, vec![28007, 55982]);
+        assert_eq!(block.gas_used, 55982);
+        assert_eq!(block.blob_gas_used, 0);
+
+        let log = Log {
+            address: recipient(),
+            topics: vec![H256::from_low_u64_be(1)],
+            data: Bytes::new(),
+        };
+        for receipt in &block.receipts {
+            assert!(receipt.success);
+            assert_eq!(receipt.logs, vec![log.clone()]);
+            assert_eq!(receipt.logs_bloom, logs_bloom([&log]));
+        }
+        assert_eq!(block.logs_bloom, logs_bloom([&log]));
+        assert_eq!(
+            block.receipts_root,
+            H256::from_str("ca364cb3265bf52d33475acb2eb678a5785d10c58b6d1f337b4a584315a10a61")
+                .unwrap()
+        );
+        assert_eq!(db.account(sender()).unwrap().nonce, 3);
+    }
+
+    #[test]
+    fn empty_block() {
+        let mut db = InMemoryDatabase::new();
+
+        let block = execute_block(&mut db, &env(30_000_000), &[]).unwrap();
+
+        assert_eq!(block.receipts_root, EMPTY_ROOT);
+        assert_eq!(block.logs_bloom, Bloom::zero());
+        assert_eq!(block.gas_used, 0);
+    }
+
+    #[test]
+    fn transaction_over_block_gas_left_is_rejected() {
+        let mut db = database();
+
+        // Room for the first transaction but not for the gas limit of the second.
+        let error = execute_block(&mut db, &env(80_000), &transactions()).unwrap_err();
+
+        assert_eq!(
+            error,
+            BlockError {
+                index: 1,
+                error: InvalidTransaction::GasLimitExceedsBlock
+            }
+        );
+        assert_eq!(db.account(sender()).unwrap().nonce, 2);
+    }
+
+    #[test]
+    fn blob_limit_follows_the_fork() {
+        // Unsigned, as the blobs are counted before the sender is recovered.
+        let transactions = [SignedTransaction {
+            transaction: Transaction::Blob(BlobTransaction {
+                chain_id: 1,
+                nonce: 0,
+                max_priority_fee_per_gas: U256::zero(),
+                max_fee_per_gas: U256::zero(),
+                gas_limit: 21000,
+                to: recipient(),
+                value: U256::zero(),
+                data: Bytes::new(),
+                access_list: Vec::new(),
+                max_fee_per_blob_gas: U256::one(),
+                blob_versioned_hashes: vec![H256::from_low_u64_be(1); 7],
+            }),
+            signature: Signature {
+                y_parity: 0,
+                r: U256::zero(),
+                s: U256::zero(),
+            },
+        }];
+        let execute_on = |fork| {
+            let env = Env {
+                fork,
+                ..env(30_000_000)
+            };
+            execute_block(&mut database(), &env, &transactions).unwrap_err()
+        };
+
+        // Six blobs fit in a Cancun block (EIP-4844) and nine from Prague (EIP-7691).
+        assert_eq!(
+            execute_on(Fork::Cancun).error,
+            InvalidTransaction::InvalidBlobCount
+        );
+        assert_eq!(
+            execute_on(Fork::Prague).error,
+            InvalidTransaction::Signature(SignatureError::OutOfRange)
+        );
+    }
+}
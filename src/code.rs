@@ -0,0 +1,132 @@
+use crate::instruction::Instruction;
+use bytes::Bytes;
+use ethereum_types::H160;
+
+/// Zero bytes appended to the code so that an immediate running off the end reads as zeros.
+///
+/// A `PUSH32` as the last byte needs 32 bytes after it, the extra byte is an implicit `STOP`.
+const PADDING: usize = 33;
+
+/// Prefix of the code of an account delegating to another account (EIP-7702).
+pub const DELEGATION_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+/// Code delegating to the account at `address`.
+pub fn delegation_designator(address: H160) -> Bytes {
+    let mut code = DELEGATION_PREFIX.to_vec();
+    code.extend_from_slice(address.as_bytes());
+    Bytes::from(code)
+}
+
+/// Account `code` delegates to, `None` if it isn't a delegation designator.
+pub fn delegated_address(code: &[u8]) -> Option<H160> {
+    match code.strip_prefix(&DELEGATION_PREFIX) {
+        Some(address) if address.len() == 20 => Some(H160::from_slice(address)),
+        _ => None,
+    }
+}
+
+/// Bytecode analyzed ahead of execution.
+#[derive(Debug, Clone, Default)]
+pub struct Code {
+    /// Code followed by `PADDING` zero bytes.
+    padded: Bytes,
+
+    /// Length of the code without the padding.
+    len: usize,
+
+    /// Whether each position is a `JUMPDEST` that isn't part of the immediate of a `PUSH`.
+    jump_destinations: Vec<bool>,
+}
+
+impl Code {
+    /// Pad the code and find its valid jump destinations.
+    pub fn analyze(code: Bytes) -> Code {
+        let len = code.len();
+
+        let mut jump_destinations = vec![false; len];
+        let mut pc = 0;
+        while pc < len {
+            match Instruction::try_from(code[pc]) {
+                Ok(Instruction::JumpDest) => jump_destinations[pc] = true,
+                Ok(instruction) => pc += instruction.push_bytes().unwrap_or(0),
+                Err(_) => {}
+            }
+
+            pc += 1;
+        }
+
+        let mut padded = Vec::with_capacity(len + PADDING);
+        padded.extend_from_slice(&code);
+        padded.resize(len + PADDING, 0);
+
+        Code {
+            padded: Bytes::from(padded),
+            len,
+            jump_destinations,
+        }
+    }
+
+    /// Length of the code without padding.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The byte at `pc`, zero past the end of the code.
+    pub fn byte(&self, pc: usize) -> u8 {
+        self.padded.get(pc).copied().unwrap_or(0)
+    }
+
+    /// The `length` bytes following `pc`, zero padded past the end of the code.
+    ///
+    /// `pc` must be inside the code and `length` at most 32, which holds for any `PUSH`.
+    pub fn immediate(&self, pc: usize, length: usize) -> &[u8] {
+        &self.padded[pc + 1..pc + 1 + length]
+    }
+
+    /// True if `destination` is a `JUMPDEST` instruction.
+    pub fn is_jump_destination(&self, destination: usize) -> bool {
+        self.jump_destinations
+            .get(destination)
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_data_is_not_a_jump_destination() {
+        // PUSH2 0x5b5b JUMPDEST
+        let code = Code::analyze(Bytes::from_static(&[0x61, 0x5b, 0x5b, 0x5b]));
+
+        assert!(!code.is_jump_destination(0));
+        assert!(!code.is_jump_destination(1));
+        assert!(!code.is_jump_destination(2));
+        assert!(code.is_jump_destination(3));
+        assert!(!code.is_jump_destination(4));
+    }
+
+    #[test]
+    fn delegation_designator_round_trips() {
+        let address = H160::from_low_u64_be(0x1234);
+        let code = delegation_designator(address);
+
+        assert_eq!(code.len(), 23);
+        assert_eq!(delegated_address(&code), Some(address));
+        assert_eq!(delegated_address(&code[..22]), None);
+        assert_eq!(delegated_address(&[0xef, 0x01]), None);
+    }
+
+    #[test]
+    fn truncated_push_reads_zeros() {
+        // PUSH32 0xff
+        let code = Code::analyze(Bytes::from_static(&[0x7f, 0xff]));
+
+        assert_eq!(code.len(), 2);
+        assert_eq!(code.immediate(0, 32)[0], 0xff);
+        assert!(code.immediate(0, 32)[1..].iter().all(|byte| *byte == 0));
+        assert_eq!(code.byte(2), 0);
+    }
+}
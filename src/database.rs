@@ -0,0 +1,285 @@
+use crate::hash::{keccak256, KECCAK_EMPTY};
+use crate::trie;
+use bytes::Bytes;
+use ethereum_types::{H160, H256, U256};
+use std::collections::HashMap;
+
+/// Account state other than storage.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Account {
+    /// Balance in wei.
+    pub balance: U256,
+
+    /// Number of transactions sent, or contracts created for a contract account.
+    pub nonce: u64,
+
+    /// Keccak-256 hash of `code`.
+    pub code_hash: H256,
+
+    /// Contract code, empty for externally owned accounts.
+    pub code: Bytes,
+}
+
+impl Account {
+    /// Account with the given balance, nonce and code.
+    pub fn new(balance: U256, nonce: u64, code: Bytes) -> Account {
+        Account {
+            balance,
+            nonce,
+            code_hash: keccak256(&code),
+            code,
+        }
+    }
+
+    /// Replace the code, keeping the code hash in sync.
+    pub fn set_code(&mut self, code: Bytes) {
+        self.code_hash = keccak256(&code);
+        self.code = code;
+    }
+
+    /// True if the account has no code, zero nonce and zero balance (EIP-161).
+    pub fn is_empty(&self) -> bool {
+        self.balance.is_zero() && self.nonce == 0 && self.code_hash == KECCAK_EMPTY
+    }
+}
+
+impl Default for Account {
+    fn default() -> Account {
+        Account {
+            balance: U256::zero(),
+            nonce: 0,
+            code_hash: KECCAK_EMPTY,
+            code: Bytes::new(),
+        }
+    }
+}
+
+/// Backend holding the state the VM executes against.
+///
+/// Storage slots that were never written read as zero, and writing zero to a slot is the same as
+/// clearing it.
+pub trait Database {
+    /// Account at `address`, `None` if it doesn't exist.
+    fn account(&self, address: H160) -> Option<Account>;
+
+    /// Value of the storage slot `key` of the account at `address`.
+    fn storage(&self, address: H160, key: H256) -> H256;
+
+    /// Create or replace the account at `address`, leaving its storage untouched.
+    fn insert_account(&mut self, address: H160, account: Account);
+
+    /// Delete the account at `address` along with its storage.
+    fn remove_account(&mut self, address: H160);
+
+    /// Write the storage slot `key` of the account at `address`.
+    fn set_storage(&mut self, address: H160, key: H256, value: H256);
+
+    /// Hash of the block `number`, zero if it isn't known.
+    fn block_hash(&self, _number: u64) -> H256 {
+        H256::zero()
+    }
+}
+
+/// Database that can list its accounts and storage, which computing the state root needs.
+pub trait IterableDatabase: Database {
+    /// Address of every existing account.
+    fn addresses(&self) -> Vec<H160>;
+
+    /// Non-zero storage slots of the account at `address`.
+    fn storage_slots(&self, address: H160) -> Vec<(H256, H256)>;
+
+    /// Root of the storage trie of the account at `address`.
+    fn storage_root(&self, address: H160) -> H256 {
+        trie::storage_root(
+            self.storage_slots(address)
+                .iter()
+                .map(|(key, value)| (key, value)),
+        )
+    }
+
+    /// Root of the state trie of every account.
+    fn state_root(&self) -> H256 {
+        let accounts: Vec<_> = self
+            .addresses()
+            .into_iter()
+            .filter_map(|address| {
+                let account = self.account(address)?;
+                Some((address, account, self.storage_root(address)))
+            })
+            .collect();
+
+        trie::state_root(
+            accounts
+                .iter()
+                .map(|(address, account, storage_root)| (address, account, *storage_root)),
+        )
+    }
+}
+
+/// Database keeping all state in memory.
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryDatabase {
+    accounts: HashMap<H160, Account>,
+    storage: HashMap<H160, HashMap<H256, H256>>,
+    block_hashes: HashMap<u64, H256>,
+}
+
+impl InMemoryDatabase {
+    pub fn new() -> InMemoryDatabase {
+        InMemoryDatabase::default()
+    }
+
+    /// All existing accounts.
+    pub fn accounts(&self) -> impl Iterator<Item = (&H160, &Account)> {
+        self.accounts.iter()
+    }
+
+    /// Non-zero storage slots of the account at `address`.
+    pub fn account_storage(&self, address: H160) -> impl Iterator<Item = (&H256, &H256)> {
+        self.storage.get(&address).into_iter().flatten()
+    }
+
+    /// Record the hash of the block `number`, for `BLOCKHASH` to return.
+    pub fn insert_block_hash(&mut self, number: u64, hash: H256) {
+        self.block_hashes.insert(number, hash);
+    }
+}
+
+impl Database for InMemoryDatabase {
+    fn account(&self, address: H160) -> Option<Account> {
+        self.accounts.get(&address).cloned()
+    }
+
+    fn storage(&self, address: H160, key: H256) -> H256 {
+        self.storage
+            .get(&address)
+            .and_then(|storage| storage.get(&key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn insert_account(&mut self, address: H160, account: Account) {
+        self.accounts.insert(address, account);
+    }
+
+    fn remove_account(&mut self, address: H160) {
+        self.accounts.remove(&address);
+        self.storage.remove(&address);
+    }
+
+    fn set_storage(&mut self, address: H160, key: H256, value: H256) {
+        if value.is_zero() {
+            if let Some(storage) = self.storage.get_mut(&address) {
+                storage.remove(&key);
+            }
+        } else {
+            self.storage.entry(address).or_default().insert(key, value);
+        }
+    }
+
+    fn block_hash(&self, number: u64) -> H256 {
+        self.block_hashes.get(&number).copied().unwrap_or_default()
+    }
+}
+
+impl IterableDatabase for InMemoryDatabase {
+    fn addresses(&self) -> Vec<H160> {
+        self.accounts.keys().copied().collect()
+    }
+
+    fn storage_slots(&self, address: H160) -> Vec<(H256, H256)> {
+        self.account_storage(address)
+            .map(|(key, value)| (*key, *value))
+            .collect()
+    }
+}
+
+impl<T: Database + ?Sized> Database for &mut T {
+    fn account(&self, address: H160) -> Option<Account> {
+        (**self).account(address)
+    }
+
+    fn storage(&self, address: H160, key: H256) -> H256 {
+        (**self).storage(address, key)
+    }
+
+    fn insert_account(&mut self, address: H160, account: Account) {
+        (**self).insert_account(address, account)
+    }
+
+    fn remove_account(&mut self, address: H160) {
+        (**self).remove_account(address)
+    }
+
+    fn set_storage(&mut self, address: H160, key: H256, value: H256) {
+        (**self).set_storage(address, key, value)
+    }
+
+    fn block_hash(&self, number: u64) -> H256 {
+        (**self).block_hash(number)
+    }
+}
+
+impl<T: IterableDatabase + ?Sized> IterableDatabase for &mut T {
+    fn addresses(&self) -> Vec<H160> {
+        (**self).addresses()
+    }
+
+    fn storage_slots(&self, address: H160) -> Vec<(H256, H256)> {
+        (**self).storage_slots(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_is_per_account() {
+        let mut db = InMemoryDatabase::new();
+        let a = H160::from_low_u64_be(1);
+        let b = H160::from_low_u64_be(2);
+        let key = H256::from_low_u64_be(7);
+
+        db.set_storage(a, key, H256::from_low_u64_be(1));
+
+        assert_eq!(db.storage(a, key), H256::from_low_u64_be(1));
+        assert_eq!(db.storage(b, key), H256::zero());
+    }
+
+    #[test]
+    fn writing_zero_clears_the_slot() {
+        let mut db = InMemoryDatabase::new();
+        let address = H160::from_low_u64_be(1);
+        let key = H256::from_low_u64_be(7);
+
+        db.set_storage(address, key, H256::from_low_u64_be(1));
+        db.set_storage(address, key, H256::zero());
+
+        assert_eq!(db.account_storage(address).count(), 0);
+    }
+
+    #[test]
+    fn removing_an_account_removes_its_storage() {
+        let mut db = InMemoryDatabase::new();
+        let address = H160::from_low_u64_be(1);
+        let key = H256::from_low_u64_be(7);
+
+        db.insert_account(address, Account::new(U256::one(), 0, Bytes::new()));
+        db.set_storage(address, key, H256::from_low_u64_be(1));
+        db.remove_account(address);
+
+        assert_eq!(db.account(address), None);
+        assert_eq!(db.storage(address, key), H256::zero());
+    }
+
+    #[test]
+    fn code_hash_follows_code() {
+        let mut account = Account::default();
+        assert!(account.is_empty());
+
+        account.set_code(Bytes::from_static(&[0x00]));
+        assert_eq!(account.code_hash, keccak256(&[0x00]));
+        assert!(!account.is_empty());
+    }
+}
@@ -0,0 +1,182 @@
+use crate::instruction::Instruction;
+use std::fmt;
+
+/// Instruction in disassembled code.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+pub struct Operation<'a> {
+    /// Position of the opcode in the code.
+    pub pc: usize,
+
+    pub opcode: u8,
+
+    /// Immediate of a `PUSH`, shorter than it should be if the code ends first.
+    pub immediate: &'a [u8],
+}
+
+impl Operation<'_> {
+    /// The instruction, `None` if the opcode is unknown.
+    pub fn instruction(&self) -> Option<Instruction> {
+        Instruction::try_from(self.opcode).ok()
+    }
+
+    /// True for a `PUSH` cut off by the end of the code.
+    pub fn is_truncated(&self) -> bool {
+        self.instruction()
+            .and_then(|instruction| instruction.push_bytes())
+            .is_some_and(|length| self.immediate.len() < length)
+    }
+
+    /// True if a jump can land here.
+    pub fn is_jump_destination(&self) -> bool {
+        self.instruction() == Some(Instruction::JumpDest)
+    }
+}
+
+impl fmt::Display for Operation<'_> {
+    /// Format as `0x0004: PUSH1 0x60`, with jump destinations and truncated pushes marked.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#06x}: ", self.pc)?;
+        match self.instruction() {
+            Some(instruction) => write!(f, "{}", instruction)?,
+            None => write!(f, "opcode {:#04x} not defined", self.opcode)?,
+        }
+
+        if !self.immediate.is_empty() {
+            f.write_str(" 0x")?;
+            for byte in self.immediate {
+                write!(f, "{:02x}", byte)?;
+            }
+        }
+        if self.is_jump_destination() {
+            f.write_str(" ; jump destination")?;
+        }
+        if self.is_truncated() {
+            f.write_str(" ; truncated")?;
+        }
+        Ok(())
+    }
+}
+
+/// Listing of the instructions in some code, in order.
+///
+/// Bytes inside the immediate of a `PUSH` are never instructions, so a `JUMPDEST` in the listing
+/// is always a valid jump destination.
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
+pub struct Disassembly<'a> {
+    operations: Vec<Operation<'a>>,
+}
+
+impl<'a> Disassembly<'a> {
+    /// Split `code` into instructions.
+    pub fn new(code: &'a [u8]) -> Disassembly<'a> {
+        let mut operations = Vec::new();
+        let mut pc = 0;
+        while pc < code.len() {
+            let opcode = code[pc];
+            let length = Instruction::try_from(opcode)
+                .ok()
+                .map_or(0, |instruction| instruction.info().immediate_size);
+            let end = (pc + 1 + length).min(code.len());
+
+            operations.push(Operation {
+                pc,
+                opcode,
+                immediate: &code[pc + 1..end],
+            });
+            pc += 1 + length;
+        }
+
+        Disassembly { operations }
+    }
+
+    pub fn operations(&self) -> &[Operation<'a>] {
+        &self.operations
+    }
+
+    /// The operation starting at `pc`, `None` if no instruction starts there.
+    pub fn at(&self, pc: usize) -> Option<&Operation<'a>> {
+        self.operations
+            .binary_search_by_key(&pc, |operation| operation.pc)
+            .ok()
+            .map(|index| &self.operations[index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Operation<'a>> {
+        self.operations.iter()
+    }
+}
+
+impl<'a> IntoIterator for Disassembly<'a> {
+    type Item = Operation<'a>;
+    type IntoIter = std::vec::IntoIter<Operation<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.operations.into_iter()
+    }
+}
+
+impl fmt::Display for Disassembly<'_> {
+    /// Format one operation per line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for operation in &self.operations {
+            writeln!(f, "{}", operation)?;
+        }
+        Ok(())
+    }
+}
+
+/// Split `code` into instructions.
+pub fn disassemble(code: &[u8]) -> Disassembly<'_> {
+    Disassembly::new(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listing_marks_jump_destinations() {
+        // PUSH1 0x04 JUMP INVALID JUMPDEST PUSH2 0x5b5b STOP
+        let code = [0x60, 0x04, 0x56, 0xfe, 0x5b, 0x61, 0x5b, 0x5b, 0x00];
+        let disassembly = disassemble(&code);
+
+        assert_eq!(
+            disassembly.to_string(),
+            "0x0000: PUSH1 0x04\n\
+             0x0002: JUMP\n\
+             0x0003: INVALID\n\
+             0x0004: JUMPDEST ; jump destination\n\
+             0x0005: PUSH2 0x5b5b\n\
+             0x0008: STOP\n"
+        );
+        assert_eq!(
+            disassembly.at(4).unwrap().instruction(),
+            Some(Instruction::JumpDest)
+        );
+        assert!(disassembly.at(6).is_none());
+    }
+
+    #[test]
+    fn unknown_bytes_and_truncated_pushes() {
+        // opcode 0xef, PUSH4 0x0102 cut off
+        let code = [0xef, 0x63, 0x01, 0x02];
+        let operations: Vec<_> = disassemble(&code).into_iter().collect();
+
+        assert_eq!(operations.len(), 2);
+        assert_eq!(operations[0].instruction(), None);
+        assert_eq!(operations[1].immediate, [0x01, 0x02]);
+        assert!(operations[1].is_truncated());
+        assert_eq!(
+            disassemble(&code).to_string(),
+            "0x0000: opcode 0xef not defined\n0x0001: PUSH4 0x0102 ; truncated\n"
+        );
+    }
+
+    #[test]
+    fn push_at_end_of_code_has_no_immediate() {
+        let operations: Vec<_> = disassemble(&[0x60]).into_iter().collect();
+
+        assert_eq!(operations[0].immediate, [] as [u8; 0]);
+        assert_eq!(operations[0].to_string(), "0x0000: PUSH1 ; truncated");
+    }
+}
@@ -0,0 +1,92 @@
+use ethereum_types::{H160, H256, U256};
+
+/// Block the code executes in.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct BlockEnv {
+    pub number: u64,
+
+    /// Account receiving the priority fees.
+    pub coinbase: H160,
+
+    pub timestamp: u64,
+
+    pub gas_limit: u64,
+
+    /// Fee per unit of gas that is burned (EIP-1559).
+    pub base_fee: U256,
+
+    /// Randomness from the beacon chain (EIP-4399), the difficulty before Paris.
+    pub prev_randao: H256,
+
+    /// Fee per unit of blob gas that is burned (EIP-4844).
+    pub blob_base_fee: U256,
+}
+
+impl Default for BlockEnv {
+    fn default() -> BlockEnv {
+        BlockEnv {
+            number: 0,
+            coinbase: H160::zero(),
+            timestamp: 0,
+            gas_limit: u64::MAX,
+            base_fee: U256::zero(),
+            prev_randao: H256::zero(),
+            blob_base_fee: U256::one(),
+        }
+    }
+}
+
+/// Transaction the code executes in.
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
+pub struct TxEnv {
+    /// Sender of the transaction.
+    pub origin: H160,
+
+    /// Price paid per unit of gas, including the base fee.
+    pub gas_price: U256,
+
+    /// Versioned hashes of the blobs carried by the transaction.
+    pub blob_hashes: Vec<H256>,
+}
+
+/// Hard fork of the network, in activation order.
+///
+/// Execution follows the rules of London and later forks, so the pricing and refunds of `SSTORE`
+/// are always those of EIP-2929 and EIP-3529. Earlier forks only describe which instructions and
+/// precompiles exist and their base costs, executing in one of them panics.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Debug, Default, Hash)]
+pub enum Fork {
+    Frontier,
+    Homestead,
+    Byzantium,
+    Constantinople,
+    Petersburg,
+    Istanbul,
+    Berlin,
+    London,
+    Paris,
+    Shanghai,
+    Cancun,
+    #[default]
+    Prague,
+}
+
+/// Everything outside of the state the code can observe.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Env {
+    pub chain_id: u64,
+    pub fork: Fork,
+    pub block: BlockEnv,
+    pub tx: TxEnv,
+}
+
+impl Default for Env {
+    fn default() -> Env {
+        Env {
+            chain_id: 1,
+            fork: Fork::default(),
+            block: BlockEnv::default(),
+            tx: TxEnv::default(),
+        }
+    }
+}
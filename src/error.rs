@@ -0,0 +1,254 @@
+use crate::instruction::Instruction;
+use crate::signature::SignatureError;
+use std::fmt;
+
+/// Reason execution failed.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Popping, peeking or swapping more items than are on the stack.
+    StackUnderflow,
+    /// Pushing past the stack limit.
+    StackOverflow,
+    /// The opcode isn't a known instruction.
+    InvalidOpcode,
+    /// Jump to a destination that isn't a `JUMPDEST`.
+    InvalidJump,
+    /// Memory access outside of the memory available to the VM.
+    InvalidMemoryAccess,
+    /// Not enough gas left to execute the instruction.
+    OutOfGas,
+    /// State modification attempted inside a static call.
+    StaticCallViolation,
+    /// Transferring more value than the sender holds.
+    InsufficientBalance,
+    /// Copying return data past its end.
+    ReturnDataOutOfBounds,
+    /// Creating a contract where an account with code or a nonce already exists.
+    CreateCollision,
+    /// Deploying code larger than allowed (EIP-170).
+    ContractSizeLimit,
+    /// Deploying code starting with the 0xef byte (EIP-3541).
+    InvalidContractPrefix,
+    /// Creating a contract with init code larger than allowed (EIP-3860).
+    InitCodeSizeLimit,
+    /// A precompiled contract was called with input it can't process.
+    PrecompileFailed,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ErrorKind::StackUnderflow => "stack underflow",
+            ErrorKind::StackOverflow => "stack overflow",
+            ErrorKind::InvalidOpcode => "invalid opcode",
+            ErrorKind::InvalidJump => "invalid jump destination",
+            ErrorKind::InvalidMemoryAccess => "invalid memory access",
+            ErrorKind::OutOfGas => "out of gas",
+            ErrorKind::StaticCallViolation => "state modification in static call",
+            ErrorKind::InsufficientBalance => "insufficient balance for transfer",
+            ErrorKind::ReturnDataOutOfBounds => "return data out of bounds",
+            ErrorKind::CreateCollision => "contract address collision",
+            ErrorKind::ContractSizeLimit => "max code size exceeded",
+            ErrorKind::InvalidContractPrefix => "invalid code: must not begin with 0xef",
+            ErrorKind::InitCodeSizeLimit => "max initcode size exceeded",
+            ErrorKind::PrecompileFailed => "precompiled contract failed",
+        })
+    }
+}
+
+/// Error raised while executing, along with the instruction that raised it.
+///
+/// Some errors aren't raised by an instruction, like a precompiled contract failing or a contract
+/// creation colliding at the start of a transaction, so have no pc or opcode.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+pub struct Error {
+    /// Program counter of the failing instruction.
+    pub pc: Option<usize>,
+
+    /// Opcode of the failing instruction.
+    pub opcode: Option<u8>,
+
+    /// What went wrong.
+    pub kind: ErrorKind,
+}
+
+impl Error {
+    /// Error raised by the instruction `opcode` at `pc`.
+    pub fn new(pc: usize, opcode: u8, kind: ErrorKind) -> Error {
+        Error {
+            pc: Some(pc),
+            opcode: Some(opcode),
+            kind,
+        }
+    }
+
+    /// The failing instruction, `None` if the opcode is unknown or there's no instruction.
+    pub fn instruction(&self) -> Option<Instruction> {
+        self.opcode
+            .and_then(|opcode| Instruction::try_from(opcode).ok())
+    }
+}
+
+impl From<ErrorKind> for Error {
+    /// Error raised outside of any instruction.
+    fn from(kind: ErrorKind) -> Error {
+        Error {
+            pc: None,
+            opcode: None,
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+
+        let (pc, opcode) = match (self.pc, self.opcode) {
+            (Some(pc), Some(opcode)) => (pc, opcode),
+            _ => return Ok(()),
+        };
+        write!(f, " at pc {} (", pc)?;
+
+        match self.instruction() {
+            Some(instruction) => write!(f, "{}", instruction)?,
+            None => write!(f, "0x{:02x}", opcode)?,
+        }
+
+        write!(f, ")")
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Reason a transaction can't be included in a block.
+///
+/// Unlike a failing execution, an invalid transaction doesn't change the state at all, not even to
+/// charge for gas.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+#[non_exhaustive]
+pub enum InvalidTransaction {
+    /// The sender can't be recovered from the signature.
+    Signature(SignatureError),
+    /// The transaction is signed for another chain.
+    ChainIdMismatch,
+    /// The nonce isn't the next nonce of the sender.
+    NonceMismatch { expected: u64, actual: u64 },
+    /// The nonce of the sender is at its maximum, so can't be incremented (EIP-2681).
+    NonceMax,
+    /// The sender has code, so can't be an externally owned account (EIP-3607).
+    SenderNotEoa,
+    /// The gas limit is above the gas left in the block.
+    GasLimitExceedsBlock,
+    /// The gas limit doesn't cover the intrinsic gas or the calldata floor (EIP-7623).
+    IntrinsicGasTooLow,
+    /// The max fee per gas is below the base fee of the block.
+    FeeCapTooLow,
+    /// The max priority fee per gas is above the max fee per gas.
+    PriorityFeeTooHigh,
+    /// The sender can't pay for the gas limit and the value.
+    InsufficientFunds,
+    /// The max fee per blob gas is below the blob base fee of the block.
+    BlobFeeCapTooLow,
+    /// A blob transaction without blobs, or with more than a block can hold.
+    InvalidBlobCount,
+    /// A blob versioned hash without the KZG version byte.
+    InvalidBlobVersionedHash,
+    /// A set code transaction without authorizations (EIP-7702).
+    EmptyAuthorizationList,
+    /// The init code is larger than allowed (EIP-3860).
+    InitCodeSizeLimit,
+    /// The transaction type isn't available in the fork.
+    TypeNotSupported,
+}
+
+impl fmt::Display for InvalidTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidTransaction::Signature(error) => write!(f, "invalid signature: {}", error),
+            InvalidTransaction::ChainIdMismatch => f.write_str("invalid chain id"),
+            InvalidTransaction::NonceMismatch { expected, actual } => {
+                write!(f, "invalid nonce: expected {}, got {}", expected, actual)
+            }
+            InvalidTransaction::NonceMax => f.write_str("nonce has max value"),
+            InvalidTransaction::SenderNotEoa => f.write_str("sender not an eoa"),
+            InvalidTransaction::GasLimitExceedsBlock => f.write_str("gas limit reached"),
+            InvalidTransaction::IntrinsicGasTooLow => f.write_str("intrinsic gas too low"),
+            InvalidTransaction::FeeCapTooLow => {
+                f.write_str("max fee per gas less than block base fee")
+            }
+            InvalidTransaction::PriorityFeeTooHigh => {
+                f.write_str("max priority fee per gas higher than max fee per gas")
+            }
+            InvalidTransaction::InsufficientFunds => {
+                f.write_str("insufficient funds for gas * price + value")
+            }
+            InvalidTransaction::BlobFeeCapTooLow => {
+                f.write_str("max fee per blob gas less than block blob gas fee")
+            }
+            InvalidTransaction::InvalidBlobCount => f.write_str("invalid number of blobs"),
+            InvalidTransaction::InvalidBlobVersionedHash => {
+                f.write_str("blob versioned hash with invalid version")
+            }
+            InvalidTransaction::EmptyAuthorizationList => {
+                f.write_str("set code transaction with empty authorization list")
+            }
+            InvalidTransaction::InitCodeSizeLimit => f.write_str("max initcode size exceeded"),
+            InvalidTransaction::TypeNotSupported => f.write_str("transaction type not supported"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidTransaction {}
+
+impl From<SignatureError> for InvalidTransaction {
+    fn from(error: SignatureError) -> InvalidTransaction {
+        InvalidTransaction::Signature(error)
+    }
+}
+
+/// Error raised by a transaction that can't be included in a block.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct BlockError {
+    /// Position of the transaction in the block.
+    pub index: usize,
+
+    /// Why the transaction is invalid.
+    pub error: InvalidTransaction,
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid transaction {}: {}", self.index, self.error)
+    }
+}
+
+impl std::error::Error for BlockError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_known_instruction() {
+        let error = Error::new(3, Instruction::Add as u8, ErrorKind::StackUnderflow);
+
+        assert_eq!(error.to_string(), "stack underflow at pc 3 (ADD)");
+    }
+
+    #[test]
+    fn display_unknown_opcode() {
+        let error = Error::new(0, 0xef, ErrorKind::InvalidOpcode);
+
+        assert_eq!(error.to_string(), "invalid opcode at pc 0 (0xef)");
+    }
+
+    #[test]
+    fn display_without_instruction() {
+        let error = Error::from(ErrorKind::PrecompileFailed);
+
+        assert_eq!(error.instruction(), None);
+        assert_eq!(error.to_string(), "precompiled contract failed");
+    }
+}
@@ -0,0 +1,688 @@
+use crate::code::{delegated_address, delegation_designator};
+use crate::database::Database;
+use crate::env::{Fork, TxEnv};
+use crate::error::{Error, ErrorKind, InvalidTransaction};
+use crate::frame::{create_address, has_collision};
+use crate::gas;
+use crate::inspector::Inspector;
+use crate::message::{CallKind, Message};
+use crate::result::{ExecutionResult, ExecutionStatus};
+use crate::transaction::{Authorization, SignedTransaction, Transaction, TxKind};
+use crate::vm::Vm;
+use bytes::Bytes;
+use ethereum_types::{H160, U256, U512};
+use log::debug;
+
+/// Version byte of a blob versioned hash committing to a KZG commitment (EIP-4844).
+const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// Most blobs a block can carry in `fork`, none before Cancun (EIP-4844), more from Prague
+/// (EIP-7691).
+pub(crate) fn max_blobs_per_block(fork: Fork) -> usize {
+    if fork >= Fork::Prague {
+        9
+    } else if fork >= Fork::Cancun {
+        6
+    } else {
+        0
+    }
+}
+
+impl<DB: Database, I: Inspector<DB>> Vm<DB, I> {
+    /// Validate and execute a transaction, leaving its changes in the database.
+    ///
+    /// The sender buys the gas limit up front at the effective gas price, and gets back what
+    /// wasn't used. The base fee and blob fee are burned and the priority fee goes to the coinbase
+    /// of the block. An invalid transaction leaves the state untouched.
+    ///
+    /// # Panics
+    ///
+    /// If the fork is older than London.
+    pub fn transact(
+        &mut self,
+        transaction: &SignedTransaction,
+    ) -> Result<ExecutionResult, InvalidTransaction> {
+        self.assert_supported_fork();
+
+        let sender = transaction.recover_sender()?;
+        let tx = &transaction.transaction;
+
+        self.validate(sender, tx)?;
+        self.inspector
+            .transaction(&self.env, sender, tx, &self.state);
+
+        let gas_price = self.effective_gas_price(tx);
+        let blob_fee = U256::from(gas::BLOB_GAS_PER_BLOB * tx.blob_versioned_hashes().len() as u64)
+            * self.env.block.blob_base_fee;
+
+        let balance = self.state.balance(sender);
+        self.state.set_balance(
+            sender,
+            balance - U256::from(tx.gas_limit()) * gas_price - blob_fee,
+        );
+        self.state.increment_nonce(sender);
+
+        self.env.tx = TxEnv {
+            origin: sender,
+            gas_price,
+            blob_hashes: tx.blob_versioned_hashes().to_vec(),
+        };
+
+        // The sender, recipient, coinbase from Shanghai (EIP-3651), precompiles and access list
+        // start out warm.
+        self.state.warm_account(sender);
+        if self.env.fork >= Fork::Shanghai {
+            self.state.warm_account(self.env.block.coinbase);
+        }
+        for address in self.precompiles.addresses() {
+            self.state.warm_account(address);
+        }
+        for item in tx.access_list() {
+            self.state.warm_account(item.address);
+            for key in &item.storage_keys {
+                self.state.warm_storage(item.address, *key);
+            }
+        }
+
+        let mut refund = 0;
+        for authorization in tx.authorization_list() {
+            refund += self.authorize(authorization);
+        }
+
+        let gas = tx.gas_limit() - gas::intrinsic_gas(tx, self.env.fork);
+        let result = match tx.to() {
+            TxKind::Call(to) => {
+                self.state.warm_account(to);
+
+                // A delegating account runs the code it delegates to (EIP-7702).
+                let mut code = self.code(to);
+                if let Some(delegate) =
+                    delegated_address(&code).filter(|_| self.env.fork >= Fork::Prague)
+                {
+                    self.state.warm_account(delegate);
+                    code = self.code(delegate);
+                }
+
+                Some(self.call(Message {
+                    kind: CallKind::Call,
+                    caller: sender,
+                    address: to,
+                    code_address: to,
+                    value: tx.value(),
+                    code,
+                    data: tx.data().clone(),
+                    gas,
+                    is_static: false,
+                    depth: 0,
+                }))
+            }
+            TxKind::Create => {
+                let address = create_address(sender, tx.nonce());
+                self.state.warm_account(address);
+
+                // A collision fails the creation, using up all the gas.
+                if has_collision(&self.state, address) {
+                    None
+                } else {
+                    Some(self.call(Message {
+                        kind: CallKind::Create,
+                        caller: sender,
+                        address,
+                        code_address: address,
+                        value: tx.value(),
+                        code: tx.data().clone(),
+                        data: Bytes::new(),
+                        gas,
+                        is_static: false,
+                        depth: 0,
+                    }))
+                }
+            }
+        };
+
+        let (status, output, remaining) = match &result {
+            Some(result) => {
+                if result.is_success() {
+                    refund += result.gas.refunded();
+                }
+
+                (result.status, result.output.clone(), result.gas.remaining())
+            }
+            None => (
+                ExecutionStatus::Halt(Error::from(ErrorKind::CreateCollision)),
+                Bytes::new(),
+                0,
+            ),
+        };
+        let success = status.is_success();
+
+        // At most a fifth of the gas used is refunded (EIP-3529), and calldata is paid at least
+        // its floor price (EIP-7623).
+        let gas_used = tx.gas_limit() - remaining;
+        let gas_refunded = (refund.max(0) as u64).min(gas_used / gas::MAX_REFUND_QUOTIENT);
+        let gas_used = (gas_used - gas_refunded).max(gas::floor_data_gas(tx, self.env.fork));
+
+        let balance = self.state.balance(sender);
+        self.state.set_balance(
+            sender,
+            balance + U256::from(tx.gas_limit() - gas_used) * gas_price,
+        );
+
+        let coinbase = self.env.block.coinbase;
+        let priority_fee = U256::from(gas_used) * (gas_price - self.env.block.base_fee);
+        if !priority_fee.is_zero() {
+            let balance = self.state.balance(coinbase);
+            self.state.set_balance(coinbase, balance + priority_fee);
+        }
+
+        let logs = self.state.take_logs();
+        self.state.finish_transaction();
+
+        debug!("Transaction used {} gas, success: {}", gas_used, success);
+
+        let result = ExecutionResult {
+            status,
+            gas_used,
+            gas_refunded,
+            output,
+            logs: if success { logs } else { Vec::new() },
+            created_address: result
+                .filter(|result| result.is_success() && result.kind.is_create())
+                .map(|result| result.address),
+        };
+        self.inspector.transaction_end(&result, &self.state);
+
+        Ok(result)
+    }
+
+    /// Check the transaction can be included in the block and the sender can pay for it.
+    fn validate(&self, sender: H160, tx: &Transaction) -> Result<(), InvalidTransaction> {
+        let env = &self.env;
+
+        // Blob transactions come with Cancun (EIP-4844) and set code ones with Prague (EIP-7702).
+        let since = match tx {
+            Transaction::Blob(_) => Fork::Cancun,
+            Transaction::SetCode(_) => Fork::Prague,
+            _ => Fork::London,
+        };
+        if env.fork < since {
+            return Err(InvalidTransaction::TypeNotSupported);
+        }
+
+        if tx
+            .chain_id()
+            .is_some_and(|chain_id| chain_id != env.chain_id)
+        {
+            return Err(InvalidTransaction::ChainIdMismatch);
+        }
+
+        let max_fee = tx.max_fee_per_gas();
+        if max_fee < env.block.base_fee {
+            return Err(InvalidTransaction::FeeCapTooLow);
+        }
+        if tx
+            .max_priority_fee_per_gas()
+            .is_some_and(|priority_fee| priority_fee > max_fee)
+        {
+            return Err(InvalidTransaction::PriorityFeeTooHigh);
+        }
+
+        if tx.gas_limit() > env.block.gas_limit {
+            return Err(InvalidTransaction::GasLimitExceedsBlock);
+        }
+        if tx.gas_limit() < gas::intrinsic_gas(tx, env.fork).max(gas::floor_data_gas(tx, env.fork))
+        {
+            return Err(InvalidTransaction::IntrinsicGasTooLow);
+        }
+
+        if env.fork >= Fork::Shanghai
+            && tx.to() == TxKind::Create
+            && tx.data().len() > gas::MAX_INITCODE_SIZE
+        {
+            return Err(InvalidTransaction::InitCodeSizeLimit);
+        }
+
+        if let Transaction::Blob(blob) = tx {
+            let count = blob.blob_versioned_hashes.len();
+            if count == 0 || count > max_blobs_per_block(env.fork) {
+                return Err(InvalidTransaction::InvalidBlobCount);
+            }
+            if blob
+                .blob_versioned_hashes
+                .iter()
+                .any(|hash| hash.as_bytes()[0] != VERSIONED_HASH_VERSION_KZG)
+            {
+                return Err(InvalidTransaction::InvalidBlobVersionedHash);
+            }
+            if blob.max_fee_per_blob_gas < env.block.blob_base_fee {
+                return Err(InvalidTransaction::BlobFeeCapTooLow);
+            }
+        }
+
+        if let Transaction::SetCode(set_code) = tx {
+            if set_code.authorization_list.is_empty() {
+                return Err(InvalidTransaction::EmptyAuthorizationList);
+            }
+        }
+
+        let account = self.state.account(sender).unwrap_or_default();
+
+        // Accounts that delegate still send transactions from Prague (EIP-7702).
+        let delegates = env.fork >= Fork::Prague && delegated_address(&account.code).is_some();
+        if !account.code.is_empty() && !delegates {
+            return Err(InvalidTransaction::SenderNotEoa);
+        }
+
+        if account.nonce == u64::MAX {
+            return Err(InvalidTransaction::NonceMax);
+        }
+        if account.nonce != tx.nonce() {
+            return Err(InvalidTransaction::NonceMismatch {
+                expected: account.nonce,
+                actual: tx.nonce(),
+            });
+        }
+
+        // The sender must afford the gas at the highest price it may pay, computed wide so huge
+        // fees can't overflow.
+        let blob_gas = gas::BLOB_GAS_PER_BLOB * tx.blob_versioned_hashes().len() as u64;
+        let cost = U512::from(tx.gas_limit()) * U512::from(max_fee)
+            + U512::from(tx.value())
+            + U512::from(blob_gas) * U512::from(tx.max_fee_per_blob_gas().unwrap_or_default());
+        if cost > U512::from(account.balance) {
+            return Err(InvalidTransaction::InsufficientFunds);
+        }
+
+        Ok(())
+    }
+
+    /// Price paid per unit of gas, including the base fee.
+    fn effective_gas_price(&self, tx: &Transaction) -> U256 {
+        match tx.max_priority_fee_per_gas() {
+            Some(priority_fee) => tx
+                .max_fee_per_gas()
+                .min(self.env.block.base_fee + priority_fee),
+            None => tx.max_fee_per_gas(),
+        }
+    }
+
+    /// Apply an authorization, setting the code of the authority to delegate (EIP-7702).
+    ///
+    /// Invalid authorizations are skipped. Returns the gas refunded, as the intrinsic gas assumed
+    /// the authority didn't exist.
+    fn authorize(&mut self, authorization: &Authorization) -> i64 {
+        if !authorization.chain_id.is_zero()
+            && authorization.chain_id != U256::from(self.env.chain_id)
+        {
+            return 0;
+        }
+
+        if authorization.nonce == u64::MAX {
+            return 0;
+        }
+
+        let authority = match authorization.recover_authority() {
+            Ok(authority) => authority,
+            Err(_) => return 0,
+        };
+
+        self.state.warm_account(authority);
+
+        let account = self.state.account(authority).unwrap_or_default();
+        if !account.code.is_empty() && delegated_address(&account.code).is_none() {
+            return 0;
+        }
+        if account.nonce != authorization.nonce {
+            return 0;
+        }
+
+        let refund = if account.is_empty() {
+            0
+        } else {
+            (gas::PER_EMPTY_ACCOUNT - gas::PER_AUTH_BASE) as i64
+        };
+
+        // Delegating to the zero address clears the delegation.
+        let code = if authorization.address.is_zero() {
+            Bytes::new()
+        } else {
+            delegation_designator(authorization.address)
+        };
+
+        self.state.set_code(authority, code);
+        self.state.increment_nonce(authority);
+
+        refund
+    }
+
+    /// Code of the account at `address`, empty if it doesn't exist.
+    fn code(&self, address: H160) -> Bytes {
+        self.state
+            .account(address)
+            .map(|account| account.code)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Account, InMemoryDatabase};
+    use crate::env::{BlockEnv, Env};
+    use crate::hash::keccak256;
+    use std::str::FromStr;
+
+    const GWEI: u64 = 1_000_000_000;
+
+    /// Legacy EIP-155 transaction sending one ether to 0x3535..35 with nonce 9, a gas price of 20
+    /// gwei and a gas limit of 21000.
+    const LEGACY: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+    /// Legacy contract creation with nonce 0, a gas price of 1 and init code
+    /// `PUSH1 0x00 PUSH1 0x00 SSTORE`.
+    const LEGACY_CREATE: &str = "f8518001830186a080808560006000551ca0cb8f9f1ef5f34cca80c5af391994befc52b75a4e4b01b36c8a64be256027228ea05e75f7a2e5e56bf34318e0006eefaeb7f9e40b3281e925459340e5c82e43e8a5";
+
+    /// EIP-1559 transaction sending 7 wei with nonce 2, a priority fee of 2 gwei, a max fee of 100
+    /// gwei, one byte of data and an access list of an address with two keys.
+    const FEE_MARKET: &str = "02f8c70102847735940085174876e80082ea609435353535353535353535353535353535353535350701f85bf859940000000000000000000000000000000000000100f842a00000000000000000000000000000000000000000000000000000000000000000a0000000000000000000000000000000000000000000000000000000000000000180a0091de516fe3fe3727db564c755dd0dfe126dab03229a1f34358d89d4e6b08fdfa05533899d1cf8309cc28afbe06a322254eaeea487b4f61bde3344a622f4cf90d0";
+
+    /// EIP-4844 transaction with nonce 3, a priority fee of 1, a max fee of 100, a max blob fee of
+    /// 3 and one blob.
+    const BLOB: &str = "03f885010301648252089435353535353535353535353535353535353535358080c003e1a0010000000000000000000000000000000000000000000000000000000000000180a01baa52eceab81f91af8acbb6e9edab66b50ee007e6f2cc7f7bbbbd731801b815a05cba7e5f473275db34b697e038fef93bfc12fd1430fdac68e7c5b5bd8a65940d";
+
+    /// EIP-7702 transaction with nonce 4 where the sender delegates to 0x00..0200 with
+    /// authorization nonce 5.
+    const SET_CODE: &str = "04f8c101040164830138809435353535353535353535353535353535353535358080c0f85cf85a019400000000000000000000000000000000000002000501a07841ba47d60bdaf403e9563b979404d7260534ce32088a904e9e1a93198495f5a00a75cd9aa6b9bbd01fd18c75a636d8a8ad532f8e9ca89b32a1befe206f1c3c5580a02f50ec6d5a6aa9f882e46cb6aa6e329a67157e66b6819636bb09d65102192260a06cc21cbd67ad3da43055eb8c1934ff5c2340a0df5a94c0ac346072d99129ca83";
+
+    fn sender() -> H160 {
+        H160::from_str("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f").unwrap()
+    }
+
+    fn recipient() -> H160 {
+        H160::from_str("3535353535353535353535353535353535353535").unwrap()
+    }
+
+    fn coinbase() -> H160 {
+        H160::from_low_u64_be(0xc0ffee)
+    }
+
+    fn ether(amount: u64) -> U256 {
+        U256::from(amount) * U256::exp10(18)
+    }
+
+    fn env(base_fee: u64) -> Env {
+        Env {
+            block: BlockEnv {
+                coinbase: coinbase(),
+                base_fee: U256::from(base_fee),
+                ..BlockEnv::default()
+            },
+            ..Env::default()
+        }
+    }
+
+    fn transact(
+        db: &mut InMemoryDatabase,
+        env: Env,
+        raw: &str,
+    ) -> Result<ExecutionResult, InvalidTransaction> {
+        let transaction = SignedTransaction::decode(&hex::decode(raw).unwrap()).unwrap();
+        Vm::with_env(db, env).transact(&transaction)
+    }
+
+    fn fund_sender(db: &mut InMemoryDatabase, nonce: u64) {
+        db.insert_account(sender(), Account::new(ether(2), nonce, Bytes::new()));
+    }
+
+    fn balance(db: &InMemoryDatabase, address: H160) -> U256 {
+        db.account(address).unwrap_or_default().balance
+    }
+
+    #[test]
+    fn transfer_pays_for_gas() {
+        let mut db = InMemoryDatabase::new();
+        fund_sender(&mut db, 9);
+
+        let result = transact(&mut db, env(0), LEGACY).unwrap();
+
+        assert!(result.is_success());
+        assert_eq!(result.gas_used, 21000);
+
+        let fee = U256::from(21000 * 20 * GWEI);
+        assert_eq!(balance(&db, sender()), ether(1) - fee);
+        assert_eq!(balance(&db, recipient()), ether(1));
+        assert_eq!(balance(&db, coinbase()), fee);
+        assert_eq!(db.account(sender()).unwrap().nonce, 10);
+    }
+
+    #[test]
+    fn base_fee_is_burned() {
+        let mut db = InMemoryDatabase::new();
+        fund_sender(&mut db, 9);
+
+        transact(&mut db, env(15 * GWEI), LEGACY).unwrap();
+
+        assert_eq!(
+            balance(&db, sender()),
+            ether(1) - U256::from(21000 * 20 * GWEI)
+        );
+        assert_eq!(balance(&db, coinbase()), U256::from(21000 * 5 * GWEI));
+    }
+
+    #[test]
+    fn fee_market_transaction_pays_effective_price() {
+        let mut db = InMemoryDatabase::new();
+        fund_sender(&mut db, 2);
+
+        let result = transact(&mut db, env(10 * GWEI), FEE_MARKET).unwrap();
+
+        // Calldata and an access list of an address with two keys.
+        let gas_used = 21000 + 16 + 2400 + 2 * 1900;
+        assert!(result.is_success());
+        assert_eq!(result.gas_used, gas_used);
+        assert_eq!(
+            balance(&db, sender()),
+            ether(2) - U256::from(7) - U256::from(gas_used * 12 * GWEI)
+        );
+        assert_eq!(balance(&db, recipient()), U256::from(7));
+        assert_eq!(balance(&db, coinbase()), U256::from(gas_used * 2 * GWEI));
+    }
+
+    #[test]
+    fn failed_call_still_pays_for_gas() {
+        let mut db = InMemoryDatabase::new();
+        fund_sender(&mut db, 9);
+        db.insert_account(
+            recipient(),
+            Account::new(U256::zero(), 0, Bytes::from_static(&[0xfe])),
+        );
+
+        let result = transact(&mut db, env(0), LEGACY).unwrap();
+
+        assert!(!result.is_success());
+        assert_eq!(result.gas_used, 21000);
+        assert_eq!(
+            balance(&db, sender()),
+            ether(2) - U256::from(21000 * 20 * GWEI)
+        );
+        assert_eq!(balance(&db, recipient()), U256::zero());
+        assert_eq!(db.account(sender()).unwrap().nonce, 10);
+    }
+
+    #[test]
+    fn creation_deploys_contract() {
+        let mut db = InMemoryDatabase::new();
+        fund_sender(&mut db, 0);
+
+        let result = transact(&mut db, env(0), LEGACY_CREATE).unwrap();
+
+        let address = create_address(sender(), 0);
+        assert!(result.is_success());
+        assert_eq!(result.created_address, Some(address));
+
+        // Intrinsic gas with the init code, then two pushes and a no-op cold SSTORE.
+        assert_eq!(
+            result.gas_used,
+            21000 + 32000 + 2 * 4 + 3 * 16 + 2 + 6 + 2200
+        );
+
+        let account = db.account(address).unwrap();
+        assert_eq!(account.nonce, 1);
+        assert_eq!(account.code_hash, keccak256(&[]));
+    }
+
+    #[test]
+    fn creation_before_shanghai_has_no_init_code_cost() {
+        let mut db = InMemoryDatabase::new();
+        fund_sender(&mut db, 0);
+
+        let env = Env {
+            fork: Fork::London,
+            ..env(0)
+        };
+        let result = transact(&mut db, env, LEGACY_CREATE).unwrap();
+
+        // Intrinsic gas without the init code word (EIP-3860).
+        assert_eq!(result.gas_used, 21000 + 32000 + 2 * 4 + 3 * 16 + 6 + 2200);
+    }
+
+    #[test]
+    fn create_collision_has_no_instruction() {
+        let mut db = InMemoryDatabase::new();
+        fund_sender(&mut db, 0);
+        db.insert_account(
+            create_address(sender(), 0),
+            Account::new(U256::zero(), 1, Bytes::new()),
+        );
+
+        let result = transact(&mut db, env(0), LEGACY_CREATE).unwrap();
+
+        let ExecutionStatus::Halt(error) = result.status else {
+            panic!("expected a halt, got {:?}", result.status);
+        };
+        assert_eq!(error, Error::from(ErrorKind::CreateCollision));
+        assert_eq!(error.to_string(), "contract address collision");
+    }
+
+    #[test]
+    fn authorization_sets_delegation() {
+        let mut db = InMemoryDatabase::new();
+        fund_sender(&mut db, 4);
+
+        let result = transact(&mut db, env(0), SET_CODE).unwrap();
+
+        // The authority exists, so part of the authorization cost is refunded up to a fifth of
+        // the gas used.
+        assert!(result.is_success());
+        assert_eq!(result.gas_refunded, 46000 / 5);
+        assert_eq!(result.gas_used, 46000 - 46000 / 5);
+
+        let account = db.account(sender()).unwrap();
+        assert_eq!(
+            account.code,
+            delegation_designator(H160::from_low_u64_be(0x200))
+        );
+        assert_eq!(account.nonce, 6);
+    }
+
+    #[test]
+    fn blob_transaction_needs_cancun() {
+        let mut db = InMemoryDatabase::new();
+        fund_sender(&mut db, 3);
+
+        let env = Env {
+            fork: Fork::Shanghai,
+            ..env(0)
+        };
+        assert_eq!(
+            transact(&mut db, env, BLOB),
+            Err(InvalidTransaction::TypeNotSupported)
+        );
+    }
+
+    #[test]
+    fn set_code_transaction_needs_prague() {
+        let mut db = InMemoryDatabase::new();
+        fund_sender(&mut db, 4);
+
+        let env = Env {
+            fork: Fork::Cancun,
+            ..env(0)
+        };
+        assert_eq!(
+            transact(&mut db, env, SET_CODE),
+            Err(InvalidTransaction::TypeNotSupported)
+        );
+    }
+
+    #[test]
+    fn invalid_transactions_leave_state_untouched() {
+        let mut db = InMemoryDatabase::new();
+        fund_sender(&mut db, 0);
+        let before = db.clone();
+
+        assert_eq!(
+            transact(&mut db, env(0), LEGACY),
+            Err(InvalidTransaction::NonceMismatch {
+                expected: 0,
+                actual: 9
+            })
+        );
+        assert_eq!(
+            transact(
+                &mut db,
+                Env {
+                    chain_id: 5,
+                    ..env(0)
+                },
+                LEGACY
+            ),
+            Err(InvalidTransaction::ChainIdMismatch)
+        );
+        assert_eq!(
+            transact(&mut db, env(21 * GWEI), LEGACY),
+            Err(InvalidTransaction::FeeCapTooLow)
+        );
+
+        assert_eq!(db.account(sender()), before.account(sender()));
+        assert_eq!(db.account(coinbase()), None);
+    }
+
+    #[test]
+    fn sender_must_afford_value_and_gas() {
+        let mut db = InMemoryDatabase::new();
+        db.insert_account(sender(), Account::new(ether(1), 9, Bytes::new()));
+
+        assert_eq!(
+            transact(&mut db, env(0), LEGACY),
+            Err(InvalidTransaction::InsufficientFunds)
+        );
+    }
+
+    #[test]
+    fn sender_with_max_nonce_is_rejected() {
+        let mut db = InMemoryDatabase::new();
+        fund_sender(&mut db, u64::MAX);
+        let before = db.clone();
+
+        assert_eq!(
+            transact(&mut db, env(0), LEGACY),
+            Err(InvalidTransaction::NonceMax)
+        );
+        assert_eq!(db.account(sender()), before.account(sender()));
+    }
+
+    #[test]
+    fn sender_with_code_is_rejected() {
+        let mut db = InMemoryDatabase::new();
+        db.insert_account(
+            sender(),
+            Account::new(ether(2), 9, Bytes::from_static(&[0x00])),
+        );
+
+        assert_eq!(
+            transact(&mut db, env(0), LEGACY),
+            Err(InvalidTransaction::SenderNotEoa)
+        );
+    }
+}
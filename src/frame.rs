@@ -0,0 +1,1452 @@
+use crate::code::{delegated_address, Code};
+use crate::database::Database;
+use crate::env::{Env, Fork};
+use crate::error::ErrorKind;
+use crate::gas::{self, Gas};
+use crate::hash::keccak256;
+use crate::i256::I256;
+use crate::inspector::{FrameView, Inspector};
+use crate::instruction::Instruction;
+use crate::journal::{Checkpoint, JournaledState, Log};
+use crate::message::{CallKind, Message};
+use crate::result::ExecutionStatus;
+use crate::rlp;
+use bytes::Bytes;
+use ethereum_types::{BigEndianHash, H160, H256, U256, U512};
+use log::{debug, error, trace};
+use std::ops::Range;
+
+/// Maximum number of nested calls.
+pub(crate) const CALL_DEPTH_LIMIT: usize = 1024;
+
+/// What to do after executing an instruction.
+pub(crate) enum Control {
+    /// Carry on with the next instruction.
+    Continue,
+    /// Halt the frame.
+    Exit(Exit),
+    /// Run a message in a new frame, writing its output to the range of memory.
+    Call(Box<Message>, Range<usize>),
+    /// Run the init code of a new contract in a new frame.
+    Create(Box<Message>),
+}
+
+/// How a frame halted without an exceptional error.
+pub(crate) enum Exit {
+    /// Halt without output.
+    Stop,
+    /// Halt returning the output.
+    Return(Bytes),
+    /// Halt reverting state changes, returning the output.
+    Revert(Bytes),
+    /// Halt after destroying the account.
+    SelfDestruct,
+    /// Halt without output after running past the end of the code.
+    EndOfCode,
+}
+
+/// Outcome of a frame, handed back to the frame that started it.
+#[derive(Debug)]
+pub(crate) struct FrameResult {
+    pub kind: CallKind,
+
+    /// Address the frame ran as, the new contract for a creation.
+    pub address: H160,
+
+    pub status: ExecutionStatus,
+
+    /// Output of `RETURN` or `REVERT`, the deployed code for a creation.
+    pub output: Bytes,
+
+    /// Gas left over and refunds accumulated by the frame.
+    pub gas: Gas,
+}
+
+impl FrameResult {
+    pub fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+
+    /// Output the caller sees as return data, none for a successful creation.
+    pub fn return_data(&self) -> Bytes {
+        if self.kind.is_create() && self.is_success() {
+            Bytes::new()
+        } else {
+            self.output.clone()
+        }
+    }
+}
+
+/// Execution of a single message, with its own stack, memory and gas.
+#[derive(Debug)]
+pub(crate) struct Frame {
+    pub message: Message,
+
+    /// The code we're executing.
+    pub code: Code,
+
+    /// EVM Stack
+    pub stack: Stack,
+
+    /// Memory, grown a word at a time as it's accessed.
+    pub memory: Vec<u8>,
+
+    /// The program counter into code in bytes.
+    pub pc: usize,
+
+    pub gas: Gas,
+
+    /// Output of the last call or create made by the frame.
+    pub return_data: Bytes,
+
+    /// Checkpoint taken when entering the frame, reverted to if it fails.
+    pub checkpoint: Checkpoint,
+
+    /// Where the output of the call the frame is waiting on goes in memory.
+    pub return_range: Range<usize>,
+}
+
+/// Stack and memory of a frame that halted, kept to be reused by a later frame.
+#[derive(Debug, Default)]
+pub(crate) struct Buffers {
+    pub stack: Stack,
+    pub memory: Vec<u8>,
+}
+
+impl Frame {
+    /// Frame for `message`, with its stack and memory in `buffers` after clearing them.
+    pub fn new(message: Message, checkpoint: Checkpoint, mut buffers: Buffers) -> Frame {
+        buffers.stack.clear();
+        buffers.memory.clear();
+
+        Frame {
+            code: Code::analyze(message.code.clone()),
+            gas: Gas::new(message.gas),
+            message,
+            stack: buffers.stack,
+            memory: buffers.memory,
+            pc: 0,
+            return_data: Bytes::new(),
+            checkpoint,
+            return_range: 0..0,
+        }
+    }
+
+    /// View of the frame for an inspector, `depth` frames deep and at `opcode`.
+    pub fn view(&self, depth: usize, opcode: u8) -> FrameView<'_> {
+        FrameView {
+            depth,
+            message: &self.message,
+            pc: self.pc,
+            opcode,
+            stack: self.stack.as_slice(),
+            memory: &self.memory,
+            gas: self.gas.remaining(),
+            refund: self.gas.refunded(),
+            return_data: &self.return_data,
+        }
+    }
+
+    /// Pick up after a call or create made by the frame returns.
+    pub fn resume(&mut self, result: FrameResult) {
+        self.gas.reimburse(result.gas.remaining());
+        if result.is_success() {
+            self.gas.refund(result.gas.refunded());
+        }
+
+        let value = if result.kind.is_create() {
+            if result.is_success() {
+                address_to_word(result.address)
+            } else {
+                U256::zero()
+            }
+        } else {
+            let range = std::mem::replace(&mut self.return_range, 0..0);
+            let return_data = result.return_data();
+            let length = range.len().min(return_data.len());
+            self.memory[range.start..range.start + length].copy_from_slice(&return_data[..length]);
+
+            U256::from(result.is_success() as u8)
+        };
+
+        // The operands of the call were popped, so there is room for its result.
+        self.stack.push(value).expect("stack has room for result");
+        self.return_data = result.return_data();
+    }
+
+    /// Execute the instruction at the program counter.
+    pub fn execute<DB: Database, I: Inspector<DB>>(
+        &mut self,
+        opcode: u8,
+        state: &mut JournaledState<DB>,
+        env: &Env,
+        inspector: &mut I,
+    ) -> Result<Control, ErrorKind> {
+        let instruction = Instruction::try_from(opcode).map_err(|err| {
+            error!("Unexpected instruction 0x{:x} err: {:?}", opcode, err);
+            ErrorKind::InvalidOpcode
+        })?;
+
+        debug!("{:?}", instruction);
+        trace!("Pc: {:?}", self.pc);
+        trace!("Gas: {:?}", self.gas.remaining());
+        trace!("Stack: {:?}", self.stack);
+
+        if !instruction.is_available(env.fork) {
+            return Err(ErrorKind::InvalidOpcode);
+        }
+
+        self.gas.charge(instruction.base_gas(env.fork))?;
+        self.pc += 1;
+
+        match instruction {
+            Instruction::Stop => return Ok(Control::Exit(Exit::Stop)),
+            Instruction::Add => {
+                let a = self.stack.pop()?;
+                let b = self.stack.pop()?;
+
+                self.stack.push(a.overflowing_add(b).0)?;
+            }
+
+            Instruction::Mul => {
+                let a = self.stack.pop()?;
+                let b = self.stack.pop()?;
+
+                self.stack.push(a.overflowing_mul(b).0)?;
+            }
+
+            Instruction::Sub => {
+                let a = self.stack.pop()?;
+                let b = self.stack.pop()?;
+
+                self.stack.push(a.overflowing_sub(b).0)?;
+            }
+
+            Instruction::Div => {
+                let a = self.stack.pop()?;
+                let b = self.stack.pop()?;
+
+                self.stack
+                    .push(if b.is_zero() { U256::zero() } else { a / b })?;
+            }
+
+            Instruction::SDiv => {
+                let a = I256::from(self.stack.pop()?);
+                let b = I256::from(self.stack.pop()?);
+
+                self.stack.push((a / b).into())?;
+            }
+
+            Instruction::Mod => {
+                let a = self.stack.pop()?;
+                let b = self.stack.pop()?;
+
+                self.stack
+                    .push(if b.is_zero() { U256::zero() } else { a % b })?;
+            }
+
+            Instruction::SMod => {
+                let a = I256::from(self.stack.pop()?);
+                let b = I256::from(self.stack.pop()?);
+
+                self.stack.push((a % b).into())?;
+            }
+
+            Instruction::AddMod => {
+                let a = self.stack.pop()?;
+                let b = self.stack.pop()?;
+                let modulus = self.stack.pop()?;
+
+                self.stack.push(add_mod(a, b, modulus))?;
+            }
+
+            Instruction::MulMod => {
+                let a = self.stack.pop()?;
+                let b = self.stack.pop()?;
+                let modulus = self.stack.pop()?;
+
+                self.stack.push(mul_mod(a, b, modulus))?;
+            }
+
+            Instruction::Exp => {
+                let a = self.stack.pop()?;
+                let b = self.stack.pop()?;
+
+                let exponent_bytes = (b.bits() as u64).div_ceil(8);
+                self.gas.charge(gas::EXP_BYTE * exponent_bytes)?;
+
+                self.stack.push(a.overflowing_pow(b).0)?;
+            }
+            Instruction::SignExtend => {
+                let b = self.stack.pop()?;
+                let x = self.stack.pop()?;
+
+                self.stack.push(I256::sign_extend(b, x).into())?;
+            }
+
+            Instruction::Lt => {
+                let a = self.stack.pop()?;
+                let b = self.stack.pop()?;
+
+                self.stack.push(U256::from((a < b) as u8))?;
+            }
+
+            Instruction::Gt => {
+                let a = self.stack.pop()?;
+                let b = self.stack.pop()?;
+
+                self.stack.push(U256::from((a > b) as u8))?;
+            }
+
+            Instruction::Slt => {
+                let a = I256::from(self.stack.pop()?);
+                let b = I256::from(self.stack.pop()?);
+
+                self.stack.push(U256::from((a < b) as u8))?;
+            }
+
+            Instruction::Sgt => {
+                let a = I256::from(self.stack.pop()?);
+                let b = I256::from(self.stack.pop()?);
+
+                self.stack.push(U256::from((a > b) as u8))?;
+            }
+
+            Instruction::Eq => {
+                let a = self.stack.pop()?;
+                let b = self.stack.pop()?;
+
+                self.stack.push(U256::from((a == b) as u8))?;
+            }
+
+            Instruction::IsZero => {
+                let a = self.stack.pop()?;
+                self.stack.push(U256::from(a.is_zero() as u8))?;
+            }
+
+            Instruction::And => {
+                let a = self.stack.pop()?;
+                let b = self.stack.pop()?;
+                self.stack.push(a & b)?;
+            }
+
+            Instruction::Or => {
+                let a = self.stack.pop()?;
+                let b = self.stack.pop()?;
+                self.stack.push(a | b)?;
+            }
+
+            Instruction::Xor => {
+                let a = self.stack.pop()?;
+                let b = self.stack.pop()?;
+                self.stack.push(a ^ b)?;
+            }
+
+            Instruction::Not => {
+                let a = self.stack.pop()?;
+                self.stack.push(!a)?;
+            }
+
+            Instruction::Byte => {
+                let i = self.stack.pop()?;
+                let x = self.stack.pop()?;
+
+                // The index counts from the most significant byte, `U256::byte` counts from the
+                // least significant byte.
+                self.stack.push(if i < U256::from(32) {
+                    U256::from(x.byte(31 - i.as_usize()))
+                } else {
+                    U256::zero()
+                })?;
+            }
+
+            Instruction::Shl => {
+                let shift = self.stack.pop()?;
+                let value = self.stack.pop()?;
+
+                self.stack.push(if shift < U256::from(256) {
+                    value << shift.as_usize()
+                } else {
+                    U256::zero()
+                })?;
+            }
+
+            Instruction::Shr => {
+                let shift = self.stack.pop()?;
+                let value = self.stack.pop()?;
+
+                self.stack.push(if shift < U256::from(256) {
+                    value >> shift.as_usize()
+                } else {
+                    U256::zero()
+                })?;
+            }
+
+            Instruction::Sar => {
+                let shift = self.stack.pop()?;
+                let value = I256::from(self.stack.pop()?);
+
+                self.stack.push(value.sar(shift).into())?;
+            }
+
+            Instruction::Keccak256 => {
+                let offset = self.stack.pop()?;
+                let length = self.stack.pop()?;
+                let range = self.memory_range(offset, length)?;
+
+                self.gas
+                    .charge(gas::KECCAK256_WORD * gas::words(range.len()))?;
+
+                self.stack
+                    .push(keccak256(&self.memory[range]).into_uint())?;
+            }
+
+            Instruction::Address => {
+                self.stack.push(address_to_word(self.message.address))?;
+            }
+
+            Instruction::Balance => {
+                let address = word_to_address(self.stack.pop()?);
+                self.access_account(state, address)?;
+
+                self.stack.push(state.balance(address))?;
+            }
+
+            Instruction::Origin => {
+                self.stack.push(address_to_word(env.tx.origin))?;
+            }
+
+            Instruction::Caller => {
+                self.stack.push(address_to_word(self.message.caller))?;
+            }
+
+            Instruction::CallValue => {
+                self.stack.push(self.message.value)?;
+            }
+
+            Instruction::CallDataLoad => {
+                let i = self.stack.pop()?;
+
+                self.stack.push(load_word(&self.message.data, i))?;
+            }
+
+            Instruction::CallDataSize => {
+                self.stack.push(U256::from(self.message.data.len()))?;
+            }
+
+            Instruction::CallDataCopy => {
+                let memory_offset = self.stack.pop()?;
+                let offset = self.stack.pop()?;
+                let length = self.stack.pop()?;
+
+                let data = self.message.data.clone();
+                self.copy_to_memory(&data, memory_offset, offset, length)?;
+            }
+
+            Instruction::CodeSize => {
+                self.stack.push(U256::from(self.message.code.len()))?;
+            }
+
+            Instruction::CodeCopy => {
+                let memory_offset = self.stack.pop()?;
+                let offset = self.stack.pop()?;
+                let length = self.stack.pop()?;
+
+                let code = self.message.code.clone();
+                self.copy_to_memory(&code, memory_offset, offset, length)?;
+            }
+
+            Instruction::GasPrice => {
+                self.stack.push(env.tx.gas_price)?;
+            }
+
+            Instruction::ExtCodeSize => {
+                let address = word_to_address(self.stack.pop()?);
+                self.access_account(state, address)?;
+
+                self.stack.push(U256::from(code_of(state, address).len()))?;
+            }
+
+            Instruction::ExtCodeCopy => {
+                let address = word_to_address(self.stack.pop()?);
+                let memory_offset = self.stack.pop()?;
+                let offset = self.stack.pop()?;
+                let length = self.stack.pop()?;
+                self.access_account(state, address)?;
+
+                let code = code_of(state, address);
+                self.copy_to_memory(&code, memory_offset, offset, length)?;
+            }
+
+            Instruction::ReturnDataSize => {
+                self.stack.push(U256::from(self.return_data.len()))?;
+            }
+
+            Instruction::ReturnDataCopy => {
+                let memory_offset = self.stack.pop()?;
+                let offset = self.stack.pop()?;
+                let length = self.stack.pop()?;
+
+                // Unlike other copies, reading past the end fails rather than padding with zeros.
+                let (end, overflow) = offset.overflowing_add(length);
+                if overflow || end > U256::from(self.return_data.len()) {
+                    return Err(ErrorKind::ReturnDataOutOfBounds);
+                }
+
+                let data = self.return_data.clone();
+                self.copy_to_memory(&data, memory_offset, offset, length)?;
+            }
+
+            Instruction::ExtCodeHash => {
+                let address = word_to_address(self.stack.pop()?);
+                self.access_account(state, address)?;
+
+                // Accounts that don't exist or are empty hash to zero (EIP-1052).
+                let hash = match state.account(address) {
+                    Some(account) if !account.is_empty() => account.code_hash,
+                    _ => H256::zero(),
+                };
+
+                self.stack.push(hash.into_uint())?;
+            }
+
+            Instruction::BlockHash => {
+                let number = self.stack.pop()?;
+                let current = env.block.number;
+
+                // Only the 256 most recent blocks are available.
+                let hash = match to_u64(number) {
+                    Some(number) if number < current && current - number <= 256 => {
+                        state.block_hash(number)
+                    }
+                    _ => H256::zero(),
+                };
+
+                self.stack.push(hash.into_uint())?;
+            }
+
+            Instruction::Coinbase => {
+                self.stack.push(address_to_word(env.block.coinbase))?;
+            }
+
+            Instruction::Timestamp => {
+                self.stack.push(U256::from(env.block.timestamp))?;
+            }
+
+            Instruction::Number => {
+                self.stack.push(U256::from(env.block.number))?;
+            }
+
+            Instruction::PrevRandao => {
+                self.stack.push(env.block.prev_randao.into_uint())?;
+            }
+
+            Instruction::GasLimit => {
+                self.stack.push(U256::from(env.block.gas_limit))?;
+            }
+
+            Instruction::ChainId => {
+                self.stack.push(U256::from(env.chain_id))?;
+            }
+
+            Instruction::SelfBalance => {
+                self.stack.push(state.balance(self.message.address))?;
+            }
+
+            Instruction::BaseFee => {
+                self.stack.push(env.block.base_fee)?;
+            }
+
+            Instruction::BlobHash => {
+                let index = self.stack.pop()?;
+
+                let hash = to_usize(index)
+                    .and_then(|index| env.tx.blob_hashes.get(index))
+                    .copied()
+                    .unwrap_or_default();
+
+                self.stack.push(hash.into_uint())?;
+            }
+
+            Instruction::BlobBaseFee => {
+                self.stack.push(env.block.blob_base_fee)?;
+            }
+
+            Instruction::Pop => {
+                self.stack.pop()?;
+            }
+
+            Instruction::MLoad => {
+                let offset = self.stack.pop()?;
+                let range = self.memory_range(offset, U256::from(32))?;
+
+                self.stack.push(U256::from(&self.memory[range]))?;
+            }
+
+            Instruction::MStore => {
+                let offset = self.stack.pop()?;
+                let value = self.stack.pop()?;
+                let range = self.memory_range(offset, U256::from(32))?;
+
+                value.to_big_endian(&mut self.memory[range]);
+            }
+
+            Instruction::MStore8 => {
+                let offset = self.stack.pop()?;
+                let value = self.stack.pop()?;
+                let range = self.memory_range(offset, U256::one())?;
+
+                self.memory[range.start] = value.low_u32() as u8;
+            }
+
+            Instruction::SLoad => {
+                let key = H256::from_uint(&self.stack.pop()?);
+
+                let cold = state.warm_storage(self.message.address, key);
+                self.gas.charge(if cold {
+                    gas::COLD_SLOAD
+                } else {
+                    gas::WARM_ACCESS
+                })?;
+
+                let value = state.storage(self.message.address, key);
+                inspector.storage_read(self.message.address, key, value);
+
+                self.stack.push(value.into_uint())?;
+            }
+
+            Instruction::SStore => {
+                let key = H256::from_uint(&self.stack.pop()?);
+                let value = H256::from_uint(&self.stack.pop()?);
+
+                self.sstore(state, key, value, inspector)?;
+            }
+
+            Instruction::Jump => {
+                let destination = self.stack.pop()?;
+
+                self.pc = self.jump_destination(destination)?;
+            }
+
+            Instruction::JumpI => {
+                let destination = self.stack.pop()?;
+                let condition = self.stack.pop()?;
+
+                if !condition.is_zero() {
+                    self.pc = self.jump_destination(destination)?;
+                }
+            }
+
+            Instruction::PC => {
+                // Remove the additional bump of the PC above.
+                self.stack.push(U256::from(self.pc - 1))?;
+            }
+
+            Instruction::MSize => {
+                self.stack.push(U256::from(self.memory.len()))?;
+            }
+
+            Instruction::Gas => {
+                self.stack.push(U256::from(self.gas.remaining()))?;
+            }
+
+            // No-op
+            Instruction::JumpDest => {}
+
+            Instruction::TLoad => {
+                let key = H256::from_uint(&self.stack.pop()?);
+
+                let value = state.transient_storage(self.message.address, key);
+
+                self.stack.push(value.into_uint())?;
+            }
+
+            Instruction::TStore => {
+                if self.message.is_static {
+                    return Err(ErrorKind::StaticCallViolation);
+                }
+
+                let key = H256::from_uint(&self.stack.pop()?);
+                let value = H256::from_uint(&self.stack.pop()?);
+
+                state.set_transient_storage(self.message.address, key, value);
+            }
+
+            Instruction::MCopy => {
+                let destination = self.stack.pop()?;
+                let source = self.stack.pop()?;
+                let length = self.stack.pop()?;
+
+                let source = self.memory_range(source, length)?;
+                let destination = self.memory_range(destination, length)?;
+                self.gas.charge(gas::COPY_WORD * gas::words(source.len()))?;
+
+                self.memory.copy_within(source, destination.start);
+            }
+
+            Instruction::Push0 => {
+                self.stack.push(U256::zero())?;
+            }
+
+            // TODO(jqphu): macroify all of this.
+            Instruction::Push1
+            | Instruction::Push2
+            | Instruction::Push3
+            | Instruction::Push4
+            | Instruction::Push5
+            | Instruction::Push6
+            | Instruction::Push7
+            | Instruction::Push8
+            | Instruction::Push9
+            | Instruction::Push10
+            | Instruction::Push11
+            | Instruction::Push12
+            | Instruction::Push13
+            | Instruction::Push14
+            | Instruction::Push15
+            | Instruction::Push16
+            | Instruction::Push17
+            | Instruction::Push18
+            | Instruction::Push19
+            | Instruction::Push20
+            | Instruction::Push21
+            | Instruction::Push22
+            | Instruction::Push23
+            | Instruction::Push24
+            | Instruction::Push25
+            | Instruction::Push26
+            | Instruction::Push27
+            | Instruction::Push28
+            | Instruction::Push29
+            | Instruction::Push30
+            | Instruction::Push31
+            | Instruction::Push32 => {
+                let bytes = instruction.push_bytes().unwrap();
+
+                let value = U256::from(self.code.immediate(self.pc - 1, bytes));
+                self.stack.push(value)?;
+
+                self.pc += bytes;
+            }
+
+            Instruction::Swap1
+            | Instruction::Swap2
+            | Instruction::Swap3
+            | Instruction::Swap4
+            | Instruction::Swap5
+            | Instruction::Swap6
+            | Instruction::Swap7
+            | Instruction::Swap8
+            | Instruction::Swap9
+            | Instruction::Swap10
+            | Instruction::Swap11
+            | Instruction::Swap12
+            | Instruction::Swap13
+            | Instruction::Swap14
+            | Instruction::Swap15
+            | Instruction::Swap16 => {
+                let position = instruction.swap_position().unwrap();
+
+                self.stack.swap(position)?;
+            }
+
+            Instruction::Dup1
+            | Instruction::Dup2
+            | Instruction::Dup3
+            | Instruction::Dup4
+            | Instruction::Dup5
+            | Instruction::Dup6
+            | Instruction::Dup7
+            | Instruction::Dup8
+            | Instruction::Dup9
+            | Instruction::Dup10
+            | Instruction::Dup11
+            | Instruction::Dup12
+            | Instruction::Dup13
+            | Instruction::Dup14
+            | Instruction::Dup15
+            | Instruction::Dup16 => {
+                let position = instruction.dup_position().unwrap();
+
+                self.stack.push(self.stack.peek(position)?)?;
+            }
+
+            Instruction::Log0
+            | Instruction::Log1
+            | Instruction::Log2
+            | Instruction::Log3
+            | Instruction::Log4 => {
+                if self.message.is_static {
+                    return Err(ErrorKind::StaticCallViolation);
+                }
+
+                let offset = self.stack.pop()?;
+                let length = self.stack.pop()?;
+
+                let count = instruction.log_topics().unwrap();
+                let mut topics = Vec::with_capacity(count);
+                for _ in 0..count {
+                    topics.push(H256::from_uint(&self.stack.pop()?));
+                }
+
+                let range = self.memory_range(offset, length)?;
+                self.gas
+                    .charge(gas::LOG_TOPIC * count as u64 + gas::LOG_DATA * range.len() as u64)?;
+
+                let log = Log {
+                    address: self.message.address,
+                    topics,
+                    data: Bytes::copy_from_slice(&self.memory[range]),
+                };
+                inspector.log(&log);
+                state.log(log);
+            }
+
+            Instruction::Create | Instruction::Create2 => {
+                return self.create(instruction, state, env.fork)
+            }
+
+            Instruction::Call
+            | Instruction::CallCode
+            | Instruction::DelegateCall
+            | Instruction::StaticCall => return self.call(instruction, state, env.fork),
+
+            Instruction::Return | Instruction::Revert => {
+                let offset = self.stack.pop()?;
+                let length = self.stack.pop()?;
+
+                let range = self.memory_range(offset, length)?;
+                let output = Bytes::copy_from_slice(&self.memory[range]);
+
+                return Ok(Control::Exit(if instruction == Instruction::Return {
+                    Exit::Return(output)
+                } else {
+                    Exit::Revert(output)
+                }));
+            }
+
+            Instruction::Invalid => return Err(ErrorKind::InvalidOpcode),
+
+            Instruction::SelfDestruct => {
+                if self.message.is_static {
+                    return Err(ErrorKind::StaticCallViolation);
+                }
+
+                let beneficiary = word_to_address(self.stack.pop()?);
+
+                if state.warm_account(beneficiary) {
+                    self.gas.charge(gas::COLD_ACCOUNT_ACCESS)?;
+                }
+                if !state.balance(self.message.address).is_zero() && is_dead(state, beneficiary) {
+                    self.gas.charge(gas::NEW_ACCOUNT)?;
+                }
+
+                inspector.selfdestruct(
+                    self.message.address,
+                    beneficiary,
+                    state.balance(self.message.address),
+                );
+                state.selfdestruct(self.message.address, beneficiary, env.fork);
+
+                return Ok(Control::Exit(Exit::SelfDestruct));
+            }
+        }
+
+        Ok(Control::Continue)
+    }
+
+    /// Write a storage slot, charging and refunding as in EIP-2200 with access costs (EIP-2929)
+    /// and reduced refunds (EIP-3529).
+    fn sstore<DB: Database, I: Inspector<DB>>(
+        &mut self,
+        state: &mut JournaledState<DB>,
+        key: H256,
+        value: H256,
+        inspector: &mut I,
+    ) -> Result<(), ErrorKind> {
+        if self.message.is_static {
+            return Err(ErrorKind::StaticCallViolation);
+        }
+
+        if self.gas.remaining() <= gas::SSTORE_SENTRY {
+            return Err(ErrorKind::OutOfGas);
+        }
+
+        let address = self.message.address;
+        if state.warm_storage(address, key) {
+            self.gas.charge(gas::COLD_SLOAD)?;
+        }
+
+        let current = state.storage(address, key);
+        let original = state.original_storage(address, key);
+
+        if value == current {
+            self.gas.charge(gas::WARM_ACCESS)?;
+        } else if original == current {
+            if original.is_zero() {
+                self.gas.charge(gas::SSTORE_SET)?;
+            } else {
+                self.gas.charge(gas::SSTORE_RESET)?;
+
+                if value.is_zero() {
+                    self.gas.refund(gas::SSTORE_CLEARS_REFUND);
+                }
+            }
+        } else {
+            // The slot was already written in the transaction, only refunds change.
+            self.gas.charge(gas::WARM_ACCESS)?;
+
+            if !original.is_zero() {
+                if current.is_zero() {
+                    self.gas.refund(-gas::SSTORE_CLEARS_REFUND);
+                } else if value.is_zero() {
+                    self.gas.refund(gas::SSTORE_CLEARS_REFUND);
+                }
+            }
+
+            if value == original {
+                let cost = if original.is_zero() {
+                    gas::SSTORE_SET
+                } else {
+                    gas::SSTORE_RESET
+                };
+
+                self.gas.refund((cost - gas::WARM_ACCESS) as i64);
+            }
+        }
+
+        inspector.storage_write(address, key, current, value);
+        state.set_storage(address, key, value);
+
+        Ok(())
+    }
+
+    /// Start creating a contract with `CREATE` or `CREATE2`.
+    fn create<DB: Database>(
+        &mut self,
+        instruction: Instruction,
+        state: &mut JournaledState<DB>,
+        fork: Fork,
+    ) -> Result<Control, ErrorKind> {
+        if self.message.is_static {
+            return Err(ErrorKind::StaticCallViolation);
+        }
+
+        let value = self.stack.pop()?;
+        let offset = self.stack.pop()?;
+        let length = self.stack.pop()?;
+        let salt = match instruction {
+            Instruction::Create2 => Some(H256::from_uint(&self.stack.pop()?)),
+            _ => None,
+        };
+
+        // Init code is limited and paid per word from Shanghai (EIP-3860).
+        let eip3860 = fork >= Fork::Shanghai;
+        if eip3860 && length > U256::from(gas::MAX_INITCODE_SIZE) {
+            return Err(ErrorKind::InitCodeSizeLimit);
+        }
+
+        let range = self.memory_range(offset, length)?;
+        let words = gas::words(range.len());
+        if eip3860 {
+            self.gas.charge(gas::INITCODE_WORD * words)?;
+        }
+        if salt.is_some() {
+            // The init code is hashed to derive the address.
+            self.gas.charge(gas::KECCAK256_WORD * words)?;
+        }
+
+        let code = Bytes::copy_from_slice(&self.memory[range]);
+        let gas = gas::all_but_one_64th(self.gas.remaining());
+        self.gas.charge(gas)?;
+        self.return_data = Bytes::new();
+
+        // These failures only push zero, handing back the gas.
+        let creator = self.message.address;
+        let nonce = state.account(creator).unwrap_or_default().nonce;
+        if self.message.depth >= CALL_DEPTH_LIMIT
+            || state.balance(creator) < value
+            || nonce == u64::MAX
+        {
+            self.gas.reimburse(gas);
+            self.stack.push(U256::zero())?;
+            return Ok(Control::Continue);
+        }
+
+        state.increment_nonce(creator);
+
+        let address = match salt {
+            Some(salt) => create2_address(creator, salt, &code),
+            None => create_address(creator, nonce),
+        };
+
+        // A collision consumes all the gas given to the creation.
+        state.warm_account(address);
+        if has_collision(state, address) {
+            self.stack.push(U256::zero())?;
+            return Ok(Control::Continue);
+        }
+
+        Ok(Control::Create(Box::new(Message {
+            kind: if salt.is_some() {
+                CallKind::Create2
+            } else {
+                CallKind::Create
+            },
+            caller: creator,
+            address,
+            code_address: address,
+            value,
+            code,
+            data: Bytes::new(),
+            gas,
+            is_static: false,
+            depth: self.message.depth + 1,
+        })))
+    }
+
+    /// Start a call with `CALL`, `CALLCODE`, `DELEGATECALL` or `STATICCALL`.
+    fn call<DB: Database>(
+        &mut self,
+        instruction: Instruction,
+        state: &mut JournaledState<DB>,
+        fork: Fork,
+    ) -> Result<Control, ErrorKind> {
+        let requested_gas = self.stack.pop()?;
+        let target = word_to_address(self.stack.pop()?);
+        let value = match instruction {
+            Instruction::Call | Instruction::CallCode => self.stack.pop()?,
+            _ => U256::zero(),
+        };
+        let input_offset = self.stack.pop()?;
+        let input_length = self.stack.pop()?;
+        let output_offset = self.stack.pop()?;
+        let output_length = self.stack.pop()?;
+
+        if instruction == Instruction::Call && self.message.is_static && !value.is_zero() {
+            return Err(ErrorKind::StaticCallViolation);
+        }
+
+        let input = self.memory_range(input_offset, input_length)?;
+        let output = self.memory_range(output_offset, output_length)?;
+
+        self.access_account(state, target)?;
+
+        // Calling an account that delegates runs the code it delegates to (EIP-7702).
+        let mut code = code_of(state, target);
+        if let Some(delegate) = delegated_address(&code).filter(|_| fork >= Fork::Prague) {
+            self.access_account(state, delegate)?;
+            code = code_of(state, delegate);
+        }
+
+        if !value.is_zero() {
+            self.gas.charge(gas::CALL_VALUE)?;
+
+            if instruction == Instruction::Call && is_dead(state, target) {
+                self.gas.charge(gas::NEW_ACCOUNT)?;
+            }
+        }
+
+        // At most all but one 64th of the remaining gas can be passed on (EIP-150).
+        let gas = match to_u64(requested_gas) {
+            Some(gas) => gas.min(gas::all_but_one_64th(self.gas.remaining())),
+            None => gas::all_but_one_64th(self.gas.remaining()),
+        };
+        self.gas.charge(gas)?;
+
+        // Calls with value get a stipend on top, for the callee to at least log the payment.
+        let gas = if value.is_zero() {
+            gas
+        } else {
+            gas + gas::CALL_STIPEND
+        };
+
+        self.return_data = Bytes::new();
+
+        // These failures only push zero, handing back the gas.
+        if self.message.depth >= CALL_DEPTH_LIMIT || state.balance(self.message.address) < value {
+            self.gas.reimburse(gas);
+            self.stack.push(U256::zero())?;
+            return Ok(Control::Continue);
+        }
+
+        let message = match instruction {
+            Instruction::Call => Message {
+                kind: CallKind::Call,
+                caller: self.message.address,
+                address: target,
+                value,
+                is_static: self.message.is_static,
+                ..Message::default()
+            },
+            Instruction::CallCode => Message {
+                kind: CallKind::CallCode,
+                caller: self.message.address,
+                address: self.message.address,
+                value,
+                is_static: self.message.is_static,
+                ..Message::default()
+            },
+            Instruction::DelegateCall => Message {
+                kind: CallKind::DelegateCall,
+                caller: self.message.caller,
+                address: self.message.address,
+                value: self.message.value,
+                is_static: self.message.is_static,
+                ..Message::default()
+            },
+            _ => Message {
+                kind: CallKind::StaticCall,
+                caller: self.message.address,
+                address: target,
+                value: U256::zero(),
+                is_static: true,
+                ..Message::default()
+            },
+        };
+
+        Ok(Control::Call(
+            Box::new(Message {
+                code_address: target,
+                code,
+                data: Bytes::copy_from_slice(&self.memory[input]),
+                gas,
+                depth: self.message.depth + 1,
+                ..message
+            }),
+            output,
+        ))
+    }
+
+    /// Charge for accessing an account, which costs more the first time in a transaction
+    /// (EIP-2929).
+    fn access_account<DB: Database>(
+        &mut self,
+        state: &mut JournaledState<DB>,
+        address: H160,
+    ) -> Result<(), ErrorKind> {
+        self.gas.charge(if state.warm_account(address) {
+            gas::COLD_ACCOUNT_ACCESS
+        } else {
+            gas::WARM_ACCESS
+        })
+    }
+
+    /// Copy `length` bytes of `source` from `offset` to memory, zero padding past its end.
+    fn copy_to_memory(
+        &mut self,
+        source: &[u8],
+        memory_offset: U256,
+        offset: U256,
+        length: U256,
+    ) -> Result<(), ErrorKind> {
+        let range = self.memory_range(memory_offset, length)?;
+        self.gas.charge(gas::COPY_WORD * gas::words(range.len()))?;
+
+        copy_padded(&mut self.memory[range], source, offset);
+
+        Ok(())
+    }
+
+    /// Range of memory `length` bytes from `offset`, expanding memory to cover it.
+    ///
+    /// An empty range doesn't touch memory, whatever the offset.
+    fn memory_range(&mut self, offset: U256, length: U256) -> Result<Range<usize>, ErrorKind> {
+        if length.is_zero() {
+            return Ok(0..0);
+        }
+
+        let start = to_usize(offset).ok_or(ErrorKind::InvalidMemoryAccess)?;
+        let length = to_usize(length).ok_or(ErrorKind::InvalidMemoryAccess)?;
+        let end = start
+            .checked_add(length)
+            .ok_or(ErrorKind::InvalidMemoryAccess)?;
+
+        if end > self.memory.len() {
+            let words = gas::words(end);
+            let current = gas::words(self.memory.len());
+            self.gas
+                .charge(gas::memory_cost(words) - gas::memory_cost(current))?;
+
+            self.memory.resize(words as usize * 32, 0);
+        }
+
+        Ok(start..end)
+    }
+
+    /// Validate a jump destination taken from the stack.
+    fn jump_destination(&self, destination: U256) -> Result<usize, ErrorKind> {
+        match to_usize(destination) {
+            Some(destination) if self.code.is_jump_destination(destination) => Ok(destination),
+            _ => Err(ErrorKind::InvalidJump),
+        }
+    }
+}
+
+/// Code of the account at `address`, empty if it doesn't exist.
+fn code_of<DB: Database>(state: &JournaledState<DB>, address: H160) -> Bytes {
+    state
+        .account(address)
+        .map(|account| account.code)
+        .unwrap_or_default()
+}
+
+/// True if the account at `address` doesn't exist or is empty (EIP-161).
+fn is_dead<DB: Database>(state: &JournaledState<DB>, address: H160) -> bool {
+    state
+        .account(address)
+        .is_none_or(|account| account.is_empty())
+}
+
+/// True if a contract can't be created at `address` because an account with code or a nonce is
+/// already there.
+pub(crate) fn has_collision<DB: Database>(state: &JournaledState<DB>, address: H160) -> bool {
+    state
+        .account(address)
+        .is_some_and(|account| account.nonce != 0 || !account.code.is_empty())
+}
+
+/// Address of a contract created by `creator` with `CREATE` or a transaction.
+pub(crate) fn create_address(creator: H160, nonce: u64) -> H160 {
+    let mut encoded = Vec::new();
+    rlp::encode_list(&[&creator, &nonce], &mut encoded);
+
+    H160::from(keccak256(&encoded))
+}
+
+/// Address of a contract created by `creator` with `CREATE2` (EIP-1014).
+pub(crate) fn create2_address(creator: H160, salt: H256, code: &[u8]) -> H160 {
+    let mut preimage = Vec::with_capacity(85);
+    preimage.push(0xff);
+    preimage.extend_from_slice(creator.as_bytes());
+    preimage.extend_from_slice(salt.as_bytes());
+    preimage.extend_from_slice(keccak256(code).as_bytes());
+
+    H160::from(keccak256(&preimage))
+}
+
+/// Convert a word to `usize`, `None` if it doesn't fit.
+fn to_usize(value: U256) -> Option<usize> {
+    if value > U256::from(usize::MAX) {
+        None
+    } else {
+        Some(value.as_usize())
+    }
+}
+
+/// Convert a word to `u64`, `None` if it doesn't fit.
+fn to_u64(value: U256) -> Option<u64> {
+    if value > U256::from(u64::MAX) {
+        None
+    } else {
+        Some(value.as_u64())
+    }
+}
+
+/// The address held in the low 20 bytes of a word.
+fn word_to_address(value: U256) -> H160 {
+    H160::from(H256::from_uint(&value))
+}
+
+fn address_to_word(address: H160) -> U256 {
+    U256::from_big_endian(address.as_bytes())
+}
+
+/// Fill `destination` from `source` starting at `offset`, zero padding past the end of `source`.
+pub(crate) fn copy_padded(destination: &mut [u8], source: &[u8], offset: U256) {
+    let start = to_usize(offset).unwrap_or(usize::MAX).min(source.len());
+    let end = start.saturating_add(destination.len()).min(source.len());
+    let length = end - start;
+
+    destination[..length].copy_from_slice(&source[start..end]);
+    destination[length..].fill(0);
+}
+
+/// Read the 32 byte word at `offset`, zero padding past the end of `bytes`.
+fn load_word(bytes: &[u8], offset: U256) -> U256 {
+    let mut word = [0u8; 32];
+    copy_padded(&mut word, bytes, offset);
+
+    U256::from(word)
+}
+
+/// `(a + b) % modulus` where the sum is not truncated to 256 bits.
+///
+/// Returns zero if the modulus is zero.
+fn add_mod(a: U256, b: U256, modulus: U256) -> U256 {
+    if modulus.is_zero() {
+        return U256::zero();
+    }
+
+    let sum = U512::from(a) + U512::from(b);
+    truncate(sum % U512::from(modulus))
+}
+
+/// `(a * b) % modulus` where the product is not truncated to 256 bits.
+///
+/// Returns zero if the modulus is zero.
+fn mul_mod(a: U256, b: U256, modulus: U256) -> U256 {
+    if modulus.is_zero() {
+        return U256::zero();
+    }
+
+    truncate(a.full_mul(b) % U512::from(modulus))
+}
+
+/// Keep the low 256 bits of a 512-bit value.
+fn truncate(value: U512) -> U256 {
+    let U512(ref words) = value;
+    U256([words[0], words[1], words[2], words[3]])
+}
+
+/// Maximum number of items on the stack.
+const STACK_LIMIT: usize = 1024;
+
+/// EVM Stack used for convenience.
+#[derive(Debug)]
+pub(crate) struct Stack {
+    /// Stack holding variables, function call arguments and return addressed.
+    ///
+    /// A stack of uint256
+    inner: Vec<U256>,
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        Stack::new()
+    }
+}
+
+impl Stack {
+    pub fn new() -> Self {
+        Stack { inner: Vec::new() }
+    }
+
+    pub fn push(&mut self, value: U256) -> Result<(), ErrorKind> {
+        if self.inner.len() >= STACK_LIMIT {
+            return Err(ErrorKind::StackOverflow);
+        }
+
+        self.inner.push(value);
+        Ok(())
+    }
+
+    /// Items from the bottom up.
+    pub fn as_slice(&self) -> &[U256] {
+        &self.inner
+    }
+
+    /// Remove every item, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    pub fn pop(&mut self) -> Result<U256, ErrorKind> {
+        let result = self.inner.pop();
+        match result {
+            None => Err(ErrorKind::StackUnderflow),
+            Some(result) => Ok(result),
+        }
+    }
+
+    pub fn peek(&self, offset_from_top: usize) -> Result<U256, ErrorKind> {
+        if offset_from_top >= self.inner.len() {
+            return Err(ErrorKind::StackUnderflow);
+        }
+
+        Ok(self.inner[self.inner.len() - offset_from_top - 1])
+    }
+
+    /// Swap the top of the stack with the item `offset_from_top` below it.
+    pub fn swap(&mut self, offset_from_top: usize) -> Result<(), ErrorKind> {
+        if offset_from_top >= self.inner.len() {
+            return Err(ErrorKind::StackUnderflow);
+        }
+
+        let top = self.inner.len() - 1;
+        self.inner.swap(top, top - offset_from_top);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{from_big, to_big, word};
+    use proptest::prelude::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn call_data_load_past_the_end_is_zero_padded() {
+        let data = [0xaa, 0xbb];
+        assert_eq!(load_word(&data, U256::zero()), U256::from(0xaabb) << 240);
+        assert_eq!(load_word(&data, U256::one()), U256::from(0xbb) << 248);
+        assert_eq!(load_word(&data, U256::from(2)), U256::zero());
+        assert_eq!(load_word(&data, U256::MAX), U256::zero());
+    }
+
+    #[test]
+    fn add_mod_near_max() {
+        let max = U256::MAX;
+        assert_eq!(add_mod(max, max, max), U256::zero());
+        assert_eq!(add_mod(max, U256::one(), max), U256::one());
+        assert_eq!(add_mod(max, max, max - U256::one()), U256::from(2));
+    }
+
+    #[test]
+    fn mul_mod_near_max() {
+        let max = U256::MAX;
+        assert_eq!(mul_mod(max, max, max), U256::zero());
+        assert_eq!(mul_mod(max, max, max - U256::one()), U256::one());
+        assert_eq!(mul_mod(max, max, U256::from(12)), U256::from(9));
+    }
+
+    #[test]
+    fn create_addresses() {
+        // Examples from EIP-1014 and the first contract created by an account.
+        let creator = H160::from_str("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0").unwrap();
+        assert_eq!(
+            create_address(creator, 0),
+            H160::from_str("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d").unwrap()
+        );
+
+        assert_eq!(
+            create2_address(H160::zero(), H256::zero(), &[0x00]),
+            H160::from_str("4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38").unwrap()
+        );
+
+        let creator = H160::from_str("deadbeef00000000000000000000000000000000").unwrap();
+        let salt =
+            H256::from_str("000000000000000000000000feed000000000000000000000000000000000000")
+                .unwrap();
+        assert_eq!(
+            create2_address(creator, salt, &[0x00]),
+            H160::from_str("d04116cdd17bebe565eb2422f2497e06cc1c9833").unwrap()
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn add_mod_matches_reference(a in word(), b in word(), modulus in word()) {
+            let expected = if modulus.is_zero() {
+                U256::zero()
+            } else {
+                from_big((to_big(a) + to_big(b)) % to_big(modulus))
+            };
+
+            prop_assert_eq!(add_mod(a, b, modulus), expected);
+        }
+
+        #[test]
+        fn mul_mod_matches_reference(a in word(), b in word(), modulus in word()) {
+            let expected = if modulus.is_zero() {
+                U256::zero()
+            } else {
+                from_big((to_big(a) * to_big(b)) % to_big(modulus))
+            };
+
+            prop_assert_eq!(mul_mod(a, b, modulus), expected);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use crate::env::Fork;
+use crate::error::ErrorKind;
+use crate::transaction::{Transaction, TxKind};
+
+pub const ZERO: u64 = 0;
+pub const JUMPDEST: u64 = 1;
+pub const BASE: u64 = 2;
+pub const VERY_LOW: u64 = 3;
+pub const LOW: u64 = 5;
+pub const MID: u64 = 8;
+pub const HIGH: u64 = 10;
+
+pub const EXP: u64 = 10;
+pub const EXP_BYTE: u64 = 50;
+pub const KECCAK256: u64 = 30;
+pub const KECCAK256_WORD: u64 = 6;
+pub const COPY_WORD: u64 = 3;
+pub const MEMORY: u64 = 3;
+pub const BLOCKHASH: u64 = 20;
+pub const LOG: u64 = 375;
+pub const LOG_TOPIC: u64 = 375;
+pub const LOG_DATA: u64 = 8;
+
+/// Accessing an account or storage slot already accessed in the transaction (EIP-2929).
+pub const WARM_ACCESS: u64 = 100;
+pub const COLD_ACCOUNT_ACCESS: u64 = 2600;
+pub const COLD_SLOAD: u64 = 2100;
+
+pub const SSTORE_SET: u64 = 20000;
+pub const SSTORE_RESET: u64 = 5000 - COLD_SLOAD;
+pub const SSTORE_CLEARS_REFUND: i64 = 4800;
+/// SSTORE fails unless more gas than the stipend is left (EIP-2200).
+pub const SSTORE_SENTRY: u64 = 2300;
+
+pub const CALL_VALUE: u64 = 9000;
+pub const CALL_STIPEND: u64 = 2300;
+pub const NEW_ACCOUNT: u64 = 25000;
+pub const CREATE: u64 = 32000;
+pub const CODE_DEPOSIT: u64 = 200;
+pub const INITCODE_WORD: u64 = 2;
+pub const SELFDESTRUCT: u64 = 5000;
+
+pub const TRANSACTION: u64 = 21000;
+pub const TRANSACTION_CREATE: u64 = 32000;
+pub const TRANSACTION_DATA_ZERO: u64 = 4;
+pub const TRANSACTION_DATA_NON_ZERO: u64 = 16;
+pub const ACCESS_LIST_ADDRESS: u64 = 2400;
+pub const ACCESS_LIST_STORAGE_KEY: u64 = 1900;
+/// Cost of an authorization, part of it refunded if the authority already exists (EIP-7702).
+pub const PER_EMPTY_ACCOUNT: u64 = 25000;
+pub const PER_AUTH_BASE: u64 = 12500;
+/// Minimum paid per token of calldata (EIP-7623).
+pub const TOTAL_COST_FLOOR_PER_TOKEN: u64 = 10;
+
+/// Share of the gas used that can be refunded at most (EIP-3529).
+pub const MAX_REFUND_QUOTIENT: u64 = 5;
+
+pub const BLOB_GAS_PER_BLOB: u64 = 131072;
+
+/// Largest code a contract can be deployed with (EIP-170).
+pub const MAX_CODE_SIZE: usize = 24576;
+/// Largest init code a contract can be created with (EIP-3860).
+pub const MAX_INITCODE_SIZE: usize = 2 * MAX_CODE_SIZE;
+
+/// Gas available to an execution and refunds accumulated by it.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct Gas {
+    remaining: u64,
+    refunded: i64,
+}
+
+impl Gas {
+    pub fn new(limit: u64) -> Gas {
+        Gas {
+            remaining: limit,
+            refunded: 0,
+        }
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Refunds accumulated so far, negative if more was taken back than refunded.
+    pub fn refunded(&self) -> i64 {
+        self.refunded
+    }
+
+    /// Take `cost` from the remaining gas, failing if there isn't enough.
+    pub fn charge(&mut self, cost: u64) -> Result<(), ErrorKind> {
+        self.remaining = self
+            .remaining
+            .checked_sub(cost)
+            .ok_or(ErrorKind::OutOfGas)?;
+        Ok(())
+    }
+
+    /// Give back gas that a call didn't use.
+    pub fn reimburse(&mut self, gas: u64) {
+        self.remaining += gas;
+    }
+
+    /// Use up all the remaining gas, as on an exceptional halt.
+    pub fn spend_all(&mut self) {
+        self.remaining = 0;
+    }
+
+    pub fn refund(&mut self, refund: i64) {
+        self.refunded += refund;
+    }
+}
+
+/// Number of 32 byte words needed to hold `length` bytes.
+pub fn words(length: usize) -> u64 {
+    (length as u64).div_ceil(32)
+}
+
+/// Total cost of memory of `words` words, quadratic so memory can't grow without bound.
+pub fn memory_cost(words: u64) -> u64 {
+    let words = words as u128;
+    let cost = words * MEMORY as u128 + words * words / 512;
+
+    cost.try_into().unwrap_or(u64::MAX)
+}
+
+/// Gas of the 63/64ths of `remaining` a call or create can be given at most (EIP-150).
+pub fn all_but_one_64th(remaining: u64) -> u64 {
+    remaining - remaining / 64
+}
+
+/// Number of calldata tokens, where a non-zero byte counts as four (EIP-7623).
+fn calldata_tokens(data: &[u8]) -> u64 {
+    let zeros = data.iter().filter(|byte| **byte == 0).count() as u64;
+    let non_zeros = data.len() as u64 - zeros;
+
+    zeros + non_zeros * 4
+}
+
+/// Gas charged before any code runs in `fork`.
+pub fn intrinsic_gas(transaction: &Transaction, fork: Fork) -> u64 {
+    let data = transaction.data();
+    let zeros = data.iter().filter(|byte| **byte == 0).count() as u64;
+    let non_zeros = data.len() as u64 - zeros;
+
+    let mut gas =
+        TRANSACTION + zeros * TRANSACTION_DATA_ZERO + non_zeros * TRANSACTION_DATA_NON_ZERO;
+
+    if transaction.to() == TxKind::Create {
+        gas += TRANSACTION_CREATE;
+
+        // Init code is paid per word from Shanghai (EIP-3860).
+        if fork >= Fork::Shanghai {
+            gas += words(data.len()) * INITCODE_WORD;
+        }
+    }
+
+    for item in transaction.access_list() {
+        gas += ACCESS_LIST_ADDRESS + item.storage_keys.len() as u64 * ACCESS_LIST_STORAGE_KEY;
+    }
+
+    gas + transaction.authorization_list().len() as u64 * PER_EMPTY_ACCOUNT
+}
+
+/// Least gas a transaction uses in `fork`, so calldata can't be cheaper than a floor from Prague
+/// (EIP-7623).
+pub fn floor_data_gas(transaction: &Transaction, fork: Fork) -> u64 {
+    if fork < Fork::Prague {
+        return 0;
+    }
+
+    TRANSACTION + calldata_tokens(transaction.data()) * TOTAL_COST_FLOOR_PER_TOKEN
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::LegacyTransaction;
+    use bytes::Bytes;
+    use ethereum_types::U256;
+
+    #[test]
+    fn memory_cost_grows_quadratically() {
+        assert_eq!(memory_cost(0), 0);
+        assert_eq!(memory_cost(1), 3);
+        assert_eq!(memory_cost(1024), 3 * 1024 + 2048);
+        assert_eq!(memory_cost(u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn out_of_gas_leaves_gas_untouched() {
+        let mut gas = Gas::new(10);
+
+        assert_eq!(gas.charge(11), Err(ErrorKind::OutOfGas));
+        assert_eq!(gas.remaining(), 10);
+        assert_eq!(gas.charge(10), Ok(()));
+        assert_eq!(gas.remaining(), 0);
+    }
+
+    #[test]
+    fn intrinsic_gas_of_creation() {
+        let transaction = Transaction::Legacy(LegacyTransaction {
+            chain_id: None,
+            nonce: 0,
+            gas_price: U256::one(),
+            gas_limit: 100_000,
+            to: TxKind::Create,
+            value: U256::zero(),
+            data: Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0x55]),
+        });
+
+        assert_eq!(
+            intrinsic_gas(&transaction, Fork::Prague),
+            21000 + 32000 + 2 * 4 + 3 * 16 + 2
+        );
+        assert_eq!(
+            floor_data_gas(&transaction, Fork::Prague),
+            21000 + (2 + 3 * 4) * 10
+        );
+
+        // Without the init code words (EIP-3860) or the floor (EIP-7623).
+        assert_eq!(
+            intrinsic_gas(&transaction, Fork::London),
+            21000 + 32000 + 2 * 4 + 3 * 16
+        );
+        assert_eq!(floor_data_gas(&transaction, Fork::London), 0);
+    }
+}
@@ -0,0 +1,28 @@
+use ethereum_types::H256;
+use tiny_keccak::{Hasher, Keccak};
+
+/// Keccak-256 of the empty string, the code hash of accounts without code.
+pub const KECCAK_EMPTY: H256 = H256([
+    0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+    0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+]);
+
+/// Keccak-256 hash of `bytes`.
+pub fn keccak256(bytes: &[u8]) -> H256 {
+    let mut hasher = Keccak::v256();
+    hasher.update(bytes);
+
+    let mut output = H256::zero();
+    hasher.finalize(output.as_bytes_mut());
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_hash() {
+        assert_eq!(keccak256(&[]), KECCAK_EMPTY);
+    }
+}
@@ -0,0 +1,295 @@
+use ethereum_types::U256;
+use std::cmp::Ordering;
+use std::ops::{Div, Rem};
+
+/// Signed 256-bit integer stored as two's complement.
+///
+/// The EVM has no signed type, the signed instructions interpret their unsigned word operands as
+/// two's complement. This wraps a word to give those instructions the semantics from the Yellow
+/// Paper.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash, Default)]
+pub struct I256(U256);
+
+impl I256 {
+    /// Zero.
+    pub const ZERO: I256 = I256(U256([0, 0, 0, 0]));
+
+    /// Negative one, all bits set.
+    pub const MINUS_ONE: I256 = I256(U256([u64::MAX, u64::MAX, u64::MAX, u64::MAX]));
+
+    /// Smallest representable value, -2^255.
+    pub const MIN: I256 = I256(U256([0, 0, 0, 1 << 63]));
+
+    /// Largest representable value, 2^255 - 1.
+    pub const MAX: I256 = I256(U256([u64::MAX, u64::MAX, u64::MAX, u64::MAX >> 1]));
+
+    /// Interpret a word as two's complement.
+    pub fn from_raw(value: U256) -> I256 {
+        I256(value)
+    }
+
+    /// The underlying two's complement word.
+    pub fn into_raw(self) -> U256 {
+        self.0
+    }
+
+    /// True if the sign bit is set.
+    pub fn is_negative(&self) -> bool {
+        self.0.bit(255)
+    }
+
+    /// True if this is zero.
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    /// Absolute value as an unsigned word.
+    ///
+    /// This never overflows, the absolute value of `MIN` is 2^255.
+    pub fn unsigned_abs(&self) -> U256 {
+        if self.is_negative() {
+            twos_complement(self.0)
+        } else {
+            self.0
+        }
+    }
+
+    /// Build a value from its magnitude and sign, wrapping on overflow.
+    fn from_magnitude(magnitude: U256, negative: bool) -> I256 {
+        if negative {
+            I256(twos_complement(magnitude))
+        } else {
+            I256(magnitude)
+        }
+    }
+
+    /// Arithmetic shift right, rounding towards negative infinity (`SAR`).
+    ///
+    /// Shifting by 256 or more gives zero for non-negative values and -1 for negative values.
+    pub fn sar(self, shift: U256) -> I256 {
+        if shift >= U256::from(256) {
+            return if self.is_negative() {
+                I256::MINUS_ONE
+            } else {
+                I256::ZERO
+            };
+        }
+
+        let shift = shift.as_usize();
+        if self.is_negative() {
+            I256(!(!self.0 >> shift))
+        } else {
+            I256(self.0 >> shift)
+        }
+    }
+
+    /// Extend the sign of the `(byte_index + 1)` low bytes of `value` to the full word
+    /// (`SIGNEXTEND`).
+    ///
+    /// Byte indices of 31 or more leave the value unchanged.
+    pub fn sign_extend(byte_index: U256, value: U256) -> I256 {
+        if byte_index >= U256::from(31) {
+            return I256(value);
+        }
+
+        let sign_bit = byte_index.as_usize() * 8 + 7;
+        let mask = (U256::one() << (sign_bit + 1)) - U256::one();
+
+        I256(if value.bit(sign_bit) {
+            value | !mask
+        } else {
+            value & mask
+        })
+    }
+}
+
+impl Div for I256 {
+    type Output = I256;
+
+    /// Signed division rounding towards zero (`SDIV`).
+    ///
+    /// Division by zero gives zero and `MIN / -1` overflows back to `MIN`.
+    fn div(self, divisor: I256) -> I256 {
+        if divisor.is_zero() {
+            return I256::ZERO;
+        }
+
+        if self == I256::MIN && divisor == I256::MINUS_ONE {
+            return I256::MIN;
+        }
+
+        let quotient = self.unsigned_abs() / divisor.unsigned_abs();
+        I256::from_magnitude(quotient, self.is_negative() ^ divisor.is_negative())
+    }
+}
+
+impl Rem for I256 {
+    type Output = I256;
+
+    /// Signed remainder whose sign follows the dividend (`SMOD`).
+    ///
+    /// A zero modulus gives zero.
+    fn rem(self, modulus: I256) -> I256 {
+        if modulus.is_zero() {
+            return I256::ZERO;
+        }
+
+        let remainder = self.unsigned_abs() % modulus.unsigned_abs();
+        I256::from_magnitude(remainder, self.is_negative())
+    }
+}
+
+impl From<U256> for I256 {
+    fn from(value: U256) -> I256 {
+        I256::from_raw(value)
+    }
+}
+
+impl From<I256> for U256 {
+    fn from(value: I256) -> U256 {
+        value.into_raw()
+    }
+}
+
+impl Ord for I256 {
+    fn cmp(&self, other: &I256) -> Ordering {
+        // Flipping the sign bit maps -2^255..2^255 onto 0..2^256 preserving order.
+        let sign = U256::one() << 255;
+        (self.0 ^ sign).cmp(&(other.0 ^ sign))
+    }
+}
+
+impl PartialOrd for I256 {
+    fn partial_cmp(&self, other: &I256) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Negate a two's complement word.
+fn twos_complement(value: U256) -> U256 {
+    (!value).overflowing_add(U256::one()).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers;
+    use num_bigint::{BigInt, Sign};
+    use proptest::prelude::*;
+
+    fn modulus() -> BigInt {
+        BigInt::from(1u8) << 256u32
+    }
+
+    fn to_big(value: I256) -> BigInt {
+        let unsigned = BigInt::from(test_helpers::to_big(value.into_raw()));
+
+        if value.is_negative() {
+            unsigned - modulus()
+        } else {
+            unsigned
+        }
+    }
+
+    /// Wrap an arbitrary integer into two's complement.
+    fn from_big(value: BigInt) -> I256 {
+        let wrapped = ((value % modulus()) + modulus()) % modulus();
+        I256::from_raw(test_helpers::from_big(
+            wrapped.to_biguint().expect("wrapped value is not negative"),
+        ))
+    }
+
+    /// Values biased towards zero, the extremes and small magnitudes of either sign.
+    fn signed() -> impl Strategy<Value = I256> {
+        prop_oneof![
+            any::<[u64; 4]>().prop_map(|words| I256::from_raw(U256(words))),
+            any::<i64>().prop_map(|value| from_big(BigInt::from(value))),
+            Just(I256::MIN),
+            Just(I256::MAX),
+            Just(I256::MINUS_ONE),
+            Just(I256::ZERO),
+        ]
+    }
+
+    #[test]
+    fn min_divided_by_minus_one_overflows() {
+        assert_eq!(I256::MIN / I256::MINUS_ONE, I256::MIN);
+        assert_eq!(I256::MIN % I256::MINUS_ONE, I256::ZERO);
+    }
+
+    #[test]
+    fn constants() {
+        assert_eq!(to_big(I256::MIN), -(BigInt::from(1u8) << 255u32));
+        assert_eq!(to_big(I256::MAX), (BigInt::from(1u8) << 255u32) - 1);
+        assert_eq!(to_big(I256::MINUS_ONE), BigInt::from(-1i8));
+        assert!(I256::MIN < I256::MINUS_ONE);
+        assert!(I256::MINUS_ONE < I256::ZERO);
+        assert!(I256::ZERO < I256::MAX);
+    }
+
+    proptest! {
+        #[test]
+        fn div_matches_reference(a in signed(), b in signed()) {
+            let expected = if b.is_zero() {
+                I256::ZERO
+            } else {
+                // BigInt division truncates towards zero like SDIV.
+                from_big(to_big(a) / to_big(b))
+            };
+
+            prop_assert_eq!(a / b, expected);
+        }
+
+        #[test]
+        fn rem_matches_reference(a in signed(), b in signed()) {
+            let expected = if b.is_zero() {
+                I256::ZERO
+            } else {
+                // BigInt remainder takes the sign of the dividend like SMOD.
+                from_big(to_big(a) % to_big(b))
+            };
+
+            prop_assert_eq!(a % b, expected);
+        }
+
+        #[test]
+        fn ordering_matches_reference(a in signed(), b in signed()) {
+            prop_assert_eq!(a.cmp(&b), to_big(a).cmp(&to_big(b)));
+        }
+
+        #[test]
+        fn sar_matches_reference(value in signed(), shift in 0u32..300) {
+            let divisor = BigInt::from(1u8) << shift;
+            let big = to_big(value);
+
+            // Floor division, rounding negative values away from zero.
+            let mut expected = &big / &divisor;
+            if big.sign() == Sign::Minus && expected.clone() * &divisor != big {
+                expected -= 1;
+            }
+
+            prop_assert_eq!(value.sar(U256::from(shift)), from_big(expected));
+        }
+
+        #[test]
+        fn sign_extend_matches_reference(value in any::<[u64; 4]>(), byte_index in 0u32..40) {
+            let value = U256(value);
+
+            let expected = if byte_index >= 31 {
+                I256::from_raw(value)
+            } else {
+                let bits = (byte_index + 1) * 8;
+                let low = to_big(I256::from_raw(value)) & ((BigInt::from(1u8) << bits) - 1);
+                let sign_bit = BigInt::from(1u8) << (bits - 1);
+
+                from_big(if &low & &sign_bit == BigInt::from(0u8) {
+                    low
+                } else {
+                    low - (BigInt::from(1u8) << bits)
+                })
+            };
+
+            prop_assert_eq!(I256::sign_extend(U256::from(byte_index), value), expected);
+        }
+    }
+}
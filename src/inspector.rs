@@ -0,0 +1,392 @@
+use crate::database::Database;
+use crate::env::Env;
+use crate::error::ErrorKind;
+use crate::journal::{JournaledState, Log};
+use crate::message::Message;
+use crate::result::{ExecutionResult, ExecutionStatus};
+use crate::transaction::Transaction;
+use ethereum_types::{H160, H256, U256};
+
+/// Frame an instruction executes in, as seen by an inspector.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameView<'a> {
+    /// Number of frames running, one for the outermost.
+    pub depth: usize,
+
+    /// Message the frame runs.
+    pub message: &'a Message,
+
+    /// Program counter into the code in bytes.
+    pub pc: usize,
+
+    /// Instruction at the program counter when the step started.
+    pub opcode: u8,
+
+    /// Stack, from the bottom up.
+    pub stack: &'a [U256],
+
+    pub memory: &'a [u8],
+
+    /// Gas left in the frame.
+    pub gas: u64,
+
+    /// Gas refunded so far by the frame, negative if it undid refunds of its callers.
+    pub refund: i64,
+
+    /// Output of the last call or create made by the frame.
+    pub return_data: &'a [u8],
+}
+
+/// How a call or creation ended.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct CallOutcome<'a> {
+    pub status: ExecutionStatus,
+
+    /// Output of `RETURN` or `REVERT`, the code deployed by a creation.
+    pub output: &'a [u8],
+
+    /// Gas used out of the gas the message was given.
+    pub gas_used: u64,
+}
+
+/// Hooks called as the VM executes, to trace or debug the execution.
+///
+/// Every hook does nothing by default. The VM takes its inspector as a type parameter, so the hooks
+/// compile away when there's nothing to inspect.
+pub trait Inspector<DB: Database> {
+    /// A valid transaction from `sender` is about to be executed in `env`, nothing has been paid
+    /// for it yet.
+    fn transaction(
+        &mut self,
+        _env: &Env,
+        _sender: H160,
+        _transaction: &Transaction,
+        _state: &JournaledState<DB>,
+    ) {
+    }
+
+    /// A transaction ended, fees paid and emptied accounts deleted.
+    fn transaction_end(&mut self, _result: &ExecutionResult, _state: &JournaledState<DB>) {}
+
+    /// Before the instruction at the program counter of `frame` executes.
+    fn step(&mut self, _frame: &FrameView<'_>, _state: &JournaledState<DB>) {}
+
+    /// After an instruction executed, with the frame as it left it and the error it halted with
+    /// if it failed.
+    ///
+    /// An instruction that calls or creates ends before the callee's first step.
+    fn step_end(
+        &mut self,
+        _frame: &FrameView<'_>,
+        _error: Option<ErrorKind>,
+        _state: &JournaledState<DB>,
+    ) {
+    }
+
+    /// A call, including the outermost one, is about to run.
+    fn call(&mut self, _message: &Message, _state: &JournaledState<DB>) {}
+
+    /// A call ended.
+    fn call_end(
+        &mut self,
+        _message: &Message,
+        _outcome: &CallOutcome<'_>,
+        _state: &JournaledState<DB>,
+    ) {
+    }
+
+    /// A creation, including the outermost one, is about to run its init code.
+    fn create(&mut self, _message: &Message, _state: &JournaledState<DB>) {}
+
+    /// A creation ended.
+    fn create_end(
+        &mut self,
+        _message: &Message,
+        _outcome: &CallOutcome<'_>,
+        _state: &JournaledState<DB>,
+    ) {
+    }
+
+    /// A log was emitted. It's dropped again if the frame emitting it fails.
+    fn log(&mut self, _log: &Log) {}
+
+    /// `SLOAD` read `value` from the storage slot `key` of the account at `address`.
+    fn storage_read(&mut self, _address: H160, _key: H256, _value: H256) {}
+
+    /// `SSTORE` changed the storage slot `key` of the account at `address` from `previous` to
+    /// `value`.
+    fn storage_write(&mut self, _address: H160, _key: H256, _previous: H256, _value: H256) {}
+
+    /// The contract at `address` self-destructed, sending its balance of `value` to
+    /// `beneficiary`.
+    fn selfdestruct(&mut self, _address: H160, _beneficiary: H160, _value: U256) {}
+}
+
+/// Inspector that does nothing, the default of the VM.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub struct NoopInspector;
+
+impl<DB: Database> Inspector<DB> for NoopInspector {}
+
+impl<DB: Database, I: Inspector<DB> + ?Sized> Inspector<DB> for &mut I {
+    fn transaction(
+        &mut self,
+        env: &Env,
+        sender: H160,
+        transaction: &Transaction,
+        state: &JournaledState<DB>,
+    ) {
+        (**self).transaction(env, sender, transaction, state)
+    }
+
+    fn transaction_end(&mut self, result: &ExecutionResult, state: &JournaledState<DB>) {
+        (**self).transaction_end(result, state)
+    }
+
+    fn step(&mut self, frame: &FrameView<'_>, state: &JournaledState<DB>) {
+        (**self).step(frame, state)
+    }
+
+    fn step_end(
+        &mut self,
+        frame: &FrameView<'_>,
+        error: Option<ErrorKind>,
+        state: &JournaledState<DB>,
+    ) {
+        (**self).step_end(frame, error, state)
+    }
+
+    fn call(&mut self, message: &Message, state: &JournaledState<DB>) {
+        (**self).call(message, state)
+    }
+
+    fn call_end(
+        &mut self,
+        message: &Message,
+        outcome: &CallOutcome<'_>,
+        state: &JournaledState<DB>,
+    ) {
+        (**self).call_end(message, outcome, state)
+    }
+
+    fn create(&mut self, message: &Message, state: &JournaledState<DB>) {
+        (**self).create(message, state)
+    }
+
+    fn create_end(
+        &mut self,
+        message: &Message,
+        outcome: &CallOutcome<'_>,
+        state: &JournaledState<DB>,
+    ) {
+        (**self).create_end(message, outcome, state)
+    }
+
+    fn log(&mut self, log: &Log) {
+        (**self).log(log)
+    }
+
+    fn storage_read(&mut self, address: H160, key: H256, value: H256) {
+        (**self).storage_read(address, key, value)
+    }
+
+    fn storage_write(&mut self, address: H160, key: H256, previous: H256, value: H256) {
+        (**self).storage_write(address, key, previous, value)
+    }
+
+    fn selfdestruct(&mut self, address: H160, beneficiary: H160, value: U256) {
+        (**self).selfdestruct(address, beneficiary, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Account, InMemoryDatabase};
+    use crate::result::SuccessReason;
+    use crate::vm::Vm;
+    use bytes::Bytes;
+
+    #[derive(Eq, PartialEq, Debug)]
+    enum Event {
+        Step(usize, u8),
+        StepEnd(usize, u64),
+        Call(H160),
+        CallEnd(H160, ExecutionStatus, u64),
+        Log(Log),
+        StorageRead(H256, H256),
+        StorageWrite(H256, H256, H256),
+        SelfDestruct(H160, H160, U256),
+    }
+
+    /// Inspector recording what it's told.
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<Event>,
+    }
+
+    impl<DB: Database> Inspector<DB> for Recorder {
+        fn step(&mut self, frame: &FrameView<'_>, _state: &JournaledState<DB>) {
+            self.events.push(Event::Step(frame.depth, frame.opcode));
+        }
+
+        fn step_end(
+            &mut self,
+            frame: &FrameView<'_>,
+            _error: Option<ErrorKind>,
+            _state: &JournaledState<DB>,
+        ) {
+            self.events.push(Event::StepEnd(frame.depth, frame.gas));
+        }
+
+        fn call(&mut self, message: &Message, _state: &JournaledState<DB>) {
+            self.events.push(Event::Call(message.address));
+        }
+
+        fn call_end(
+            &mut self,
+            message: &Message,
+            outcome: &CallOutcome<'_>,
+            _state: &JournaledState<DB>,
+        ) {
+            self.events.push(Event::CallEnd(
+                message.address,
+                outcome.status,
+                outcome.gas_used,
+            ));
+        }
+
+        fn log(&mut self, log: &Log) {
+            self.events.push(Event::Log(log.clone()));
+        }
+
+        fn storage_read(&mut self, _address: H160, key: H256, value: H256) {
+            self.events.push(Event::StorageRead(key, value));
+        }
+
+        fn storage_write(&mut self, _address: H160, key: H256, previous: H256, value: H256) {
+            self.events.push(Event::StorageWrite(key, previous, value));
+        }
+
+        fn selfdestruct(&mut self, address: H160, beneficiary: H160, value: U256) {
+            self.events
+                .push(Event::SelfDestruct(address, beneficiary, value));
+        }
+    }
+
+    #[test]
+    fn inspector_sees_storage_and_logs() {
+        // PUSH1 0x00 SLOAD PUSH1 0x01 ADD DUP1 PUSH1 0x00 SSTORE PUSH1 0x00 PUSH1 0x00 LOG1
+        let code = [
+            0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x80, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00,
+            0xa1,
+        ];
+        let mut recorder = Recorder::default();
+
+        let result = Vm::new(InMemoryDatabase::new())
+            .with_inspector(&mut recorder)
+            .exec(Message {
+                code: Bytes::copy_from_slice(&code),
+                gas: 100_000,
+                ..Message::default()
+            });
+        assert!(result.is_success());
+
+        let events: Vec<_> = recorder
+            .events
+            .into_iter()
+            .filter(|event| !matches!(event, Event::Step(..) | Event::StepEnd(..)))
+            .collect();
+        let one = H256::from_low_u64_be(1);
+        assert_eq!(
+            events,
+            vec![
+                Event::Call(H160::zero()),
+                Event::StorageRead(H256::zero(), H256::zero()),
+                Event::StorageWrite(H256::zero(), H256::zero(), one),
+                Event::Log(Log {
+                    address: H160::zero(),
+                    topics: vec![one],
+                    data: Bytes::new(),
+                }),
+                Event::CallEnd(
+                    H160::zero(),
+                    ExecutionStatus::Success(SuccessReason::EndOfCode),
+                    result.gas_used
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn inspector_steps_into_calls() {
+        let callee = H160::from_low_u64_be(0xca11);
+        let beneficiary = H160::from_low_u64_be(0xbeef);
+        let mut db = InMemoryDatabase::new();
+        // PUSH2 0xbeef SELFDESTRUCT
+        db.insert_account(
+            callee,
+            Account::new(
+                U256::from(7),
+                0,
+                Bytes::from_static(&[0x61, 0xbe, 0xef, 0xff]),
+            ),
+        );
+
+        // PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH2 0xca11 GAS CALL
+        let code = [
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x61, 0xca, 0x11, 0x5a,
+            0xf1,
+        ];
+        let mut recorder = Recorder::default();
+        let result = Vm::new(&mut db)
+            .with_inspector(&mut recorder)
+            .exec(Message {
+                code: Bytes::copy_from_slice(&code),
+                gas: 100_000,
+                ..Message::default()
+            });
+        assert!(result.is_success());
+
+        let steps: Vec<_> = recorder
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Step(depth, opcode) => Some((*depth, *opcode)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (1, 0x60),
+                (1, 0x60),
+                (1, 0x60),
+                (1, 0x60),
+                (1, 0x60),
+                (1, 0x61),
+                (1, 0x5a),
+                (1, 0xf1),
+                (2, 0x61),
+                (2, 0xff),
+                (1, 0x00),
+            ]
+        );
+
+        let position = |wanted: &Event| {
+            recorder
+                .events
+                .iter()
+                .position(|event| event == wanted)
+                .unwrap()
+        };
+        let call = position(&Event::Call(callee));
+        let selfdestruct = position(&Event::SelfDestruct(callee, beneficiary, U256::from(7)));
+        let call_end = recorder
+            .events
+            .iter()
+            .position(|event| matches!(event, Event::CallEnd(address, ..) if *address == callee))
+            .unwrap();
+        assert!(call < selfdestruct && selfdestruct < call_end);
+    }
+}
@@ -1,4 +1,8 @@
+use crate::env::Fork;
+use crate::gas;
 use num_enum::TryFromPrimitive;
+use std::fmt;
+use std::str::FromStr;
 
 /// Virtual machine instructions.
 #[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Debug, Hash, TryFromPrimitive)]
@@ -58,12 +62,68 @@ pub enum Instruction {
     /// Arithmetic (signed) shift right
     Sar = 0x1d,
 
+    /// Keccak-256 hash of memory.
+    Keccak256 = 0x20,
+
+    /// Address of the executing account.
+    Address = 0x30,
+    /// Balance of an account.
+    Balance = 0x31,
+    /// Sender of the transaction.
+    Origin = 0x32,
+    /// Account that called the executing account.
+    Caller = 0x33,
+    /// Value sent with the call.
+    CallValue = 0x34,
+
     /// Load the calldata.
     CallDataLoad = 0x35,
 
     /// Get calldata size.
     CallDataSize = 0x36,
 
+    /// Copy calldata to memory.
+    CallDataCopy = 0x37,
+    /// Size of the executing code.
+    CodeSize = 0x38,
+    /// Copy the executing code to memory.
+    CodeCopy = 0x39,
+    /// Gas price of the transaction.
+    GasPrice = 0x3a,
+    /// Size of the code of an account.
+    ExtCodeSize = 0x3b,
+    /// Copy the code of an account to memory.
+    ExtCodeCopy = 0x3c,
+    /// Size of the output of the last call.
+    ReturnDataSize = 0x3d,
+    /// Copy the output of the last call to memory.
+    ReturnDataCopy = 0x3e,
+    /// Code hash of an account.
+    ExtCodeHash = 0x3f,
+
+    /// Hash of one of the 256 most recent blocks.
+    BlockHash = 0x40,
+    /// Beneficiary of the block.
+    Coinbase = 0x41,
+    /// Timestamp of the block.
+    Timestamp = 0x42,
+    /// Number of the block.
+    Number = 0x43,
+    /// Randomness from the beacon chain, the difficulty before the merge.
+    PrevRandao = 0x44,
+    /// Gas limit of the block.
+    GasLimit = 0x45,
+    /// Chain id.
+    ChainId = 0x46,
+    /// Balance of the executing account.
+    SelfBalance = 0x47,
+    /// Base fee of the block.
+    BaseFee = 0x48,
+    /// Versioned hash of a blob of the transaction.
+    BlobHash = 0x49,
+    /// Blob base fee of the block.
+    BlobBaseFee = 0x4a,
+
     /// Pop from the stack.
     Pop = 0x50,
     /// Load from memory
@@ -82,10 +142,23 @@ pub enum Instruction {
     JumpI = 0x57,
     /// Program counter
     PC = 0x58,
+    /// Size of memory in bytes.
+    MSize = 0x59,
+    /// Gas left.
+    Gas = 0x5a,
 
     /// Metadata just to indicate jump destination. No-op.
     JumpDest = 0x5b,
 
+    /// Load from transient storage.
+    TLoad = 0x5c,
+    /// Save word to transient storage.
+    TStore = 0x5d,
+    /// Copy memory to memory.
+    MCopy = 0x5e,
+
+    /// Push zero on the stack
+    Push0 = 0x5f,
     /// Push n bytes on the stack
     Push1 = 0x60,
     Push2 = 0x61,
@@ -156,8 +229,34 @@ pub enum Instruction {
     Swap15 = 0x9e,
     Swap16 = 0x9f,
 
+    /// Emit a log with n topics.
+    Log0 = 0xa0,
+    Log1 = 0xa1,
+    Log2 = 0xa2,
+    Log3 = 0xa3,
+    Log4 = 0xa4,
+
+    /// Create a contract.
+    Create = 0xf0,
+    /// Call an account.
+    Call = 0xf1,
+    /// Run the code of an account as the executing account.
+    CallCode = 0xf2,
     /// Return
     Return = 0xf3,
+    /// Run the code of an account as the executing account with the same caller and value.
+    DelegateCall = 0xf4,
+    /// Create a contract at an address derived from a salt.
+    Create2 = 0xf5,
+    /// Call an account without allowing state changes.
+    StaticCall = 0xfa,
+    /// Halt, reverting state changes and returning data.
+    Revert = 0xfd,
+    /// Designated invalid instruction.
+    Invalid = 0xfe,
+    /// Send the balance to an account and delete the executing account if created in the same
+    /// transaction.
+    SelfDestruct = 0xff,
 }
 
 use Instruction::*;
@@ -188,6 +287,16 @@ impl Instruction {
         }
     }
 
+    /// Returns number of topics of `LOGN` instruction
+    /// Log0 -> 0
+    pub fn log_topics(&self) -> Option<usize> {
+        if *self >= Log0 && *self <= Log4 {
+            Some(((*self as u8) - (Log0 as u8)) as usize)
+        } else {
+            None
+        }
+    }
+
     /// Returns stack position of item to SWAP top with
     /// SWAP0 -> 0
     pub fn dup_position(&self) -> Option<usize> {
@@ -198,3 +307,476 @@ impl Instruction {
         }
     }
 }
+
+impl Instruction {
+    /// Metadata of the instruction from `INSTRUCTION_TABLE`.
+    pub fn info(&self) -> &'static InstructionInfo {
+        match &INSTRUCTION_TABLE[*self as usize] {
+            Some(info) => info,
+            None => unreachable!("{:?} is missing from the instruction table", self),
+        }
+    }
+
+    /// Gas charged by the instruction in `fork` before any dynamic costs.
+    ///
+    /// Forks without a variant of their own are folded into the next one, so the repricing of
+    /// Tangerine Whistle (EIP-150) shows from Byzantium on.
+    pub fn base_gas(&self, fork: Fork) -> u64 {
+        let eip150 = fork >= Fork::Byzantium;
+        let eip1884 = fork >= Fork::Istanbul;
+        // Berlin charges accesses by whether they're warm or cold (EIP-2929), all at run time.
+        let eip2929 = fork >= Fork::Berlin;
+
+        match self {
+            Balance if !eip2929 => match (eip150, eip1884) {
+                (_, true) => 700,
+                (true, false) => 400,
+                (false, false) => 20,
+            },
+            ExtCodeSize | ExtCodeCopy if !eip2929 => {
+                if eip150 {
+                    700
+                } else {
+                    20
+                }
+            }
+            ExtCodeHash if !eip2929 => {
+                if eip1884 {
+                    700
+                } else {
+                    400
+                }
+            }
+            SLoad if !eip2929 => match (eip150, eip1884) {
+                (_, true) => 800,
+                (true, false) => 200,
+                (false, false) => 50,
+            },
+            Call | CallCode | DelegateCall | StaticCall if !eip2929 => {
+                if eip150 {
+                    700
+                } else {
+                    40
+                }
+            }
+            SelfDestruct if !eip150 => gas::ZERO,
+            _ => self.info().base_gas,
+        }
+    }
+
+    /// True if the instruction exists in `fork`.
+    pub fn is_available(&self, fork: Fork) -> bool {
+        self.info().fork <= fork
+    }
+}
+
+impl fmt::Display for Instruction {
+    /// Format as the mnemonic, like `PUSH1`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.info().name)
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = ParseInstructionError;
+
+    /// Parse a mnemonic, whatever its case.
+    fn from_str(name: &str) -> Result<Instruction, ParseInstructionError> {
+        INSTRUCTION_TABLE
+            .iter()
+            .position(|info| info.is_some_and(|info| info.name.eq_ignore_ascii_case(name)))
+            .and_then(|opcode| Instruction::try_from(opcode as u8).ok())
+            .ok_or(ParseInstructionError)
+    }
+}
+
+/// Error parsing a string that isn't the mnemonic of an instruction.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+pub struct ParseInstructionError;
+
+impl fmt::Display for ParseInstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unknown instruction")
+    }
+}
+
+impl std::error::Error for ParseInstructionError {}
+
+/// What an instruction takes and does, whatever it runs with.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+pub struct InstructionInfo {
+    /// Mnemonic, like `PUSH1`.
+    pub name: &'static str,
+
+    /// Items popped off the stack.
+    pub inputs: usize,
+
+    /// Items pushed on the stack.
+    pub outputs: usize,
+
+    /// Bytes following the opcode in the code, the value of a `PUSH`.
+    pub immediate_size: usize,
+
+    /// Gas charged before any dynamic costs in the latest fork, see `Instruction::base_gas` for
+    /// earlier ones.
+    pub base_gas: u64,
+
+    /// Fork the instruction was introduced in.
+    pub fork: Fork,
+
+    /// Whether the instruction halts the frame.
+    pub terminating: bool,
+
+    /// Whether the instruction can move the program counter elsewhere than the next instruction.
+    pub jump: bool,
+
+    /// Whether the instruction changes the state, so fails in a static call.
+    pub modifies_state: bool,
+}
+
+impl InstructionInfo {
+    const fn new(name: &'static str, inputs: usize, outputs: usize, base_gas: u64) -> Self {
+        InstructionInfo {
+            name,
+            inputs,
+            outputs,
+            immediate_size: 0,
+            base_gas,
+            fork: Fork::Frontier,
+            terminating: false,
+            jump: false,
+            modifies_state: false,
+        }
+    }
+
+    const fn since(mut self, fork: Fork) -> Self {
+        self.fork = fork;
+        self
+    }
+
+    const fn halts(mut self) -> Self {
+        self.terminating = true;
+        self
+    }
+
+    const fn jumps(mut self) -> Self {
+        self.jump = true;
+        self
+    }
+
+    const fn writes(mut self) -> Self {
+        self.modifies_state = true;
+        self
+    }
+}
+
+/// Metadata of every instruction by opcode, `None` for the opcodes without an instruction.
+pub static INSTRUCTION_TABLE: [Option<InstructionInfo>; 256] = instruction_table();
+
+const fn instruction_table() -> [Option<InstructionInfo>; 256] {
+    use gas::*;
+
+    const PUSH: [&str; 32] = [
+        "PUSH1", "PUSH2", "PUSH3", "PUSH4", "PUSH5", "PUSH6", "PUSH7", "PUSH8", "PUSH9", "PUSH10",
+        "PUSH11", "PUSH12", "PUSH13", "PUSH14", "PUSH15", "PUSH16", "PUSH17", "PUSH18", "PUSH19",
+        "PUSH20", "PUSH21", "PUSH22", "PUSH23", "PUSH24", "PUSH25", "PUSH26", "PUSH27", "PUSH28",
+        "PUSH29", "PUSH30", "PUSH31", "PUSH32",
+    ];
+    const DUP: [&str; 16] = [
+        "DUP1", "DUP2", "DUP3", "DUP4", "DUP5", "DUP6", "DUP7", "DUP8", "DUP9", "DUP10", "DUP11",
+        "DUP12", "DUP13", "DUP14", "DUP15", "DUP16",
+    ];
+    const SWAP: [&str; 16] = [
+        "SWAP1", "SWAP2", "SWAP3", "SWAP4", "SWAP5", "SWAP6", "SWAP7", "SWAP8", "SWAP9", "SWAP10",
+        "SWAP11", "SWAP12", "SWAP13", "SWAP14", "SWAP15", "SWAP16",
+    ];
+    const LOG: [&str; 5] = ["LOG0", "LOG1", "LOG2", "LOG3", "LOG4"];
+
+    let mut table = [None; 256];
+    macro_rules! set {
+        ($instruction:ident, $info:expr) => {
+            table[$instruction as usize] = Some($info);
+        };
+    }
+
+    set!(Stop, InstructionInfo::new("STOP", 0, 0, ZERO).halts());
+    set!(Add, InstructionInfo::new("ADD", 2, 1, VERY_LOW));
+    set!(Mul, InstructionInfo::new("MUL", 2, 1, LOW));
+    set!(Sub, InstructionInfo::new("SUB", 2, 1, VERY_LOW));
+    set!(Div, InstructionInfo::new("DIV", 2, 1, LOW));
+    set!(SDiv, InstructionInfo::new("SDIV", 2, 1, LOW));
+    set!(Mod, InstructionInfo::new("MOD", 2, 1, LOW));
+    set!(SMod, InstructionInfo::new("SMOD", 2, 1, LOW));
+    set!(AddMod, InstructionInfo::new("ADDMOD", 3, 1, MID));
+    set!(MulMod, InstructionInfo::new("MULMOD", 3, 1, MID));
+    set!(Exp, InstructionInfo::new("EXP", 2, 1, gas::EXP));
+    set!(SignExtend, InstructionInfo::new("SIGNEXTEND", 2, 1, LOW));
+
+    set!(Lt, InstructionInfo::new("LT", 2, 1, VERY_LOW));
+    set!(Gt, InstructionInfo::new("GT", 2, 1, VERY_LOW));
+    set!(Slt, InstructionInfo::new("SLT", 2, 1, VERY_LOW));
+    set!(Sgt, InstructionInfo::new("SGT", 2, 1, VERY_LOW));
+    set!(Eq, InstructionInfo::new("EQ", 2, 1, VERY_LOW));
+    set!(IsZero, InstructionInfo::new("ISZERO", 1, 1, VERY_LOW));
+    set!(And, InstructionInfo::new("AND", 2, 1, VERY_LOW));
+    set!(Or, InstructionInfo::new("OR", 2, 1, VERY_LOW));
+    set!(Xor, InstructionInfo::new("XOR", 2, 1, VERY_LOW));
+    set!(Not, InstructionInfo::new("NOT", 1, 1, VERY_LOW));
+    set!(Byte, InstructionInfo::new("BYTE", 2, 1, VERY_LOW));
+    set!(
+        Shl,
+        InstructionInfo::new("SHL", 2, 1, VERY_LOW).since(Fork::Constantinople)
+    );
+    set!(
+        Shr,
+        InstructionInfo::new("SHR", 2, 1, VERY_LOW).since(Fork::Constantinople)
+    );
+    set!(
+        Sar,
+        InstructionInfo::new("SAR", 2, 1, VERY_LOW).since(Fork::Constantinople)
+    );
+
+    set!(
+        Keccak256,
+        InstructionInfo::new("KECCAK256", 2, 1, gas::KECCAK256)
+    );
+
+    set!(Address, InstructionInfo::new("ADDRESS", 0, 1, BASE));
+    // Accounts and storage slots are charged in full once it's known whether they're warm.
+    set!(Balance, InstructionInfo::new("BALANCE", 1, 1, ZERO));
+    set!(Origin, InstructionInfo::new("ORIGIN", 0, 1, BASE));
+    set!(Caller, InstructionInfo::new("CALLER", 0, 1, BASE));
+    set!(CallValue, InstructionInfo::new("CALLVALUE", 0, 1, BASE));
+    set!(
+        CallDataLoad,
+        InstructionInfo::new("CALLDATALOAD", 1, 1, VERY_LOW)
+    );
+    set!(
+        CallDataSize,
+        InstructionInfo::new("CALLDATASIZE", 0, 1, BASE)
+    );
+    set!(
+        CallDataCopy,
+        InstructionInfo::new("CALLDATACOPY", 3, 0, VERY_LOW)
+    );
+    set!(CodeSize, InstructionInfo::new("CODESIZE", 0, 1, BASE));
+    set!(CodeCopy, InstructionInfo::new("CODECOPY", 3, 0, VERY_LOW));
+    set!(GasPrice, InstructionInfo::new("GASPRICE", 0, 1, BASE));
+    set!(ExtCodeSize, InstructionInfo::new("EXTCODESIZE", 1, 1, ZERO));
+    set!(ExtCodeCopy, InstructionInfo::new("EXTCODECOPY", 4, 0, ZERO));
+    set!(
+        ReturnDataSize,
+        InstructionInfo::new("RETURNDATASIZE", 0, 1, BASE).since(Fork::Byzantium)
+    );
+    set!(
+        ReturnDataCopy,
+        InstructionInfo::new("RETURNDATACOPY", 3, 0, VERY_LOW).since(Fork::Byzantium)
+    );
+    set!(
+        ExtCodeHash,
+        InstructionInfo::new("EXTCODEHASH", 1, 1, ZERO).since(Fork::Constantinople)
+    );
+
+    set!(
+        BlockHash,
+        InstructionInfo::new("BLOCKHASH", 1, 1, BLOCKHASH)
+    );
+    set!(Coinbase, InstructionInfo::new("COINBASE", 0, 1, BASE));
+    set!(Timestamp, InstructionInfo::new("TIMESTAMP", 0, 1, BASE));
+    set!(Number, InstructionInfo::new("NUMBER", 0, 1, BASE));
+    set!(PrevRandao, InstructionInfo::new("PREVRANDAO", 0, 1, BASE));
+    set!(GasLimit, InstructionInfo::new("GASLIMIT", 0, 1, BASE));
+    set!(
+        ChainId,
+        InstructionInfo::new("CHAINID", 0, 1, BASE).since(Fork::Istanbul)
+    );
+    set!(
+        SelfBalance,
+        InstructionInfo::new("SELFBALANCE", 0, 1, LOW).since(Fork::Istanbul)
+    );
+    set!(
+        BaseFee,
+        InstructionInfo::new("BASEFEE", 0, 1, BASE).since(Fork::London)
+    );
+    set!(
+        BlobHash,
+        InstructionInfo::new("BLOBHASH", 1, 1, VERY_LOW).since(Fork::Cancun)
+    );
+    set!(
+        BlobBaseFee,
+        InstructionInfo::new("BLOBBASEFEE", 0, 1, BASE).since(Fork::Cancun)
+    );
+
+    set!(Pop, InstructionInfo::new("POP", 1, 0, BASE));
+    set!(MLoad, InstructionInfo::new("MLOAD", 1, 1, VERY_LOW));
+    set!(MStore, InstructionInfo::new("MSTORE", 2, 0, VERY_LOW));
+    set!(MStore8, InstructionInfo::new("MSTORE8", 2, 0, VERY_LOW));
+    set!(SLoad, InstructionInfo::new("SLOAD", 1, 1, ZERO));
+    set!(SStore, InstructionInfo::new("SSTORE", 2, 0, ZERO).writes());
+    set!(Jump, InstructionInfo::new("JUMP", 1, 0, MID).jumps());
+    set!(JumpI, InstructionInfo::new("JUMPI", 2, 0, HIGH).jumps());
+    set!(PC, InstructionInfo::new("PC", 0, 1, BASE));
+    set!(MSize, InstructionInfo::new("MSIZE", 0, 1, BASE));
+    set!(Gas, InstructionInfo::new("GAS", 0, 1, BASE));
+    set!(JumpDest, InstructionInfo::new("JUMPDEST", 0, 0, JUMPDEST));
+    set!(
+        TLoad,
+        InstructionInfo::new("TLOAD", 1, 1, WARM_ACCESS).since(Fork::Cancun)
+    );
+    set!(
+        TStore,
+        InstructionInfo::new("TSTORE", 2, 0, WARM_ACCESS)
+            .since(Fork::Cancun)
+            .writes()
+    );
+    set!(
+        MCopy,
+        InstructionInfo::new("MCOPY", 3, 0, VERY_LOW).since(Fork::Cancun)
+    );
+
+    set!(
+        Push0,
+        InstructionInfo::new("PUSH0", 0, 1, BASE).since(Fork::Shanghai)
+    );
+    let mut index = 0;
+    while index < PUSH.len() {
+        let mut info = InstructionInfo::new(PUSH[index], 0, 1, VERY_LOW);
+        info.immediate_size = index + 1;
+        table[Push1 as usize + index] = Some(info);
+        index += 1;
+    }
+
+    let mut index = 0;
+    while index < DUP.len() {
+        table[Dup1 as usize + index] = Some(InstructionInfo::new(
+            DUP[index],
+            index + 1,
+            index + 2,
+            VERY_LOW,
+        ));
+        table[Swap1 as usize + index] = Some(InstructionInfo::new(
+            SWAP[index],
+            index + 2,
+            index + 2,
+            VERY_LOW,
+        ));
+        index += 1;
+    }
+
+    let mut index = 0;
+    while index < LOG.len() {
+        table[Log0 as usize + index] =
+            Some(InstructionInfo::new(LOG[index], index + 2, 0, gas::LOG).writes());
+        index += 1;
+    }
+
+    set!(
+        Create,
+        InstructionInfo::new("CREATE", 3, 1, gas::CREATE).writes()
+    );
+    set!(Call, InstructionInfo::new("CALL", 7, 1, ZERO));
+    set!(CallCode, InstructionInfo::new("CALLCODE", 7, 1, ZERO));
+    set!(Return, InstructionInfo::new("RETURN", 2, 0, ZERO).halts());
+    set!(
+        DelegateCall,
+        InstructionInfo::new("DELEGATECALL", 6, 1, ZERO).since(Fork::Homestead)
+    );
+    set!(
+        Create2,
+        InstructionInfo::new("CREATE2", 4, 1, gas::CREATE)
+            .since(Fork::Constantinople)
+            .writes()
+    );
+    set!(
+        StaticCall,
+        InstructionInfo::new("STATICCALL", 6, 1, ZERO).since(Fork::Byzantium)
+    );
+    set!(
+        Revert,
+        InstructionInfo::new("REVERT", 2, 0, ZERO)
+            .since(Fork::Byzantium)
+            .halts()
+    );
+    set!(Invalid, InstructionInfo::new("INVALID", 0, 0, ZERO).halts());
+    set!(
+        SelfDestruct,
+        InstructionInfo::new("SELFDESTRUCT", 1, 0, gas::SELFDESTRUCT)
+            .halts()
+            .writes()
+    );
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_covers_every_instruction() {
+        for opcode in 0..=u8::MAX {
+            let info = INSTRUCTION_TABLE[opcode as usize];
+            match Instruction::try_from(opcode) {
+                Ok(instruction) => {
+                    let info = info.unwrap();
+                    assert_eq!(info.name, format!("{:?}", instruction).to_uppercase());
+                    assert_eq!(
+                        Some(info.immediate_size),
+                        instruction.push_bytes().or(Some(0))
+                    );
+                }
+                Err(_) => assert_eq!(info, None),
+            }
+        }
+    }
+
+    #[test]
+    fn mnemonics_round_trip() {
+        for opcode in 0..=u8::MAX {
+            if let Ok(instruction) = Instruction::try_from(opcode) {
+                assert_eq!(instruction.to_string().parse(), Ok(instruction));
+            }
+        }
+
+        assert_eq!("keccak256".parse(), Ok(Keccak256));
+        assert_eq!("PUSH33".parse::<Instruction>(), Err(ParseInstructionError));
+    }
+
+    #[test]
+    fn stack_inputs_and_outputs() {
+        let io = |instruction: Instruction| (instruction.info().inputs, instruction.info().outputs);
+
+        assert_eq!(io(AddMod), (3, 1));
+        assert_eq!(io(Dup16), (16, 17));
+        assert_eq!(io(Swap1), (2, 2));
+        assert_eq!(io(Log4), (6, 0));
+        assert_eq!(io(Call), (7, 1));
+        assert_eq!(io(DelegateCall), (6, 1));
+    }
+
+    #[test]
+    fn base_gas_follows_repricings() {
+        assert_eq!(SLoad.base_gas(Fork::Frontier), 50);
+        assert_eq!(SLoad.base_gas(Fork::Byzantium), 200);
+        assert_eq!(SLoad.base_gas(Fork::Istanbul), 800);
+        assert_eq!(SLoad.base_gas(Fork::Berlin), 0);
+        assert_eq!(Balance.base_gas(Fork::Petersburg), 400);
+        assert_eq!(Call.base_gas(Fork::Homestead), 40);
+        assert_eq!(SelfDestruct.base_gas(Fork::Homestead), 0);
+        assert_eq!(SelfDestruct.base_gas(Fork::Prague), 5000);
+        assert_eq!(Add.base_gas(Fork::Frontier), 3);
+    }
+
+    #[test]
+    fn flags_and_availability() {
+        assert!(Return.info().terminating && !Return.info().modifies_state);
+        assert!(SelfDestruct.info().terminating && SelfDestruct.info().modifies_state);
+        assert!(JumpI.info().jump);
+        assert!(TStore.info().modifies_state);
+        assert!(!Call.info().modifies_state);
+
+        assert!(!Push0.is_available(Fork::Paris));
+        assert!(Push0.is_available(Fork::Shanghai));
+        assert!(Stop.is_available(Fork::Frontier));
+    }
+}
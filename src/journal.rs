@@ -0,0 +1,687 @@
+use crate::database::{Account, Database};
+use crate::env::Fork;
+use crate::error::ErrorKind;
+use crate::rlp::{self, Decodable, DecodeError, Encodable};
+use bytes::Bytes;
+use ethereum_types::{H160, H256, U256};
+use std::collections::{HashMap, HashSet};
+
+/// Log emitted by `LOGn`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Log {
+    /// Account that emitted the log.
+    pub address: H160,
+
+    /// Indexed topics.
+    pub topics: Vec<H256>,
+
+    /// Unindexed data.
+    pub data: Bytes,
+}
+
+impl Encodable for Log {
+    fn encode(&self, out: &mut Vec<u8>) {
+        rlp::encode_list(&[&self.address, &self.topics, &self.data], out)
+    }
+}
+
+impl Decodable for Log {
+    fn decode(buf: &mut &[u8]) -> Result<Log, DecodeError> {
+        rlp::decode_list(buf, |payload| {
+            Ok(Log {
+                address: H160::decode(payload)?,
+                topics: Vec::decode(payload)?,
+                data: Bytes::decode(payload)?,
+            })
+        })
+    }
+}
+
+/// A change to the state that can be undone.
+#[derive(Eq, PartialEq, Clone, Debug)]
+enum JournalEntry {
+    /// A storage slot was written.
+    StorageChanged {
+        address: H160,
+        key: H256,
+        previous: H256,
+    },
+    /// Value moved from one account to another.
+    BalanceTransfer { from: H160, to: H160, value: U256 },
+    /// A balance was set outside of a transfer, e.g. when paying for gas.
+    BalanceChanged { address: H160, previous: U256 },
+    /// A nonce was incremented.
+    NonceBumped { address: H160 },
+    /// An account was created, replacing `previous` if there was one.
+    AccountCreated {
+        address: H160,
+        previous: Option<Account>,
+    },
+    /// Code was deployed to an account.
+    CodeChanged { address: H160, previous: Bytes },
+    /// A log was emitted.
+    LogEmitted,
+    /// An account was accessed for the first time in the transaction.
+    AccountWarmed { address: H160 },
+    /// A storage slot was accessed for the first time in the transaction.
+    StorageWarmed { address: H160, key: H256 },
+    /// A transient storage slot was written.
+    TransientStorageChanged {
+        address: H160,
+        key: H256,
+        previous: H256,
+    },
+    /// An account was touched for the first time in the transaction.
+    AccountTouched { address: H160 },
+    /// A contract was created by the transaction.
+    ContractCreated { address: H160 },
+    /// An account was marked for deletion at the end of the transaction.
+    AccountDestroyed { address: H160 },
+}
+
+/// Position in the journal that later changes can be reverted to.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct Checkpoint {
+    journal_len: usize,
+}
+
+/// State that records every change so it can be undone.
+///
+/// Changes are written through to the database as they happen and the journal remembers how to
+/// undo them. Checkpoints nest: reverting to a checkpoint undoes everything after it, including
+/// changes under inner checkpoints that were already committed. Once the outermost checkpoint is
+/// committed the changes are final and the journal is cleared.
+///
+/// Some state only lasts for a transaction, like which accounts are warm. It's journaled as well
+/// and dropped by `finish_transaction`.
+#[derive(Debug)]
+pub struct JournaledState<DB: Database> {
+    db: DB,
+    journal: Vec<JournalEntry>,
+    logs: Vec<Log>,
+
+    /// Number of checkpoints that are neither committed nor reverted.
+    depth: usize,
+
+    /// Accounts accessed in the transaction (EIP-2929).
+    warm_accounts: HashSet<H160>,
+
+    /// Storage slots accessed in the transaction (EIP-2929).
+    warm_storage: HashSet<(H160, H256)>,
+
+    /// Value of written storage slots at the start of the transaction (EIP-2200).
+    original_storage: HashMap<(H160, H256), H256>,
+
+    /// Storage that only lasts for the transaction (EIP-1153).
+    transient_storage: HashMap<(H160, H256), H256>,
+
+    /// Accounts touched in the transaction, deleted at the end if empty (EIP-161).
+    touched: HashSet<H160>,
+
+    /// Contracts created in the transaction, the only ones `SELFDESTRUCT` deletes (EIP-6780).
+    created: HashSet<H160>,
+
+    /// Accounts deleted at the end of the transaction.
+    destroyed: HashSet<H160>,
+}
+
+impl<DB: Database> JournaledState<DB> {
+    pub fn new(db: DB) -> JournaledState<DB> {
+        JournaledState {
+            db,
+            journal: Vec::new(),
+            logs: Vec::new(),
+            depth: 0,
+            warm_accounts: HashSet::new(),
+            warm_storage: HashSet::new(),
+            original_storage: HashMap::new(),
+            transient_storage: HashMap::new(),
+            touched: HashSet::new(),
+            created: HashSet::new(),
+            destroyed: HashSet::new(),
+        }
+    }
+
+    /// The underlying database.
+    pub fn db(&self) -> &DB {
+        &self.db
+    }
+
+    /// The underlying database, for changes that aren't journaled.
+    pub fn db_mut(&mut self) -> &mut DB {
+        &mut self.db
+    }
+
+    /// Drop the journal, returning the underlying database.
+    pub fn into_db(self) -> DB {
+        self.db
+    }
+
+    /// Logs emitted since the last time they were taken.
+    pub fn logs(&self) -> &[Log] {
+        &self.logs
+    }
+
+    /// Remove and return the logs emitted so far.
+    pub fn take_logs(&mut self) -> Vec<Log> {
+        std::mem::take(&mut self.logs)
+    }
+
+    /// Start recording changes that can be reverted as a unit.
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.depth += 1;
+
+        Checkpoint {
+            journal_len: self.journal.len(),
+        }
+    }
+
+    /// Keep the changes since `checkpoint`.
+    ///
+    /// They can still be reverted by an enclosing checkpoint.
+    pub fn commit(&mut self, _checkpoint: Checkpoint) {
+        self.depth -= 1;
+
+        if self.depth == 0 {
+            self.journal.clear();
+        }
+    }
+
+    /// Undo every change since `checkpoint`.
+    pub fn revert_to(&mut self, checkpoint: Checkpoint) {
+        self.depth -= 1;
+
+        while self.journal.len() > checkpoint.journal_len {
+            let entry = self
+                .journal
+                .pop()
+                .expect("journal is longer than checkpoint");
+            self.undo(entry);
+        }
+    }
+
+    fn undo(&mut self, entry: JournalEntry) {
+        match entry {
+            JournalEntry::StorageChanged {
+                address,
+                key,
+                previous,
+            } => self.db.set_storage(address, key, previous),
+            JournalEntry::BalanceTransfer { from, to, value } => {
+                self.modify_account(to, |account| account.balance -= value);
+                self.modify_account(from, |account| account.balance += value);
+            }
+            JournalEntry::BalanceChanged { address, previous } => {
+                self.modify_account(address, |account| account.balance = previous)
+            }
+            JournalEntry::NonceBumped { address } => {
+                self.modify_account(address, |account| account.nonce -= 1)
+            }
+            JournalEntry::AccountCreated { address, previous } => match previous {
+                Some(previous) => self.db.insert_account(address, previous),
+                None => self.db.remove_account(address),
+            },
+            JournalEntry::CodeChanged { address, previous } => {
+                self.modify_account(address, |account| account.set_code(previous))
+            }
+            JournalEntry::LogEmitted => {
+                self.logs.pop();
+            }
+            JournalEntry::AccountWarmed { address } => {
+                self.warm_accounts.remove(&address);
+            }
+            JournalEntry::StorageWarmed { address, key } => {
+                self.warm_storage.remove(&(address, key));
+            }
+            JournalEntry::TransientStorageChanged {
+                address,
+                key,
+                previous,
+            } => {
+                self.transient_storage.insert((address, key), previous);
+            }
+            JournalEntry::AccountTouched { address } => {
+                self.touched.remove(&address);
+            }
+            JournalEntry::ContractCreated { address } => {
+                self.created.remove(&address);
+            }
+            JournalEntry::AccountDestroyed { address } => {
+                self.destroyed.remove(&address);
+            }
+        }
+    }
+
+    /// End the transaction, deleting self-destructed accounts and touched accounts that are
+    /// empty, and dropping the state that only lasts for a transaction.
+    pub fn finish_transaction(&mut self) {
+        for address in std::mem::take(&mut self.destroyed) {
+            self.db.remove_account(address);
+        }
+
+        for address in std::mem::take(&mut self.touched) {
+            if self
+                .db
+                .account(address)
+                .is_some_and(|account| account.is_empty())
+            {
+                self.db.remove_account(address);
+            }
+        }
+
+        self.clear_transaction();
+    }
+
+    /// Drop the state that only lasts for a transaction, without deleting any accounts.
+    pub fn clear_transaction(&mut self) {
+        self.journal.clear();
+        self.depth = 0;
+        self.warm_accounts.clear();
+        self.warm_storage.clear();
+        self.original_storage.clear();
+        self.transient_storage.clear();
+        self.touched.clear();
+        self.created.clear();
+        self.destroyed.clear();
+    }
+
+    /// Create an empty account at `address` if there isn't one, so reverting removes it again.
+    fn create_if_missing(&mut self, address: H160) {
+        if self.db.account(address).is_none() {
+            self.create_account(address, Account::default());
+        }
+    }
+
+    /// Apply `change` to the account at `address`, creating it if it doesn't exist.
+    fn modify_account(&mut self, address: H160, change: impl FnOnce(&mut Account)) {
+        let mut account = self.db.account(address).unwrap_or_default();
+        change(&mut account);
+        self.db.insert_account(address, account);
+    }
+
+    /// Account at `address`, `None` if it doesn't exist.
+    pub fn account(&self, address: H160) -> Option<Account> {
+        self.db.account(address)
+    }
+
+    /// Balance of the account at `address`, zero if it doesn't exist.
+    pub fn balance(&self, address: H160) -> U256 {
+        self.db
+            .account(address)
+            .map(|account| account.balance)
+            .unwrap_or_default()
+    }
+
+    /// Value of the storage slot `key` of the account at `address`.
+    pub fn storage(&self, address: H160, key: H256) -> H256 {
+        self.db.storage(address, key)
+    }
+
+    /// Write a storage slot.
+    pub fn set_storage(&mut self, address: H160, key: H256, value: H256) {
+        let previous = self.db.storage(address, key);
+        if previous == value {
+            return;
+        }
+
+        self.original_storage
+            .entry((address, key))
+            .or_insert(previous);
+        self.journal.push(JournalEntry::StorageChanged {
+            address,
+            key,
+            previous,
+        });
+        self.db.set_storage(address, key, value);
+    }
+
+    /// Value of a storage slot at the start of the transaction.
+    pub fn original_storage(&self, address: H160, key: H256) -> H256 {
+        match self.original_storage.get(&(address, key)) {
+            Some(value) => *value,
+            None => self.db.storage(address, key),
+        }
+    }
+
+    /// Value of the transient storage slot `key` of the account at `address`.
+    pub fn transient_storage(&self, address: H160, key: H256) -> H256 {
+        self.transient_storage
+            .get(&(address, key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Write a transient storage slot.
+    pub fn set_transient_storage(&mut self, address: H160, key: H256, value: H256) {
+        let previous = self
+            .transient_storage
+            .insert((address, key), value)
+            .unwrap_or_default();
+
+        if previous != value {
+            self.journal.push(JournalEntry::TransientStorageChanged {
+                address,
+                key,
+                previous,
+            });
+        }
+    }
+
+    /// Mark an account as accessed, returning true if it wasn't already.
+    pub fn warm_account(&mut self, address: H160) -> bool {
+        let cold = self.warm_accounts.insert(address);
+        if cold {
+            self.journal.push(JournalEntry::AccountWarmed { address });
+        }
+
+        cold
+    }
+
+    /// Mark a storage slot as accessed, returning true if it wasn't already.
+    pub fn warm_storage(&mut self, address: H160, key: H256) -> bool {
+        let cold = self.warm_storage.insert((address, key));
+        if cold {
+            self.journal
+                .push(JournalEntry::StorageWarmed { address, key });
+        }
+
+        cold
+    }
+
+    /// Mark an account as touched, so it's deleted at the end of the transaction if it's empty.
+    pub fn touch(&mut self, address: H160) {
+        if self.touched.insert(address) {
+            self.journal.push(JournalEntry::AccountTouched { address });
+        }
+    }
+
+    /// Move `value` wei between accounts, creating the recipient if it doesn't exist.
+    pub fn transfer(&mut self, from: H160, to: H160, value: U256) -> Result<(), ErrorKind> {
+        if self.balance(from) < value {
+            return Err(ErrorKind::InsufficientBalance);
+        }
+
+        self.create_if_missing(to);
+        self.touch(from);
+        self.touch(to);
+        self.modify_account(from, |account| account.balance -= value);
+        self.modify_account(to, |account| {
+            account.balance = account.balance.overflowing_add(value).0
+        });
+        self.journal
+            .push(JournalEntry::BalanceTransfer { from, to, value });
+
+        Ok(())
+    }
+
+    /// Set the balance of an account outside of a transfer, creating it if it doesn't exist.
+    pub fn set_balance(&mut self, address: H160, balance: U256) {
+        self.create_if_missing(address);
+        let previous = self.balance(address);
+
+        self.modify_account(address, |account| account.balance = balance);
+        self.journal
+            .push(JournalEntry::BalanceChanged { address, previous });
+    }
+
+    /// Increment the nonce of an account, creating it if it doesn't exist.
+    pub fn increment_nonce(&mut self, address: H160) {
+        self.create_if_missing(address);
+        self.modify_account(address, |account| account.nonce += 1);
+        self.journal.push(JournalEntry::NonceBumped { address });
+    }
+
+    /// Create a fresh account, replacing any existing account at `address`.
+    pub fn create_account(&mut self, address: H160, account: Account) {
+        let previous = self.db.account(address);
+
+        self.db.insert_account(address, account);
+        self.journal
+            .push(JournalEntry::AccountCreated { address, previous });
+    }
+
+    /// Deploy code to an account, creating it if it doesn't exist.
+    pub fn set_code(&mut self, address: H160, code: Bytes) {
+        self.create_if_missing(address);
+        let previous = self
+            .db
+            .account(address)
+            .map(|account| account.code)
+            .unwrap_or_default();
+
+        self.modify_account(address, |account| account.set_code(code));
+        self.journal
+            .push(JournalEntry::CodeChanged { address, previous });
+    }
+
+    /// Turn `address` into a new contract account, keeping any balance it already holds.
+    pub fn create_contract(&mut self, address: H160) {
+        let balance = self.balance(address);
+
+        self.create_account(address, Account::new(balance, 1, Bytes::new()));
+        if self.created.insert(address) {
+            self.journal.push(JournalEntry::ContractCreated { address });
+        }
+    }
+
+    /// Send the balance of `address` to `beneficiary`.
+    ///
+    /// The account is deleted at the end of the transaction, from Cancun only if it was created
+    /// in the same transaction (EIP-6780). A deleted account sending to itself burns its balance.
+    pub fn selfdestruct(&mut self, address: H160, beneficiary: H160, fork: Fork) {
+        let balance = self.balance(address);
+        let destroyed = fork < Fork::Cancun || self.created.contains(&address);
+
+        if beneficiary != address {
+            self.transfer(address, beneficiary, balance)
+                .expect("balance covers itself");
+        } else if destroyed {
+            self.set_balance(address, U256::zero());
+        }
+
+        if destroyed && self.destroyed.insert(address) {
+            self.journal
+                .push(JournalEntry::AccountDestroyed { address });
+        }
+    }
+
+    /// Hash of the block `number`, zero if it isn't known.
+    pub fn block_hash(&self, number: u64) -> H256 {
+        self.db.block_hash(number)
+    }
+
+    /// Emit a log.
+    pub fn log(&mut self, log: Log) {
+        self.logs.push(log);
+        self.journal.push(JournalEntry::LogEmitted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::InMemoryDatabase;
+
+    fn address(value: u64) -> H160 {
+        H160::from_low_u64_be(value)
+    }
+
+    fn word(value: u64) -> H256 {
+        H256::from_low_u64_be(value)
+    }
+
+    fn funded() -> JournaledState<InMemoryDatabase> {
+        let mut db = InMemoryDatabase::new();
+        db.insert_account(address(1), Account::new(U256::from(100), 0, Bytes::new()));
+        JournaledState::new(db)
+    }
+
+    #[test]
+    fn revert_undoes_every_kind_of_change() {
+        let mut state = funded();
+        state.set_storage(address(1), word(1), word(1));
+        let before = state.db().clone();
+
+        let checkpoint = state.checkpoint();
+        state.set_storage(address(1), word(1), word(2));
+        state.set_storage(address(1), word(2), word(3));
+        state
+            .transfer(address(1), address(2), U256::from(40))
+            .unwrap();
+        state.set_balance(address(1), U256::from(7));
+        state.increment_nonce(address(1));
+        state.create_account(address(3), Account::default());
+        state.set_code(address(3), Bytes::from_static(&[0x00]));
+        state.log(Log {
+            address: address(1),
+            topics: vec![word(1)],
+            data: Bytes::new(),
+        });
+        state.revert_to(checkpoint);
+
+        assert_eq!(state.account(address(1)), before.account(address(1)));
+        assert_eq!(state.storage(address(1), word(1)), word(1));
+        assert_eq!(state.storage(address(1), word(2)), H256::zero());
+        assert_eq!(state.account(address(3)), None);
+        assert!(state.logs().is_empty());
+        assert_eq!(state.account(address(2)), None);
+    }
+
+    #[test]
+    fn reverting_outer_checkpoint_undoes_committed_inner_changes() {
+        let mut state = funded();
+
+        let outer = state.checkpoint();
+        state.set_storage(address(1), word(1), word(1));
+
+        let inner = state.checkpoint();
+        state.set_storage(address(1), word(2), word(2));
+        state.commit(inner);
+
+        assert_eq!(state.storage(address(1), word(2)), word(2));
+
+        state.revert_to(outer);
+
+        assert_eq!(state.storage(address(1), word(1)), H256::zero());
+        assert_eq!(state.storage(address(1), word(2)), H256::zero());
+    }
+
+    #[test]
+    fn reverting_inner_checkpoint_keeps_outer_changes() {
+        let mut state = funded();
+
+        let outer = state.checkpoint();
+        state.set_storage(address(1), word(1), word(1));
+        state.log(Log {
+            address: address(1),
+            topics: Vec::new(),
+            data: Bytes::new(),
+        });
+
+        let inner = state.checkpoint();
+        state.set_storage(address(1), word(1), word(2));
+        state.increment_nonce(address(1));
+        state.log(Log {
+            address: address(1),
+            topics: Vec::new(),
+            data: Bytes::new(),
+        });
+        state.revert_to(inner);
+
+        state.commit(outer);
+
+        assert_eq!(state.storage(address(1), word(1)), word(1));
+        assert_eq!(state.account(address(1)).unwrap().nonce, 0);
+        assert_eq!(state.logs().len(), 1);
+    }
+
+    #[test]
+    fn transfer_needs_enough_balance() {
+        let mut state = funded();
+
+        assert_eq!(
+            state.transfer(address(1), address(2), U256::from(101)),
+            Err(ErrorKind::InsufficientBalance)
+        );
+        assert_eq!(state.balance(address(1)), U256::from(100));
+        assert_eq!(state.account(address(2)), None);
+    }
+
+    #[test]
+    fn warm_accounts_are_reverted() {
+        let mut db = InMemoryDatabase::new();
+        let mut state = JournaledState::new(&mut db);
+        let address = address(1);
+
+        let checkpoint = state.checkpoint();
+        assert!(state.warm_account(address));
+        assert!(!state.warm_account(address));
+        state.revert_to(checkpoint);
+
+        assert!(state.warm_account(address));
+    }
+
+    #[test]
+    fn original_storage_is_kept_until_the_transaction_ends() {
+        let mut db = InMemoryDatabase::new();
+        let mut state = JournaledState::new(&mut db);
+        let address = address(1);
+        let key = H256::zero();
+
+        state.set_storage(address, key, word(1));
+        state.set_storage(address, key, word(2));
+        assert_eq!(state.original_storage(address, key), H256::zero());
+
+        state.finish_transaction();
+        assert_eq!(state.original_storage(address, key), word(2));
+    }
+
+    #[test]
+    fn touched_empty_accounts_are_deleted() {
+        let mut db = InMemoryDatabase::new();
+        let from = address(1);
+        let to = address(2);
+        db.insert_account(from, Account::new(U256::one(), 0, Bytes::new()));
+
+        let mut state = JournaledState::new(&mut db);
+        state.transfer(from, to, U256::zero()).unwrap();
+        assert!(state.account(to).is_some());
+        state.finish_transaction();
+
+        assert!(db.account(from).is_some());
+        assert!(db.account(to).is_none());
+    }
+
+    #[test]
+    fn selfdestruct_only_deletes_contracts_created_in_the_transaction() {
+        let mut db = InMemoryDatabase::new();
+        let existing = address(1);
+        let created = address(2);
+        let beneficiary = address(3);
+        db.insert_account(existing, Account::new(U256::one(), 1, Bytes::new()));
+
+        let mut state = JournaledState::new(&mut db);
+        state.create_contract(created);
+        state.set_balance(created, U256::one());
+        state.selfdestruct(existing, beneficiary, Fork::Cancun);
+        state.selfdestruct(created, beneficiary, Fork::Cancun);
+        state.finish_transaction();
+
+        assert_eq!(db.account(existing).unwrap().balance, U256::zero());
+        assert!(db.account(created).is_none());
+        assert_eq!(db.account(beneficiary).unwrap().balance, U256::from(2));
+    }
+
+    #[test]
+    fn selfdestruct_deletes_any_account_before_cancun() {
+        let mut db = InMemoryDatabase::new();
+        let existing = address(1);
+        db.insert_account(existing, Account::new(U256::one(), 1, Bytes::new()));
+
+        let mut state = JournaledState::new(&mut db);
+        state.selfdestruct(existing, existing, Fork::Shanghai);
+        state.finish_transaction();
+
+        assert!(db.account(existing).is_none());
+    }
+}
@@ -1,6 +1,58 @@
+mod assembler;
+mod block;
+mod code;
+mod database;
+mod disassembler;
+mod env;
+mod error;
+mod executor;
+mod frame;
+mod gas;
+mod hash;
+mod i256;
+mod inspector;
 mod instruction;
+mod journal;
+mod message;
+mod precompile;
+mod result;
+pub mod rlp;
+mod signature;
+#[cfg(test)]
+mod test_helpers;
+mod tracer;
 mod transaction;
+mod trie;
 mod vm;
 
-pub use transaction::Transaction;
-pub use vm::Vm;
+pub use assembler::{assemble, AssembleError};
+pub use block::{execute_block, execute_block_with_precompiles, logs_bloom, BlockResult, Receipt};
+pub use database::{Account, Database, InMemoryDatabase, IterableDatabase};
+pub use disassembler::{disassemble, Disassembly, Operation};
+pub use env::{BlockEnv, Env, Fork, TxEnv};
+pub use error::{BlockError, Error, ErrorKind, InvalidTransaction};
+pub use i256::I256;
+pub use inspector::{CallOutcome, FrameView, Inspector, NoopInspector};
+pub use instruction::{Instruction, InstructionInfo, ParseInstructionError, INSTRUCTION_TABLE};
+pub use journal::{Checkpoint, JournaledState, Log};
+pub use message::{CallKind, Message};
+pub use precompile::{
+    Precompile, PrecompileContext, PrecompileOutput, PrecompileResult, PrecompileState,
+    Precompiles, StatefulPrecompile,
+};
+pub use result::{ExecutionResult, ExecutionStatus, SuccessReason};
+pub use signature::{Signature, SignatureError};
+pub use tracer::Eip3155Tracer;
+#[cfg(feature = "serde")]
+pub use tracer::{
+    AccountDiff, CallAction, CallFrame, CallLog, CallTracer, CreateAction, Diff, MemoryDelta,
+    ParityTracer, PrestateAccount, PrestateResult, PrestateTracer, StateDiff, StateDiffTracer,
+    StorageDelta, StructLog, StructLogResult, StructLogger, SuicideAction, TraceAction,
+    TraceResult, TransactionTrace, VmExecutedOperation, VmInstruction, VmTrace, VmTracer,
+};
+pub use transaction::{
+    AccessListItem, AccessListTransaction, Authorization, BlobTransaction, FeeMarketTransaction,
+    LegacyTransaction, SetCodeTransaction, SignedTransaction, Transaction, TxKind,
+};
+pub use trie::{state_root, storage_root, Trie, EMPTY_ROOT};
+pub use vm::{Step, StepStatus, Vm};
@@ -0,0 +1,80 @@
+use bytes::Bytes;
+use ethereum_types::{H160, U256};
+
+/// How a message was sent.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default, Hash)]
+pub enum CallKind {
+    #[default]
+    Call,
+    /// Code of another account running as the caller, with a value of its own.
+    CallCode,
+    /// Code of another account running as the caller, with the caller's caller and value.
+    DelegateCall,
+    /// Call that can't modify state.
+    StaticCall,
+    /// Contract creation from a transaction or `CREATE`.
+    Create,
+    /// Contract creation at an address derived from a salt.
+    Create2,
+}
+
+impl CallKind {
+    /// True if the message creates a contract.
+    pub fn is_create(&self) -> bool {
+        matches!(self, CallKind::Create | CallKind::Create2)
+    }
+}
+
+/// Input to a single execution of the VM.
+///
+/// This isn't a transaction as sent to the network but rather what the VM needs to run code, be it
+/// for a transaction or for a call made by a contract.
+#[derive(Clone, Debug, Default)]
+pub struct Message {
+    pub kind: CallKind,
+
+    /// Account sending the message.
+    pub caller: H160,
+
+    /// Address of the account the code runs as, storage reads and writes go to this account.
+    ///
+    /// For a creation, the address of the new contract.
+    pub address: H160,
+
+    /// Address of the account the code was loaded from, which differs from `address` for
+    /// `CALLCODE` and `DELEGATECALL`.
+    pub code_address: H160,
+
+    /// Wei sent along with the message.
+    pub value: U256,
+
+    /// Code to be executed, the init code for a creation.
+    pub code: Bytes,
+
+    /// Call data.
+    pub data: Bytes,
+
+    /// Gas available to the execution.
+    pub gas: u64,
+
+    /// True if the execution can't modify state.
+    pub is_static: bool,
+
+    /// Number of calls the message is nested in, zero for a transaction.
+    pub depth: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn can_construct_message() {
+        let _ = Message {
+            address: H160::zero(),
+            code: Bytes::from("hello world"),
+            data: Bytes::from("asd"),
+            ..Message::default()
+        };
+    }
+}
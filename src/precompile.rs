@@ -0,0 +1,808 @@
+use crate::database::{Account, Database};
+use crate::env::Fork;
+use crate::error::ErrorKind;
+use crate::frame::copy_padded;
+use crate::gas::{self, Gas};
+use crate::journal::{JournaledState, Log};
+use crate::signature::Signature;
+use bytes::Bytes;
+use c_kzg::{Bytes32, Bytes48};
+use ethereum_types::{H160, H256, U256};
+use num_bigint::BigUint;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use substrate_bn::{AffineG1, AffineG2, Fq, Fq2, Fr, Group, Gt, G1, G2};
+
+/// Output of a precompiled contract that ran successfully.
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
+pub struct PrecompileOutput {
+    pub output: Bytes,
+
+    /// Gas used, at most the gas limit the contract was given.
+    pub gas_used: u64,
+}
+
+/// Outcome of running a precompiled contract.
+///
+/// An error uses up all the gas given to the contract, `ErrorKind::PrecompileFailed` being the
+/// error for input it can't process.
+pub type PrecompileResult = Result<PrecompileOutput, ErrorKind>;
+
+/// Contract implemented in native code rather than EVM bytecode.
+pub trait Precompile: Send + Sync {
+    /// Run the contract on `input` with at most `gas_limit` gas.
+    fn run(&self, input: &[u8], gas_limit: u64) -> PrecompileResult;
+}
+
+impl<F: Fn(&[u8], u64) -> PrecompileResult + Send + Sync> Precompile for F {
+    fn run(&self, input: &[u8], gas_limit: u64) -> PrecompileResult {
+        self(input, gas_limit)
+    }
+}
+
+/// State a stateful precompiled contract can read and write.
+///
+/// Changes are journaled like those made by bytecode, so they're undone if the contract or one of
+/// its callers fails.
+pub trait PrecompileState {
+    fn account(&self, address: H160) -> Option<Account>;
+    fn balance(&self, address: H160) -> U256;
+    fn storage(&self, address: H160, key: H256) -> H256;
+    fn set_storage(&mut self, address: H160, key: H256, value: H256);
+
+    /// Move `value` from one account to another, failing if `from` can't afford it.
+    fn transfer(&mut self, from: H160, to: H160, value: U256) -> Result<(), ErrorKind>;
+
+    fn log(&mut self, log: Log);
+}
+
+impl<DB: Database> PrecompileState for JournaledState<DB> {
+    fn account(&self, address: H160) -> Option<Account> {
+        JournaledState::account(self, address)
+    }
+
+    fn balance(&self, address: H160) -> U256 {
+        JournaledState::balance(self, address)
+    }
+
+    fn storage(&self, address: H160, key: H256) -> H256 {
+        JournaledState::storage(self, address, key)
+    }
+
+    fn set_storage(&mut self, address: H160, key: H256, value: H256) {
+        JournaledState::set_storage(self, address, key, value)
+    }
+
+    fn transfer(&mut self, from: H160, to: H160, value: U256) -> Result<(), ErrorKind> {
+        JournaledState::transfer(self, from, to, value)
+    }
+
+    fn log(&mut self, log: Log) {
+        JournaledState::log(self, log)
+    }
+}
+
+/// Call a stateful precompiled contract runs in.
+pub struct PrecompileContext<'a> {
+    /// Account calling the contract.
+    pub caller: H160,
+
+    /// Account the contract runs as, the caller itself for `CALLCODE` and `DELEGATECALL`.
+    pub address: H160,
+
+    /// Wei sent along with the call, already transferred.
+    pub value: U256,
+
+    /// True if the contract must not modify state, it's up to the contract to honour it.
+    pub is_static: bool,
+
+    pub state: &'a mut dyn PrecompileState,
+}
+
+/// Contract implemented in native code that can access the state.
+pub trait StatefulPrecompile: Send + Sync {
+    /// Run the contract on `input` with at most `gas_limit` gas.
+    fn run(
+        &self,
+        input: &[u8],
+        gas_limit: u64,
+        context: &mut PrecompileContext<'_>,
+    ) -> PrecompileResult;
+}
+
+/// Precompiled contract registered at an address.
+#[derive(Clone)]
+pub(crate) enum Entry {
+    Stateless(Arc<dyn Precompile>),
+    Stateful(Arc<dyn StatefulPrecompile>),
+}
+
+/// Precompiled contracts by address.
+///
+/// Starts out with the contracts of the protocol, which embedders can add to, override or
+/// remove.
+#[derive(Clone, Default)]
+pub struct Precompiles {
+    contracts: HashMap<H160, Entry>,
+}
+
+impl Precompiles {
+    /// The precompiled contracts of `fork`.
+    pub fn new(fork: Fork) -> Precompiles {
+        let mut precompiles = Precompiles::default();
+        for (index, &(since, run)) in STANDARD.iter().enumerate() {
+            if fork >= since {
+                precompiles.insert(
+                    H160::from_low_u64_be(index as u64 + 1),
+                    Standard { run, fork },
+                );
+            }
+        }
+        precompiles
+    }
+
+    /// Register `precompile` at `address`, replacing any contract registered there.
+    pub fn insert(&mut self, address: H160, precompile: impl Precompile + 'static) {
+        self.contracts
+            .insert(address, Entry::Stateless(Arc::new(precompile)));
+    }
+
+    /// Register a stateful `precompile` at `address`, replacing any contract registered there.
+    pub fn insert_stateful(
+        &mut self,
+        address: H160,
+        precompile: impl StatefulPrecompile + 'static,
+    ) {
+        self.contracts
+            .insert(address, Entry::Stateful(Arc::new(precompile)));
+    }
+
+    /// Unregister the contract at `address`, returning true if there was one.
+    pub fn remove(&mut self, address: H160) -> bool {
+        self.contracts.remove(&address).is_some()
+    }
+
+    pub fn contains(&self, address: H160) -> bool {
+        self.contracts.contains_key(&address)
+    }
+
+    /// Addresses with a contract registered, in no particular order.
+    pub fn addresses(&self) -> impl Iterator<Item = H160> + '_ {
+        self.contracts.keys().copied()
+    }
+
+    pub(crate) fn get(&self, address: H160) -> Option<&Entry> {
+        self.contracts.get(&address)
+    }
+}
+
+impl fmt::Debug for Precompiles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.contracts.keys()).finish()
+    }
+}
+
+/// Native code of a contract of the protocol, charging its gas up front.
+type StandardFn = fn(&[u8], &mut Gas, Fork) -> Result<Bytes, ErrorKind>;
+
+/// Contracts of the protocol at addresses 0x01, 0x02... and the fork each became available in.
+const STANDARD: [(Fork, StandardFn); 10] = [
+    (Fork::Frontier, ecrecover),
+    (Fork::Frontier, sha256),
+    (Fork::Frontier, ripemd160),
+    (Fork::Frontier, identity),
+    (Fork::Byzantium, modexp),
+    (Fork::Byzantium, bn_add),
+    (Fork::Byzantium, bn_mul),
+    (Fork::Byzantium, bn_pairing),
+    (Fork::Istanbul, blake2f),
+    (Fork::Cancun, point_evaluation),
+];
+
+/// Contract of the protocol, priced as in a fork.
+struct Standard {
+    run: StandardFn,
+    fork: Fork,
+}
+
+impl Precompile for Standard {
+    fn run(&self, input: &[u8], gas_limit: u64) -> PrecompileResult {
+        let mut gas = Gas::new(gas_limit);
+        let output = (self.run)(input, &mut gas, self.fork)?;
+
+        Ok(PrecompileOutput {
+            output,
+            gas_used: gas_limit - gas.remaining(),
+        })
+    }
+}
+
+/// `input` zero padded or truncated to `N` bytes.
+fn padded<const N: usize>(input: &[u8]) -> [u8; N] {
+    let mut padded = [0u8; N];
+    copy_padded(&mut padded, input, U256::zero());
+    padded
+}
+
+/// Charge a base cost plus a cost per word of input.
+fn charge_per_word(gas: &mut Gas, input: &[u8], base: u64, word: u64) -> Result<(), ErrorKind> {
+    gas.charge(base + word * gas::words(input.len()))
+}
+
+/// Address of the key that signed a hash, empty output if the signature is invalid.
+fn ecrecover(input: &[u8], gas: &mut Gas, _fork: Fork) -> Result<Bytes, ErrorKind> {
+    gas.charge(3000)?;
+
+    let input = padded::<128>(input);
+    let v = U256::from_big_endian(&input[32..64]);
+    if v != U256::from(27) && v != U256::from(28) {
+        return Ok(Bytes::new());
+    }
+
+    let signature = Signature {
+        y_parity: (v == U256::from(28)) as u8,
+        r: U256::from_big_endian(&input[64..96]),
+        s: U256::from_big_endian(&input[96..128]),
+    };
+    match signature.recover_address_allowing_high_s(H256::from_slice(&input[..32])) {
+        Ok(address) => Ok(Bytes::copy_from_slice(H256::from(address).as_bytes())),
+        Err(_) => Ok(Bytes::new()),
+    }
+}
+
+fn sha256(input: &[u8], gas: &mut Gas, _fork: Fork) -> Result<Bytes, ErrorKind> {
+    charge_per_word(gas, input, 60, 12)?;
+
+    Ok(Bytes::copy_from_slice(&Sha256::digest(input)))
+}
+
+fn ripemd160(input: &[u8], gas: &mut Gas, _fork: Fork) -> Result<Bytes, ErrorKind> {
+    charge_per_word(gas, input, 600, 120)?;
+
+    // The hash is left padded to a word.
+    let mut output = [0u8; 32];
+    output[12..].copy_from_slice(&Ripemd160::digest(input));
+    Ok(Bytes::copy_from_slice(&output))
+}
+
+fn identity(input: &[u8], gas: &mut Gas, _fork: Fork) -> Result<Bytes, ErrorKind> {
+    charge_per_word(gas, input, 15, 3)?;
+
+    Ok(Bytes::copy_from_slice(input))
+}
+
+/// `base ** exponent % modulus` over integers of any length (EIP-198), priced by EIP-2565 since
+/// Berlin.
+fn modexp(input: &[u8], gas: &mut Gas, fork: Fork) -> Result<Bytes, ErrorKind> {
+    // Lengths too large to fit are too expensive to compute anyway.
+    let length = |offset: usize| {
+        let length = U256::from_big_endian(&padded::<32>(input.get(offset..).unwrap_or(&[])));
+        if length > U256::from(u64::MAX) {
+            u64::MAX
+        } else {
+            length.as_u64()
+        }
+    };
+    let base_length = length(0);
+    let exponent_length = length(32);
+    let modulus_length = length(64);
+
+    // The iteration count depends on the first word of the exponent.
+    let exponent_offset = U256::from(96) + U256::from(base_length);
+    let mut exponent_head = vec![0u8; exponent_length.min(32) as usize];
+    copy_padded(&mut exponent_head, input, exponent_offset);
+    let exponent_head = U256::from_big_endian(&exponent_head);
+
+    let head_bits = exponent_head.bits().saturating_sub(1) as u128;
+    let iterations = if exponent_length <= 32 {
+        head_bits
+    } else {
+        8 * (exponent_length as u128 - 32) + head_bits
+    }
+    .max(1);
+
+    let length = base_length.max(modulus_length) as u128;
+    let cost = if fork >= Fork::Berlin {
+        let words = length.div_ceil(8);
+        (words.saturating_mul(words).saturating_mul(iterations) / 3).max(200)
+    } else {
+        let complexity = if length <= 64 {
+            length * length
+        } else if length <= 1024 {
+            length * length / 4 + 96 * length - 3072
+        } else {
+            (length.saturating_mul(length) / 16).saturating_add(480 * length) - 199680
+        };
+        complexity.saturating_mul(iterations) / 20
+    };
+    gas.charge(u64::try_from(cost).unwrap_or(u64::MAX))?;
+
+    if modulus_length == 0 {
+        return Ok(Bytes::new());
+    }
+
+    // Having paid for it, the lengths are small enough to read.
+    let read = |offset: U256, length: u64| {
+        let mut bytes = vec![0u8; length as usize];
+        copy_padded(&mut bytes, input, offset);
+        BigUint::from_bytes_be(&bytes)
+    };
+    let base = read(U256::from(96), base_length);
+    let exponent = read(exponent_offset, exponent_length);
+    let modulus = read(
+        exponent_offset + U256::from(exponent_length),
+        modulus_length,
+    );
+
+    let mut output = vec![0u8; modulus_length as usize];
+    if modulus != BigUint::from(0u8) {
+        let result = base.modpow(&exponent, &modulus).to_bytes_be();
+        let start = output.len() - result.len();
+        output[start..].copy_from_slice(&result);
+    }
+    Ok(Bytes::from(output))
+}
+
+/// Point of the bn254 G1 group from its coordinates, the point at infinity being (0, 0).
+fn read_g1(input: &[u8]) -> Result<G1, ErrorKind> {
+    let x = Fq::from_slice(&input[..32]).map_err(|_| ErrorKind::PrecompileFailed)?;
+    let y = Fq::from_slice(&input[32..64]).map_err(|_| ErrorKind::PrecompileFailed)?;
+
+    if x.is_zero() && y.is_zero() {
+        Ok(G1::zero())
+    } else {
+        AffineG1::new(x, y)
+            .map(G1::from)
+            .map_err(|_| ErrorKind::PrecompileFailed)
+    }
+}
+
+/// Point of the bn254 G2 group from its coordinates, the imaginary part of each first.
+fn read_g2(input: &[u8]) -> Result<G2, ErrorKind> {
+    let fq = |offset: usize| {
+        Fq::from_slice(&input[offset..offset + 32]).map_err(|_| ErrorKind::PrecompileFailed)
+    };
+    let x = Fq2::new(fq(32)?, fq(0)?);
+    let y = Fq2::new(fq(96)?, fq(64)?);
+
+    if x.is_zero() && y.is_zero() {
+        Ok(G2::zero())
+    } else {
+        AffineG2::new(x, y)
+            .map(G2::from)
+            .map_err(|_| ErrorKind::PrecompileFailed)
+    }
+}
+
+fn write_g1(point: G1) -> Bytes {
+    let mut output = [0u8; 64];
+    if let Some(point) = AffineG1::from_jacobian(point) {
+        point.x().to_big_endian(&mut output[..32]).unwrap();
+        point.y().to_big_endian(&mut output[32..]).unwrap();
+    }
+    Bytes::copy_from_slice(&output)
+}
+
+/// Addition on the bn254 curve (EIP-196), repriced in Istanbul (EIP-1108).
+fn bn_add(input: &[u8], gas: &mut Gas, fork: Fork) -> Result<Bytes, ErrorKind> {
+    gas.charge(if fork >= Fork::Istanbul { 150 } else { 500 })?;
+
+    let input = padded::<128>(input);
+    Ok(write_g1(read_g1(&input[..64])? + read_g1(&input[64..])?))
+}
+
+/// Scalar multiplication on the bn254 curve (EIP-196), repriced in Istanbul (EIP-1108).
+fn bn_mul(input: &[u8], gas: &mut Gas, fork: Fork) -> Result<Bytes, ErrorKind> {
+    gas.charge(if fork >= Fork::Istanbul { 6000 } else { 40000 })?;
+
+    let input = padded::<96>(input);
+    let scalar = Fr::from_slice(&input[64..]).map_err(|_| ErrorKind::PrecompileFailed)?;
+    Ok(write_g1(read_g1(&input[..64])? * scalar))
+}
+
+/// Pairing check on the bn254 curve (EIP-197), repriced in Istanbul (EIP-1108).
+fn bn_pairing(input: &[u8], gas: &mut Gas, fork: Fork) -> Result<Bytes, ErrorKind> {
+    let pairs = input.len() as u64 / 192;
+    gas.charge(if fork >= Fork::Istanbul {
+        45000 + 34000 * pairs
+    } else {
+        100000 + 80000 * pairs
+    })?;
+
+    if !input.len().is_multiple_of(192) {
+        return Err(ErrorKind::PrecompileFailed);
+    }
+
+    let pairs = input
+        .chunks(192)
+        .map(|pair| Ok((read_g1(&pair[..64])?, read_g2(&pair[64..])?)))
+        .collect::<Result<Vec<_>, ErrorKind>>()?;
+
+    let mut output = [0u8; 32];
+    output[31] = (substrate_bn::pairing_batch(&pairs) == Gt::one()) as u8;
+    Ok(Bytes::copy_from_slice(&output))
+}
+
+/// Initialization vector of BLAKE2b.
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// Order the message words are mixed in, by round.
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// Mixing function of BLAKE2b.
+fn blake2b_mix(v: &mut [u64; 16], [a, b, c, d]: [usize; 4], x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// Compression function F of BLAKE2b with a chosen number of rounds (EIP-152).
+fn blake2f(input: &[u8], gas: &mut Gas, _fork: Fork) -> Result<Bytes, ErrorKind> {
+    if input.len() != 213 {
+        return Err(ErrorKind::PrecompileFailed);
+    }
+
+    let rounds = u32::from_be_bytes(input[..4].try_into().unwrap());
+    gas.charge(rounds as u64)?;
+
+    let final_block = match input[212] {
+        0 => false,
+        1 => true,
+        _ => return Err(ErrorKind::PrecompileFailed),
+    };
+
+    let word = |offset: usize| u64::from_le_bytes(input[offset..offset + 8].try_into().unwrap());
+    let mut h: [u64; 8] = std::array::from_fn(|i| word(4 + 8 * i));
+    let m: [u64; 16] = std::array::from_fn(|i| word(68 + 8 * i));
+
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(&h);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+    v[12] ^= word(196);
+    v[13] ^= word(204);
+    if final_block {
+        v[14] = !v[14];
+    }
+
+    for round in 0..rounds as usize {
+        let s = &BLAKE2B_SIGMA[round % 10];
+        blake2b_mix(&mut v, [0, 4, 8, 12], m[s[0]], m[s[1]]);
+        blake2b_mix(&mut v, [1, 5, 9, 13], m[s[2]], m[s[3]]);
+        blake2b_mix(&mut v, [2, 6, 10, 14], m[s[4]], m[s[5]]);
+        blake2b_mix(&mut v, [3, 7, 11, 15], m[s[6]], m[s[7]]);
+        blake2b_mix(&mut v, [0, 5, 10, 15], m[s[8]], m[s[9]]);
+        blake2b_mix(&mut v, [1, 6, 11, 12], m[s[10]], m[s[11]]);
+        blake2b_mix(&mut v, [2, 7, 8, 13], m[s[12]], m[s[13]]);
+        blake2b_mix(&mut v, [3, 4, 9, 14], m[s[14]], m[s[15]]);
+    }
+
+    for i in 0..8 {
+        h[i] ^= v[i] ^ v[i + 8];
+    }
+
+    Ok(h.iter().flat_map(|word| word.to_le_bytes()).collect())
+}
+
+/// Field elements in a blob and the modulus of the BLS12-381 scalar field, returned by a
+/// successful point evaluation.
+const POINT_EVALUATION_OUTPUT: [u8; 64] = {
+    let mut output = [0u8; 64];
+    output[30] = 0x10;
+    let modulus = [
+        0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8,
+        0x05, 0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00,
+        0x00, 0x01,
+    ];
+    let mut i = 0;
+    while i < 32 {
+        output[32 + i] = modulus[i];
+        i += 1;
+    }
+    output
+};
+
+/// Check that a blob committed to by a versioned hash evaluates to `y` at `z` (EIP-4844).
+fn point_evaluation(input: &[u8], gas: &mut Gas, _fork: Fork) -> Result<Bytes, ErrorKind> {
+    gas.charge(50000)?;
+
+    if input.len() != 192 {
+        return Err(ErrorKind::PrecompileFailed);
+    }
+
+    let commitment = &input[96..144];
+    let mut versioned_hash: [u8; 32] = Sha256::digest(commitment).into();
+    versioned_hash[0] = 0x01;
+    if versioned_hash[..] != input[..32] {
+        return Err(ErrorKind::PrecompileFailed);
+    }
+
+    let bytes32 =
+        |bytes: &[u8]| Bytes32::from_bytes(bytes).map_err(|_| ErrorKind::PrecompileFailed);
+    let bytes48 =
+        |bytes: &[u8]| Bytes48::from_bytes(bytes).map_err(|_| ErrorKind::PrecompileFailed);
+    let verified = c_kzg::ethereum_kzg_settings(0).verify_kzg_proof(
+        &bytes48(commitment)?,
+        &bytes32(&input[32..64])?,
+        &bytes32(&input[64..96])?,
+        &bytes48(&input[144..])?,
+    );
+
+    match verified {
+        Ok(true) => Ok(Bytes::from_static(&POINT_EVALUATION_OUTPUT)),
+        _ => Err(ErrorKind::PrecompileFailed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(address: u64, input: &str, fork: Fork) -> (Result<Bytes, ErrorKind>, u64) {
+        let precompiles = Precompiles::new(fork);
+        let Some(Entry::Stateless(precompile)) = precompiles.get(H160::from_low_u64_be(address))
+        else {
+            panic!("no precompile at {}", address);
+        };
+        let result = precompile.run(&hex::decode(input).unwrap(), 100_000_000);
+
+        match result {
+            Ok(output) => (Ok(output.output), output.gas_used),
+            Err(kind) => (Err(kind), 100_000_000),
+        }
+    }
+
+    fn output(address: u64, input: &str) -> String {
+        hex::encode(run(address, input, Fork::default()).0.unwrap())
+    }
+
+    #[test]
+    fn available_by_fork() {
+        let precompiles = |fork| Precompiles::new(fork).addresses().count();
+
+        assert_eq!(precompiles(Fork::Frontier), 4);
+        assert_eq!(precompiles(Fork::Byzantium), 8);
+        assert_eq!(precompiles(Fork::Istanbul), 9);
+        assert_eq!(precompiles(Fork::Prague), 10);
+        assert!(!Precompiles::new(Fork::Homestead).contains(H160::from_low_u64_be(5)));
+    }
+
+    #[test]
+    fn custom_precompiles_override_standard_ones() {
+        let mut precompiles = Precompiles::new(Fork::default());
+        precompiles.insert(H160::from_low_u64_be(4), |_: &[u8], _: u64| {
+            Err(ErrorKind::PrecompileFailed)
+        });
+        precompiles.insert(H160::from_low_u64_be(0x0100), |input: &[u8], _: u64| {
+            Ok(PrecompileOutput {
+                output: Bytes::copy_from_slice(input),
+                gas_used: 1,
+            })
+        });
+        assert!(precompiles.remove(H160::from_low_u64_be(1)));
+
+        assert_eq!(precompiles.addresses().count(), 10);
+        assert!(!precompiles.contains(H160::from_low_u64_be(1)));
+        let Some(Entry::Stateless(identity)) = precompiles.get(H160::from_low_u64_be(4)) else {
+            panic!("identity is registered");
+        };
+        assert_eq!(identity.run(&[], 100), Err(ErrorKind::PrecompileFailed));
+    }
+
+    #[test]
+    fn ecrecover_recovers_the_signer() {
+        let input = |v: &str| {
+            [
+                "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53",
+                v,
+                "28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276",
+                "67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83",
+            ]
+            .concat()
+        };
+
+        assert_eq!(
+            output(1, &input(&format!("{:064x}", 27))),
+            "0000000000000000000000009d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"
+        );
+        // Any other v is invalid, which isn't an error.
+        assert_eq!(output(1, &input(&format!("{:064x}", 1))), "");
+    }
+
+    #[test]
+    fn hashes_and_identity() {
+        assert_eq!(
+            run(2, "", Fork::default()),
+            (
+                Ok(Bytes::from(
+                    hex::decode("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+                        .unwrap()
+                )),
+                60
+            )
+        );
+        assert_eq!(
+            output(3, ""),
+            "0000000000000000000000009c1185a5c5e9fc54612808977ee8f548b2258d31"
+        );
+        assert_eq!(run(4, "0102", Fork::default()).1, 18);
+        assert_eq!(output(4, "0102"), "0102");
+    }
+
+    #[test]
+    fn modexp_priced_by_fork() {
+        // EIP-198 example: 3 ** (p - 1) % p for the secp256k1 field prime p.
+        let input = "0000000000000000000000000000000000000000000000000000000000000001\
+                     0000000000000000000000000000000000000000000000000000000000000020\
+                     0000000000000000000000000000000000000000000000000000000000000020\
+                     03\
+                     fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e\
+                     fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f";
+
+        assert_eq!(
+            output(5, input),
+            "0000000000000000000000000000000000000000000000000000000000000001"
+        );
+        assert_eq!(run(5, input, Fork::Byzantium).1, 13056);
+        assert_eq!(run(5, input, Fork::Berlin).1, 1360);
+
+        // A zero modulus gives zero.
+        assert_eq!(
+            output(5, &input[..input.len() - 64]),
+            "0000000000000000000000000000000000000000000000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn modexp_with_huge_lengths_runs_out_of_gas() {
+        let input = "0000000000000000000000000000000000000000000000000000000000000001\
+                     ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\
+                     0000000000000000000000000000000000000000000000000000000000000001";
+
+        assert_eq!(run(5, input, Fork::default()).0, Err(ErrorKind::OutOfGas));
+    }
+
+    #[test]
+    fn bn_add_and_mul() {
+        let add = "18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9\
+                   063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266\
+                   07c2b7f58a84bd6145f00c9c2bc0bb1a187f20ff2c92963a88019e7c6a014eed\
+                   06614e20c147e940f2d70da3f74c9a17df361706a4485c742bd6788478fa17d7";
+        assert_eq!(
+            output(6, add),
+            "2243525c5efd4b9c3d3c45ac0ca3fe4dd85e830a4ce6b65fa1eeaee202839703\
+             301d1d33be6da8e509df21cc35964723180eed7532537db9ae5e7d48f195c915"
+        );
+        assert_eq!(run(6, add, Fork::Byzantium).1, 500);
+        assert_eq!(run(6, add, Fork::Istanbul).1, 150);
+
+        let mul = "2bd3e6d0f3b142924f5ca7b49ce5b9d54c4703d7ae5648e61d02268b1a0a9fb7\
+                   21611ce0a6af85915e2f1d70300909ce2e49dfad4a4619c8390cae66cefdb204\
+                   00000000000000000000000000000000000000000000000011138ce750fa15c2";
+        assert_eq!(
+            output(7, mul),
+            "070a8d6a982153cae4be29d434e8faef8a47b274a053f5a4ee2a6c9c13c31e5c\
+             031b8ce914eba3a9ffb989f9cdd5b0f01943074bf4f0f315690ec3cec6981afc"
+        );
+        assert_eq!(output(7, ""), "0".repeat(128));
+
+        // A point off the curve.
+        assert_eq!(
+            run(6, &"11".repeat(64), Fork::default()).0,
+            Err(ErrorKind::PrecompileFailed)
+        );
+    }
+
+    #[test]
+    fn bn_pairing() {
+        let input = "1c76476f4def4bb94541d57ebba1193381ffa7aa76ada664dd31c16024c43f59\
+                     3034dd2920f673e204fee2811c678745fc819b55d3e9d294e45c9b03a76aef41\
+                     209dd15ebff5d46c4bd888e51a93cf99a7329636c63514396b4a452003a35bf7\
+                     04bf11ca01483bfa8b34b43561848d28905960114c8ac04049af4b6315a41678\
+                     2bb8324af6cfc93537a2ad1a445cfd0ca2a71acd7ac41fadbf933c2a51be344d\
+                     120a2a4cf30c1bf9845f20c6fe39e07ea2cce61f0c9bb048165fe5e4de877550\
+                     111e129f1cf1097710d41c4ac70fcdfa5ba2023c6ff1cbeac322de49d1b6df7c\
+                     2032c61a830e3c17286de9462bf242fca2883585b93870a73853face6a6bf411\
+                     198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+                     1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+                     090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
+                     12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa";
+        let one = "0000000000000000000000000000000000000000000000000000000000000001";
+
+        assert_eq!(output(8, input), one);
+        assert_eq!(run(8, input, Fork::Byzantium).1, 260000);
+        assert_eq!(run(8, input, Fork::Istanbul).1, 113000);
+        assert_eq!(output(8, ""), one);
+        assert_eq!(
+            run(8, &input[2..], Fork::default()).0,
+            Err(ErrorKind::PrecompileFailed)
+        );
+    }
+
+    #[test]
+    fn blake2f_compresses() {
+        // Final block of BLAKE2b-512 of "abc" (EIP-152 test vector 5).
+        let input = "0000000c\
+                     48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+                     d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b\
+                     6162630000000000000000000000000000000000000000000000000000000000\
+                     0000000000000000000000000000000000000000000000000000000000000000\
+                     0000000000000000000000000000000000000000000000000000000000000000\
+                     0000000000000000000000000000000000000000000000000000000000000000\
+                     0300000000000000\
+                     0000000000000000\
+                     01";
+
+        assert_eq!(
+            output(9, input),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+        assert_eq!(run(9, input, Fork::default()).1, 12);
+        assert_eq!(
+            run(
+                9,
+                &format!("{}02", &input[..input.len() - 2]),
+                Fork::default()
+            )
+            .0,
+            Err(ErrorKind::PrecompileFailed)
+        );
+    }
+
+    #[test]
+    fn point_evaluation_verifies_proof() {
+        // Correct proof from the c-kzg-4844 verify_kzg_proof tests.
+        let commitment = "8f59a8d2a1a625a17f3fea0fe5eb8c896db3764f3185481bc22f91b4aaffcca25f26936857bc3a7c2539ea8ec3a952b7";
+        let mut versioned_hash = Sha256::digest(hex::decode(commitment).unwrap());
+        versioned_hash[0] = 0x01;
+        let input = [
+            &hex::encode(versioned_hash),
+            "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000000",
+            "1522a4a7f34e1ea350ae07c29c96c7e79655aa926122e95fe69fcbd932ca49e9",
+            commitment,
+            "a62ad71d14c5719385c0686f1871430475bf3a00f0aa3f7b8dd99a9abc2160744faf0070725e00b60ad9a026a15b1a8c",
+        ]
+        .concat();
+
+        assert_eq!(
+            output(10, &input),
+            "0000000000000000000000000000000000000000000000000000000000001000\
+             73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001"
+        );
+
+        // A different evaluation doesn't verify.
+        let wrong = input.replace("1522a4a7", "1522a4a8");
+        assert_eq!(
+            run(10, &wrong, Fork::default()).0,
+            Err(ErrorKind::PrecompileFailed)
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use crate::error::Error;
+use crate::journal::Log;
+use bytes::Bytes;
+use ethereum_types::H160;
+
+/// Why an execution halted without error.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+pub enum SuccessReason {
+    /// `STOP`.
+    Stop,
+    /// `RETURN`.
+    Return,
+    /// `SELFDESTRUCT`.
+    SelfDestruct,
+    /// Ran past the end of the code, which behaves like `STOP`.
+    EndOfCode,
+}
+
+/// How an execution ended.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+pub enum ExecutionStatus {
+    /// Halted normally, keeping its changes.
+    Success(SuccessReason),
+    /// Halted with `REVERT`, undoing its changes and handing back the gas left.
+    Revert,
+    /// Halted with an exceptional error, undoing its changes and using up all the gas.
+    Halt(Error),
+}
+
+impl ExecutionStatus {
+    pub fn is_success(&self) -> bool {
+        matches!(self, ExecutionStatus::Success(_))
+    }
+}
+
+/// Outcome of executing a message or a transaction.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct ExecutionResult {
+    pub status: ExecutionStatus,
+
+    /// Output of `RETURN`, the code deployed by a creation or the revert reason.
+    pub output: Bytes,
+
+    /// Gas used, for a transaction the gas paid for after refunds.
+    pub gas_used: u64,
+
+    /// Gas refunded for clearing storage.
+    ///
+    /// For a transaction, already taken off `gas_used` and capped to a fifth of it (EIP-3529).
+    pub gas_refunded: u64,
+
+    /// Logs emitted by a successful execution.
+    pub logs: Vec<Log>,
+
+    /// Address of the contract created by a successful creation.
+    pub created_address: Option<H160>,
+}
+
+impl ExecutionResult {
+    pub fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+}
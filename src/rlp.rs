@@ -0,0 +1,469 @@
+use bytes::Bytes;
+use ethereum_types::{Bloom, H160, H256, U256};
+use std::fmt;
+
+/// Reason RLP input couldn't be decoded.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The input ends before the item does.
+    InputTooShort,
+    /// Bytes are left over after the item.
+    TrailingBytes,
+    /// A single byte below `0x80` encoded as a string instead of as itself.
+    NonCanonicalSingleByte,
+    /// A length below 56 encoded in the long form.
+    NonCanonicalSize,
+    /// An integer or a length with leading zero bytes.
+    LeadingZero,
+    /// An integer too large for the type decoded into.
+    Overflow,
+    /// A list where a string was expected.
+    UnexpectedList,
+    /// A string where a list was expected.
+    UnexpectedString,
+    /// A string of the wrong length for a fixed size type.
+    InvalidLength,
+    /// Well-formed RLP that doesn't hold a valid value of the type decoded into.
+    Custom(&'static str),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DecodeError::InputTooShort => "input too short",
+            DecodeError::TrailingBytes => "trailing bytes after item",
+            DecodeError::NonCanonicalSingleByte => "non-canonical single byte",
+            DecodeError::NonCanonicalSize => "non-canonical size",
+            DecodeError::LeadingZero => "leading zero",
+            DecodeError::Overflow => "integer overflow",
+            DecodeError::UnexpectedList => "unexpected list",
+            DecodeError::UnexpectedString => "unexpected string",
+            DecodeError::InvalidLength => "invalid length",
+            DecodeError::Custom(message) => message,
+        })
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Prefix of an item giving its kind and the length of its payload.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct Header {
+    /// True for a list, false for a string.
+    pub list: bool,
+
+    /// Length of the payload in bytes.
+    pub payload_length: usize,
+}
+
+impl Header {
+    /// Append the prefix to `out`.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let offset = if self.list { 0xc0 } else { 0x80 };
+
+        if self.payload_length < 56 {
+            out.push(offset + self.payload_length as u8);
+        } else {
+            let bytes = self.payload_length.to_be_bytes();
+            let length = strip_leading_zeros(&bytes);
+
+            out.push(offset + 55 + length.len() as u8);
+            out.extend_from_slice(length);
+        }
+    }
+
+    /// Decode the prefix at the front of `buf`, advancing to the payload.
+    ///
+    /// A single byte below `0x80` is its own payload, so `buf` is left as is.
+    pub fn decode(buf: &mut &[u8]) -> Result<Header, DecodeError> {
+        let prefix = *buf.first().ok_or(DecodeError::InputTooShort)?;
+
+        let header = match prefix {
+            0x00..=0x7f => {
+                return Ok(Header {
+                    list: false,
+                    payload_length: 1,
+                })
+            }
+            0x80..=0xb7 => {
+                *buf = &buf[1..];
+                let payload_length = (prefix - 0x80) as usize;
+
+                if payload_length == 1 && buf.first().is_some_and(|byte| *byte < 0x80) {
+                    return Err(DecodeError::NonCanonicalSingleByte);
+                }
+
+                Header {
+                    list: false,
+                    payload_length,
+                }
+            }
+            0xb8..=0xbf => {
+                *buf = &buf[1..];
+                Header {
+                    list: false,
+                    payload_length: decode_long_length(buf, (prefix - 0xb7) as usize)?,
+                }
+            }
+            0xc0..=0xf7 => {
+                *buf = &buf[1..];
+                Header {
+                    list: true,
+                    payload_length: (prefix - 0xc0) as usize,
+                }
+            }
+            0xf8..=0xff => {
+                *buf = &buf[1..];
+                Header {
+                    list: true,
+                    payload_length: decode_long_length(buf, (prefix - 0xf7) as usize)?,
+                }
+            }
+        };
+
+        if buf.len() < header.payload_length {
+            return Err(DecodeError::InputTooShort);
+        }
+
+        Ok(header)
+    }
+}
+
+/// Decode the length following a long form prefix.
+fn decode_long_length(buf: &mut &[u8], length_of_length: usize) -> Result<usize, DecodeError> {
+    if buf.len() < length_of_length {
+        return Err(DecodeError::InputTooShort);
+    }
+
+    let (bytes, rest) = buf.split_at(length_of_length);
+    if bytes[0] == 0 {
+        return Err(DecodeError::LeadingZero);
+    }
+    if bytes.len() > std::mem::size_of::<usize>() {
+        return Err(DecodeError::InputTooShort);
+    }
+
+    let length = bytes
+        .iter()
+        .fold(0usize, |length, byte| length << 8 | *byte as usize);
+    if length < 56 {
+        return Err(DecodeError::NonCanonicalSize);
+    }
+
+    *buf = rest;
+    Ok(length)
+}
+
+fn strip_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let leading_zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+    &bytes[leading_zeros..]
+}
+
+/// Type that can be RLP encoded.
+pub trait Encodable {
+    /// Append the encoding of `self` to `out`.
+    fn encode(&self, out: &mut Vec<u8>);
+}
+
+/// Type that can be decoded from RLP.
+pub trait Decodable: Sized {
+    /// Decode the item at the front of `buf`, advancing past it.
+    fn decode(buf: &mut &[u8]) -> Result<Self, DecodeError>;
+}
+
+/// Encoding of `value`.
+pub fn encode<T: Encodable + ?Sized>(value: &T) -> Vec<u8> {
+    let mut out = Vec::new();
+    value.encode(&mut out);
+    out
+}
+
+/// Decode `bytes` as a single item, failing if anything is left over.
+pub fn decode<T: Decodable>(mut bytes: &[u8]) -> Result<T, DecodeError> {
+    let value = T::decode(&mut bytes)?;
+
+    if !bytes.is_empty() {
+        return Err(DecodeError::TrailingBytes);
+    }
+
+    Ok(value)
+}
+
+/// Append a list holding `items` to `out`, which is how structs are encoded.
+pub fn encode_list(items: &[&dyn Encodable], out: &mut Vec<u8>) {
+    let mut payload = Vec::new();
+    for item in items {
+        item.encode(&mut payload);
+    }
+
+    Header {
+        list: true,
+        payload_length: payload.len(),
+    }
+    .encode(out);
+    out.extend(payload);
+}
+
+/// Decode the string at the front of `buf`, returning its payload.
+pub fn decode_bytes<'a>(buf: &mut &'a [u8]) -> Result<&'a [u8], DecodeError> {
+    let header = Header::decode(buf)?;
+    if header.list {
+        return Err(DecodeError::UnexpectedList);
+    }
+
+    let (payload, rest) = buf.split_at(header.payload_length);
+    *buf = rest;
+    Ok(payload)
+}
+
+/// Decode the list at the front of `buf`, returning its payload for the items to be decoded from.
+pub fn decode_list_payload<'a>(buf: &mut &'a [u8]) -> Result<&'a [u8], DecodeError> {
+    let header = Header::decode(buf)?;
+    if !header.list {
+        return Err(DecodeError::UnexpectedString);
+    }
+
+    let (payload, rest) = buf.split_at(header.payload_length);
+    *buf = rest;
+    Ok(payload)
+}
+
+/// Decode the list at the front of `buf` with `f`, which must consume the whole payload.
+pub fn decode_list<T>(
+    buf: &mut &[u8],
+    f: impl FnOnce(&mut &[u8]) -> Result<T, DecodeError>,
+) -> Result<T, DecodeError> {
+    let mut payload = decode_list_payload(buf)?;
+    let value = f(&mut payload)?;
+
+    if !payload.is_empty() {
+        return Err(DecodeError::TrailingBytes);
+    }
+
+    Ok(value)
+}
+
+impl Encodable for [u8] {
+    fn encode(&self, out: &mut Vec<u8>) {
+        if let [byte] = self {
+            if *byte < 0x80 {
+                out.push(*byte);
+                return;
+            }
+        }
+
+        Header {
+            list: false,
+            payload_length: self.len(),
+        }
+        .encode(out);
+        out.extend_from_slice(self);
+    }
+}
+
+impl Encodable for str {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.as_bytes().encode(out)
+    }
+}
+
+impl Encodable for Bytes {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self[..].encode(out)
+    }
+}
+
+impl Decodable for Bytes {
+    fn decode(buf: &mut &[u8]) -> Result<Bytes, DecodeError> {
+        decode_bytes(buf).map(Bytes::copy_from_slice)
+    }
+}
+
+impl Encodable for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u8).encode(out)
+    }
+}
+
+impl Decodable for bool {
+    fn decode(buf: &mut &[u8]) -> Result<bool, DecodeError> {
+        match u8::decode(buf)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::Overflow),
+        }
+    }
+}
+
+/// Decode an integer of at most `N` bytes, rejecting leading zeros.
+fn decode_uint<const N: usize>(buf: &mut &[u8]) -> Result<[u8; N], DecodeError> {
+    let bytes = decode_bytes(buf)?;
+
+    if bytes.len() > N {
+        return Err(DecodeError::Overflow);
+    }
+    if bytes.first() == Some(&0) {
+        return Err(DecodeError::LeadingZero);
+    }
+
+    let mut padded = [0u8; N];
+    padded[N - bytes.len()..].copy_from_slice(bytes);
+    Ok(padded)
+}
+
+macro_rules! impl_uint {
+    ($($ty:ty),*) => {
+        $(
+            impl Encodable for $ty {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    strip_leading_zeros(&self.to_be_bytes()).encode(out)
+                }
+            }
+
+            impl Decodable for $ty {
+                fn decode(buf: &mut &[u8]) -> Result<$ty, DecodeError> {
+                    decode_uint(buf).map(<$ty>::from_be_bytes)
+                }
+            }
+        )*
+    };
+}
+
+impl_uint!(u8, u16, u32, u64, u128, usize);
+
+impl Encodable for U256 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        let mut bytes = [0u8; 32];
+        self.to_big_endian(&mut bytes);
+
+        strip_leading_zeros(&bytes).encode(out)
+    }
+}
+
+impl Decodable for U256 {
+    fn decode(buf: &mut &[u8]) -> Result<U256, DecodeError> {
+        decode_uint::<32>(buf).map(|bytes| U256::from_big_endian(&bytes))
+    }
+}
+
+macro_rules! impl_hash {
+    ($($ty:ty),*) => {
+        $(
+            impl Encodable for $ty {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    self.as_bytes().encode(out)
+                }
+            }
+
+            impl Decodable for $ty {
+                fn decode(buf: &mut &[u8]) -> Result<$ty, DecodeError> {
+                    let bytes = decode_bytes(buf)?;
+
+                    if bytes.len() != <$ty>::len_bytes() {
+                        return Err(DecodeError::InvalidLength);
+                    }
+
+                    Ok(<$ty>::from_slice(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_hash!(H160, H256, Bloom);
+
+/// Vectors are encoded as lists, use `Bytes` or `[u8]` for byte strings.
+impl<T: Encodable> Encodable for Vec<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        let items: Vec<&dyn Encodable> = self.iter().map(|item| item as &dyn Encodable).collect();
+        encode_list(&items, out)
+    }
+}
+
+impl<T: Decodable> Decodable for Vec<T> {
+    fn decode(buf: &mut &[u8]) -> Result<Vec<T>, DecodeError> {
+        let mut payload = decode_list_payload(buf)?;
+
+        let mut items = Vec::new();
+        while !payload.is_empty() {
+            items.push(T::decode(&mut payload)?);
+        }
+
+        Ok(items)
+    }
+}
+
+impl<T: Encodable + ?Sized> Encodable for &T {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (**self).encode(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers_are_minimal() {
+        assert_eq!(encode(&0u64), vec![0x80]);
+        assert_eq!(encode(&0x7fu64), vec![0x7f]);
+        assert_eq!(encode(&0x80u64), vec![0x81, 0x80]);
+        assert_eq!(encode(&0x0400u16), vec![0x82, 0x04, 0x00]);
+        assert_eq!(encode(&U256::MAX), [&[0xa0][..], &[0xff; 32]].concat());
+
+        assert_eq!(decode::<u64>(&[0x80]), Ok(0));
+        assert_eq!(decode::<u64>(&[0x82, 0x04, 0x00]), Ok(0x0400));
+        assert_eq!(decode::<U256>(&encode(&U256::MAX)), Ok(U256::MAX));
+    }
+
+    #[test]
+    fn integers_reject_leading_zeros() {
+        assert_eq!(decode::<u64>(&[0x00]), Err(DecodeError::LeadingZero));
+        assert_eq!(
+            decode::<u64>(&[0x82, 0x00, 0x80]),
+            Err(DecodeError::LeadingZero)
+        );
+        assert_eq!(
+            decode::<U256>(&[0x82, 0x00, 0x80]),
+            Err(DecodeError::LeadingZero)
+        );
+    }
+
+    #[test]
+    fn integers_reject_overflow() {
+        assert_eq!(
+            decode::<u8>(&[0x82, 0x01, 0x00]),
+            Err(DecodeError::Overflow)
+        );
+        assert_eq!(
+            decode::<U256>(&[&[0xa1, 0x01][..], &[0x00; 32]].concat()),
+            Err(DecodeError::Overflow)
+        );
+    }
+
+    #[test]
+    fn hashes_have_fixed_length() {
+        let address = H160::repeat_byte(0x35);
+
+        assert_eq!(decode::<H160>(&encode(&address)), Ok(address));
+        assert_eq!(
+            decode::<H256>(&encode(&address)),
+            Err(DecodeError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn lists_of_items() {
+        let values = vec![1u64, 2, 0x400];
+        let encoded = encode(&values);
+
+        assert_eq!(encoded, vec![0xc5, 0x01, 0x02, 0x82, 0x04, 0x00]);
+        assert_eq!(decode::<Vec<u64>>(&encoded), Ok(values));
+        assert_eq!(
+            decode::<Vec<u64>>(&[0x82, 0x04, 0x00]),
+            Err(DecodeError::UnexpectedString)
+        );
+        assert_eq!(decode::<u64>(&encoded), Err(DecodeError::UnexpectedList));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::hash::keccak256;
+use ethereum_types::{H160, H256, U256};
+use k256::ecdsa::{self, RecoveryId, VerifyingKey};
+use std::fmt;
+
+/// Half the order of the secp256k1 curve, the largest `s` allowed since EIP-2.
+const SECP256K1_HALF_ORDER: U256 = U256([
+    0xdfe92f46681b20a0,
+    0x5d576e7357a4501d,
+    0xffffffffffffffff,
+    0x7fffffffffffffff,
+]);
+
+/// Reason a signature is invalid.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+#[non_exhaustive]
+pub enum SignatureError {
+    /// `r` or `s` is zero or not below the order of the curve.
+    OutOfRange,
+    /// `s` is in the upper half of the order of the curve (EIP-2).
+    HighS,
+    /// No public key can be recovered from the signature.
+    RecoveryFailed,
+    /// The y parity is neither 0 nor 1.
+    InvalidParity,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SignatureError::OutOfRange => "signature value out of range",
+            SignatureError::HighS => "signature s value too high",
+            SignatureError::RecoveryFailed => "public key recovery failed",
+            SignatureError::InvalidParity => "invalid signature y parity",
+        })
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// ECDSA signature over secp256k1.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct Signature {
+    /// Parity of the y coordinate of the curve point `r` is the x coordinate of.
+    ///
+    /// Kept as decoded, anything other than 0 or 1 fails recovery.
+    pub y_parity: u8,
+    pub r: U256,
+    pub s: U256,
+}
+
+impl Signature {
+    /// `v` of a legacy transaction, which also holds the chain id since EIP-155.
+    pub(crate) fn legacy_v(&self, chain_id: Option<u64>) -> u64 {
+        let offset = match chain_id {
+            Some(chain_id) => chain_id * 2 + 35,
+            None => 27,
+        };
+
+        offset + u64::from(self.y_parity)
+    }
+
+    /// Address of the account whose key signed `hash`.
+    pub fn recover_address(&self, hash: H256) -> Result<H160, SignatureError> {
+        if self.s > SECP256K1_HALF_ORDER {
+            return Err(SignatureError::HighS);
+        }
+
+        self.recover_address_allowing_high_s(hash)
+    }
+
+    /// Address of the account whose key signed `hash`, accepting any `s` below the order of the
+    /// curve as the ecrecover precompile does.
+    pub(crate) fn recover_address_allowing_high_s(
+        &self,
+        hash: H256,
+    ) -> Result<H160, SignatureError> {
+        let y_parity = match self.y_parity {
+            0 | 1 => self.y_parity == 1,
+            _ => return Err(SignatureError::InvalidParity),
+        };
+
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        self.r.to_big_endian(&mut r);
+        self.s.to_big_endian(&mut s);
+
+        let signature =
+            ecdsa::Signature::from_scalars(r, s).map_err(|_| SignatureError::OutOfRange)?;
+
+        // Recovery only takes low `s`, negating `s` flips the parity of the point.
+        let (signature, y_parity) = match signature.normalize_s() {
+            Some(normalized) => (normalized, !y_parity),
+            None => (signature, y_parity),
+        };
+        let recovery_id = RecoveryId::new(y_parity, false);
+
+        let key = VerifyingKey::recover_from_prehash(hash.as_bytes(), &signature, recovery_id)
+            .map_err(|_| SignatureError::RecoveryFailed)?;
+
+        // The address is the end of the hash of the uncompressed key, without its prefix.
+        let point = key.to_encoded_point(false);
+        Ok(H160::from_slice(
+            &keccak256(&point.as_bytes()[1..]).as_bytes()[12..],
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn signature() -> Signature {
+        // Signature of the EIP-155 example transaction.
+        Signature {
+            y_parity: 0,
+            r: U256::from_str("28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276")
+                .unwrap(),
+            s: U256::from_str("67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83")
+                .unwrap(),
+        }
+    }
+
+    fn hash() -> H256 {
+        H256::from_str("daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53").unwrap()
+    }
+
+    #[test]
+    fn recovers_the_signer() {
+        assert_eq!(
+            signature().recover_address(hash()),
+            Ok(H160::from_str("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f").unwrap())
+        );
+    }
+
+    #[test]
+    fn rejects_high_s() {
+        let mut signature = signature();
+        signature.s = SECP256K1_HALF_ORDER + 1;
+
+        assert_eq!(
+            signature.recover_address(hash()),
+            Err(SignatureError::HighS)
+        );
+    }
+
+    #[test]
+    fn rejects_zero_r() {
+        let mut signature = signature();
+        signature.r = U256::zero();
+
+        assert_eq!(
+            signature.recover_address(hash()),
+            Err(SignatureError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_parity() {
+        let mut signature = signature();
+        signature.y_parity = 2;
+
+        assert_eq!(
+            signature.recover_address(hash()),
+            Err(SignatureError::InvalidParity)
+        );
+    }
+}
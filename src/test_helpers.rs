@@ -0,0 +1,22 @@
+use ethereum_types::U256;
+use num_bigint::BigUint;
+use proptest::prelude::*;
+
+pub fn to_big(value: U256) -> BigUint {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    BigUint::from_bytes_be(&bytes)
+}
+
+pub fn from_big(value: BigUint) -> U256 {
+    U256::from_big_endian(&value.to_bytes_be())
+}
+
+/// Values biased towards the edges of the 256-bit range where overflow happens.
+pub fn word() -> impl Strategy<Value = U256> {
+    prop_oneof![
+        any::<[u64; 4]>().prop_map(U256),
+        any::<u64>().prop_map(U256::from),
+        any::<u64>().prop_map(|value| U256::MAX - U256::from(value)),
+    ]
+}
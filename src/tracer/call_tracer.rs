@@ -0,0 +1,376 @@
+use super::{hex_bytes, hex_u64, kind_name, revert_reason, status_error};
+use crate::database::Database;
+use crate::env::Env;
+use crate::inspector::{CallOutcome, Inspector};
+use crate::journal::{JournaledState, Log};
+use crate::message::{CallKind, Message};
+use crate::result::{ExecutionResult, ExecutionStatus};
+use crate::transaction::Transaction;
+use bytes::Bytes;
+use ethereum_types::{H160, H256, U256};
+use serde::Serialize;
+
+/// Log emitted by a call, as in geth's `callTracer` output.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+pub struct CallLog {
+    pub address: H160,
+    pub topics: Vec<H256>,
+
+    #[serde(serialize_with = "hex_bytes")]
+    pub data: Bytes,
+
+    /// Number of calls the frame made before emitting the log.
+    #[serde(serialize_with = "hex_usize")]
+    pub position: usize,
+}
+
+/// Call or creation in the call tree built by `CallTracer`, laid out like geth's `callTracer`
+/// output.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    pub from: H160,
+
+    #[serde(serialize_with = "hex_u64")]
+    pub gas: u64,
+
+    #[serde(serialize_with = "hex_u64")]
+    pub gas_used: u64,
+
+    /// Account called or created, `None` for a failed creation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<H160>,
+
+    #[serde(serialize_with = "hex_bytes")]
+    pub input: Bytes,
+
+    /// Output of a successful or reverted call, the code deployed by a creation.
+    #[serde(skip_serializing_if = "Bytes::is_empty", serialize_with = "hex_bytes")]
+    pub output: Bytes,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Message of a revert with `Error(string)`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallFrame>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<CallLog>,
+
+    /// Value sent, `None` for a static call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<U256>,
+
+    /// `CALL`, `CREATE2` or `SELFDESTRUCT` for example.
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+impl CallFrame {
+    /// Drop the logs of the frame and of the calls it made, which a failure undid.
+    fn clear_logs(&mut self) {
+        self.logs.clear();
+        for call in &mut self.calls {
+            call.clear_logs();
+        }
+    }
+}
+
+/// Inspector building the tree of calls and creations made by an execution, as geth's
+/// `callTracer` does.
+#[derive(Debug, Default)]
+pub struct CallTracer {
+    with_logs: bool,
+
+    /// Calls running, the outermost first.
+    stack: Vec<CallFrame>,
+
+    /// Gas limit of the transaction being executed.
+    gas_limit: Option<u64>,
+
+    root: Option<CallFrame>,
+}
+
+impl CallTracer {
+    pub fn new() -> CallTracer {
+        CallTracer::default()
+    }
+
+    /// Record the logs emitted by each call.
+    pub fn with_logs(mut self, enabled: bool) -> CallTracer {
+        self.with_logs = enabled;
+        self
+    }
+
+    /// The outermost call, `None` if it hasn't ended.
+    pub fn result(&self) -> Option<&CallFrame> {
+        self.root.as_ref()
+    }
+
+    /// Give up the tracer, returning the outermost call.
+    pub fn into_result(self) -> Option<CallFrame> {
+        self.root
+    }
+
+    fn enter(&mut self, message: &Message) {
+        let (from, to) = match message.kind {
+            CallKind::CallCode => (message.caller, message.code_address),
+            CallKind::DelegateCall => (message.address, message.code_address),
+            _ => (message.caller, message.address),
+        };
+        let input = if message.kind.is_create() {
+            &message.code
+        } else {
+            &message.data
+        };
+        let gas = match self.gas_limit {
+            Some(gas_limit) if self.stack.is_empty() => gas_limit,
+            _ => message.gas,
+        };
+
+        self.stack.push(CallFrame {
+            from,
+            gas,
+            gas_used: 0,
+            to: Some(to),
+            input: input.clone(),
+            output: Bytes::new(),
+            error: None,
+            revert_reason: None,
+            calls: Vec::new(),
+            logs: Vec::new(),
+            value: (message.kind != CallKind::StaticCall).then_some(message.value),
+            kind: kind_name(message.kind).to_string(),
+        });
+    }
+
+    fn exit(&mut self, message: &Message, outcome: &CallOutcome<'_>) {
+        let Some(mut frame) = self.stack.pop() else {
+            return;
+        };
+        frame.gas_used = outcome.gas_used;
+        frame.error = status_error(&outcome.status);
+
+        match outcome.status {
+            ExecutionStatus::Success(_) => frame.output = Bytes::copy_from_slice(outcome.output),
+            ExecutionStatus::Revert => {
+                frame.output = Bytes::copy_from_slice(outcome.output);
+                frame.revert_reason = revert_reason(outcome.output);
+            }
+            ExecutionStatus::Halt(_) => {}
+        }
+        if !outcome.status.is_success() {
+            frame.clear_logs();
+            if message.kind.is_create() {
+                frame.to = None;
+            }
+        }
+
+        match self.stack.last_mut() {
+            Some(parent) => parent.calls.push(frame),
+            None => self.root = Some(frame),
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for CallTracer {
+    fn transaction(
+        &mut self,
+        _env: &Env,
+        _sender: H160,
+        transaction: &Transaction,
+        _state: &JournaledState<DB>,
+    ) {
+        self.gas_limit = Some(transaction.gas_limit());
+    }
+
+    fn transaction_end(&mut self, result: &ExecutionResult, _state: &JournaledState<DB>) {
+        if let Some(root) = &mut self.root {
+            root.gas_used = result.gas_used;
+        }
+    }
+
+    fn call(&mut self, message: &Message, _state: &JournaledState<DB>) {
+        self.enter(message);
+    }
+
+    fn call_end(
+        &mut self,
+        message: &Message,
+        outcome: &CallOutcome<'_>,
+        _state: &JournaledState<DB>,
+    ) {
+        self.exit(message, outcome);
+    }
+
+    fn create(&mut self, message: &Message, _state: &JournaledState<DB>) {
+        self.enter(message);
+    }
+
+    fn create_end(
+        &mut self,
+        message: &Message,
+        outcome: &CallOutcome<'_>,
+        _state: &JournaledState<DB>,
+    ) {
+        self.exit(message, outcome);
+    }
+
+    fn log(&mut self, log: &Log) {
+        if !self.with_logs {
+            return;
+        }
+
+        if let Some(frame) = self.stack.last_mut() {
+            frame.logs.push(CallLog {
+                address: log.address,
+                topics: log.topics.clone(),
+                data: log.data.clone(),
+                position: frame.calls.len(),
+            });
+        }
+    }
+
+    fn selfdestruct(&mut self, address: H160, beneficiary: H160, value: U256) {
+        if let Some(frame) = self.stack.last_mut() {
+            frame.calls.push(CallFrame {
+                from: address,
+                gas: 0,
+                gas_used: 0,
+                to: Some(beneficiary),
+                input: Bytes::new(),
+                output: Bytes::new(),
+                error: None,
+                revert_reason: None,
+                calls: Vec::new(),
+                logs: Vec::new(),
+                value: Some(value),
+                kind: "SELFDESTRUCT".to_string(),
+            });
+        }
+    }
+}
+
+fn hex_usize<S: serde::Serializer>(value: &usize, serializer: S) -> Result<S::Ok, S::Error> {
+    hex_u64(&(*value as u64), serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Account, InMemoryDatabase};
+    use crate::vm::Vm;
+    use serde_json::json;
+
+    #[test]
+    fn call_tree_matches_geth() {
+        let callee = H160::from_low_u64_be(0xca11);
+        let mut db = InMemoryDatabase::new();
+        // PUSH1 0x00 PUSH1 0x00 LOG0
+        // (PUSH32 word PUSH1 offset MSTORE) for each word of Error("no")
+        // PUSH1 0x64 PUSH1 0x00 REVERT
+        let mut callee_code = vec![0x60, 0x00, 0x60, 0x00, 0xa0];
+        let revert_data = [
+            &[0x08, 0xc3, 0x79, 0xa0][..],
+            &H256::from_low_u64_be(0x20)[..],
+            &H256::from_low_u64_be(2)[..],
+            b"no",
+            &[0; 30],
+        ]
+        .concat();
+        for (index, word) in revert_data.chunks(32).enumerate() {
+            let mut padded = [0; 32];
+            padded[..word.len()].copy_from_slice(word);
+            callee_code.push(0x7f);
+            callee_code.extend(padded);
+            callee_code.extend([0x60, (index * 32) as u8, 0x52]);
+        }
+        callee_code.extend([0x60, 0x64, 0x60, 0x00, 0xfd]);
+        db.insert_account(
+            callee,
+            Account::new(U256::zero(), 0, Bytes::from(callee_code)),
+        );
+        db.insert_account(H160::zero(), Account::new(U256::one(), 0, Bytes::new()));
+
+        // PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x01 PUSH2 0xca11 PUSH2 0xffff CALL
+        // PUSH1 0x00 PUSH1 0x00 LOG0
+        let code = [
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x01, 0x61, 0xca, 0x11, 0x61,
+            0xff, 0xff, 0xf1, 0x60, 0x00, 0x60, 0x00, 0xa0,
+        ];
+        let mut tracer = CallTracer::new().with_logs(true);
+        let result = Vm::new(&mut db).with_inspector(&mut tracer).exec(Message {
+            caller: H160::from_low_u64_be(0xcafe),
+            code: Bytes::copy_from_slice(&code),
+            data: Bytes::from_static(&[0xab]),
+            gas: 100_000,
+            ..Message::default()
+        });
+        assert!(result.is_success());
+
+        let root = tracer.into_result().unwrap();
+        let call = &root.calls[0];
+        assert_eq!(call.revert_reason.as_deref(), Some("no"));
+        assert_eq!(
+            serde_json::to_value(&root).unwrap(),
+            json!({
+                "from": "0x000000000000000000000000000000000000cafe",
+                "gas": "0x186a0",
+                "gasUsed": format!("{:#x}", result.gas_used),
+                "to": "0x0000000000000000000000000000000000000000",
+                "input": "0xab",
+                "calls": [{
+                    "from": "0x0000000000000000000000000000000000000000",
+                    "gas": "0x108fb",
+                    "gasUsed": format!("{:#x}", call.gas_used),
+                    "to": "0x000000000000000000000000000000000000ca11",
+                    "input": "0x",
+                    "output": format!("0x{}", hex::encode(&revert_data)),
+                    "error": "execution reverted",
+                    "revertReason": "no",
+                    "value": "0x1",
+                    "type": "CALL",
+                }],
+                "logs": [{
+                    "address": "0x0000000000000000000000000000000000000000",
+                    "topics": [],
+                    "data": "0x",
+                    "position": "0x1",
+                }],
+                "value": "0x0",
+                "type": "CALL",
+            })
+        );
+    }
+
+    #[test]
+    fn selfdestruct_is_a_call() {
+        let mut db = InMemoryDatabase::new();
+        db.insert_account(H160::zero(), Account::new(U256::from(5), 0, Bytes::new()));
+        let mut tracer = CallTracer::new();
+        // PUSH2 0xbeef SELFDESTRUCT
+        Vm::new(&mut db).with_inspector(&mut tracer).exec(Message {
+            code: Bytes::from_static(&[0x61, 0xbe, 0xef, 0xff]),
+            gas: 100_000,
+            ..Message::default()
+        });
+
+        let root = tracer.into_result().unwrap();
+        assert_eq!(
+            serde_json::to_value(&root.calls).unwrap(),
+            json!([{
+                "from": "0x0000000000000000000000000000000000000000",
+                "gas": "0x0",
+                "gasUsed": "0x0",
+                "to": "0x000000000000000000000000000000000000beef",
+                "input": "0x",
+                "value": "0x5",
+                "type": "SELFDESTRUCT",
+            }])
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use super::{opcode_name, status_error, write_json_string};
+use crate::database::Database;
+use crate::error::ErrorKind;
+use crate::inspector::{CallOutcome, FrameView, Inspector};
+use crate::journal::JournaledState;
+use crate::message::Message;
+use ethereum_types::U256;
+use std::io::{self, Write};
+
+/// Instruction about to execute, written out once its cost is known.
+#[derive(Debug)]
+struct PendingStep {
+    pc: usize,
+    opcode: u8,
+    gas: u64,
+    memory: Option<Vec<u8>>,
+    memory_size: usize,
+    stack: Vec<U256>,
+    return_data: Option<Vec<u8>>,
+    depth: usize,
+    refund: u64,
+}
+
+/// Inspector writing an EIP-3155 trace as JSON lines, one per instruction and a summary line
+/// once the outermost call or creation ends.
+///
+/// The lines are laid out like those of geth's `evm --json`. Memory and return data are left out
+/// unless enabled, as geth does by default.
+///
+/// Writing can't fail the execution, so the first error writing is kept and returned by
+/// `into_inner`, and nothing is written after it.
+#[derive(Debug)]
+pub struct Eip3155Tracer<W: Write> {
+    writer: W,
+    memory: bool,
+    return_data: bool,
+    pending: Option<PendingStep>,
+
+    /// Refunds of the frames running, by depth. Together they make up the refund counter of the
+    /// transaction.
+    refunds: Vec<i64>,
+
+    error: Option<io::Error>,
+}
+
+impl<W: Write> Eip3155Tracer<W> {
+    pub fn new(writer: W) -> Eip3155Tracer<W> {
+        Eip3155Tracer {
+            writer,
+            memory: false,
+            return_data: false,
+            pending: None,
+            refunds: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Include the memory of the frame in each line.
+    pub fn with_memory(mut self, enabled: bool) -> Eip3155Tracer<W> {
+        self.memory = enabled;
+        self
+    }
+
+    /// Include the output of the last call made by the frame in each line.
+    pub fn with_return_data(mut self, enabled: bool) -> Eip3155Tracer<W> {
+        self.return_data = enabled;
+        self
+    }
+
+    /// Stop tracing, returning the writer or the error writing to it.
+    pub fn into_inner(self) -> io::Result<W> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.writer),
+        }
+    }
+
+    /// Run `write` unless writing already failed, keeping its error.
+    fn write_with(&mut self, write: impl FnOnce(&mut W) -> io::Result<()>) {
+        if self.error.is_none() {
+            if let Err(error) = write(&mut self.writer) {
+                self.error = Some(error);
+            }
+        }
+    }
+}
+
+impl<W: Write, DB: Database> Inspector<DB> for Eip3155Tracer<W> {
+    fn step(&mut self, frame: &FrameView<'_>, _state: &JournaledState<DB>) {
+        self.refunds.resize(frame.depth, 0);
+        self.refunds[frame.depth - 1] = frame.refund;
+
+        self.pending = Some(PendingStep {
+            pc: frame.pc,
+            opcode: frame.opcode,
+            gas: frame.gas,
+            memory: self.memory.then(|| frame.memory.to_vec()),
+            memory_size: frame.memory.len(),
+            stack: frame.stack.to_vec(),
+            return_data: self.return_data.then(|| frame.return_data.to_vec()),
+            depth: frame.depth,
+            refund: self.refunds.iter().sum::<i64>().max(0) as u64,
+        });
+    }
+
+    fn step_end(
+        &mut self,
+        frame: &FrameView<'_>,
+        error: Option<ErrorKind>,
+        _state: &JournaledState<DB>,
+    ) {
+        let Some(step) = self.pending.take() else {
+            return;
+        };
+        let cost = step.gas.saturating_sub(frame.gas);
+
+        self.write_with(|out| write_step(out, &step, cost, error));
+    }
+
+    fn call_end(
+        &mut self,
+        message: &Message,
+        outcome: &CallOutcome<'_>,
+        _state: &JournaledState<DB>,
+    ) {
+        if message.depth == 0 {
+            self.write_with(|out| write_summary(out, outcome));
+        }
+    }
+
+    fn create_end(
+        &mut self,
+        message: &Message,
+        outcome: &CallOutcome<'_>,
+        _state: &JournaledState<DB>,
+    ) {
+        if message.depth == 0 {
+            self.write_with(|out| write_summary(out, outcome));
+        }
+    }
+}
+
+fn write_step(
+    out: &mut impl Write,
+    step: &PendingStep,
+    cost: u64,
+    error: Option<ErrorKind>,
+) -> io::Result<()> {
+    write!(
+        out,
+        "{{\"pc\":{},\"op\":{},\"gas\":\"{:#x}\",\"gasCost\":\"{:#x}\"",
+        step.pc, step.opcode, step.gas, cost
+    )?;
+    // Empty memory is left out, as geth does.
+    if let Some(memory) = step.memory.as_ref().filter(|memory| !memory.is_empty()) {
+        out.write_all(b",\"memory\":\"0x")?;
+        write_hex(out, memory)?;
+        out.write_all(b"\"")?;
+    }
+    write!(out, ",\"memSize\":{},\"stack\":[", step.memory_size)?;
+    for (index, value) in step.stack.iter().enumerate() {
+        if index > 0 {
+            out.write_all(b",")?;
+        }
+        write!(out, "\"{:#x}\"", value)?;
+    }
+    out.write_all(b"]")?;
+    if let Some(return_data) = step.return_data.as_ref().filter(|data| !data.is_empty()) {
+        out.write_all(b",\"returnData\":\"0x")?;
+        write_hex(out, return_data)?;
+        out.write_all(b"\"")?;
+    }
+    write!(
+        out,
+        ",\"depth\":{},\"refund\":{},\"opName\":\"{}\"",
+        step.depth,
+        step.refund,
+        opcode_name(step.opcode)
+    )?;
+    if let Some(error) = error {
+        out.write_all(b",\"error\":")?;
+        write_json_string(out, &error.to_string())?;
+    }
+    out.write_all(b"}\n")
+}
+
+fn write_summary(out: &mut impl Write, outcome: &CallOutcome<'_>) -> io::Result<()> {
+    out.write_all(b"{\"output\":\"")?;
+    write_hex(out, outcome.output)?;
+    write!(out, "\",\"gasUsed\":\"{:#x}\"", outcome.gas_used)?;
+    if let Some(error) = status_error(&outcome.status) {
+        out.write_all(b",\"error\":")?;
+        write_json_string(out, &error)?;
+    }
+    out.write_all(b"}\n")
+}
+
+fn write_hex(out: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    for byte in bytes {
+        write!(out, "{:02x}", byte)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::InMemoryDatabase;
+    use crate::vm::Vm;
+    use bytes::Bytes;
+
+    fn trace(code: &[u8], gas: u64) -> String {
+        let mut tracer = Eip3155Tracer::new(Vec::new());
+        Vm::new(InMemoryDatabase::new())
+            .with_inspector(&mut tracer)
+            .exec(Message {
+                code: Bytes::copy_from_slice(code),
+                gas,
+                ..Message::default()
+            });
+
+        String::from_utf8(tracer.into_inner().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn trace_matches_geth() {
+        // PUSH1 0x01 PUSH1 0x02 ADD PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+        let code = [
+            0x60, 0x01, 0x60, 0x02, 0x01, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+        ];
+
+        assert_eq!(
+            trace(&code, 10_000_000_000),
+            concat!(
+                r#"{"pc":0,"op":96,"gas":"0x2540be400","gasCost":"0x3","memSize":0,"stack":[],"depth":1,"refund":0,"opName":"PUSH1"}"#,
+                "\n",
+                r#"{"pc":2,"op":96,"gas":"0x2540be3fd","gasCost":"0x3","memSize":0,"stack":["0x1"],"depth":1,"refund":0,"opName":"PUSH1"}"#,
+                "\n",
+                r#"{"pc":4,"op":1,"gas":"0x2540be3fa","gasCost":"0x3","memSize":0,"stack":["0x1","0x2"],"depth":1,"refund":0,"opName":"ADD"}"#,
+                "\n",
+                r#"{"pc":5,"op":96,"gas":"0x2540be3f7","gasCost":"0x3","memSize":0,"stack":["0x3"],"depth":1,"refund":0,"opName":"PUSH1"}"#,
+                "\n",
+                r#"{"pc":7,"op":82,"gas":"0x2540be3f4","gasCost":"0x6","memSize":0,"stack":["0x3","0x0"],"depth":1,"refund":0,"opName":"MSTORE"}"#,
+                "\n",
+                r#"{"pc":8,"op":96,"gas":"0x2540be3ee","gasCost":"0x3","memSize":32,"stack":[],"depth":1,"refund":0,"opName":"PUSH1"}"#,
+                "\n",
+                r#"{"pc":10,"op":96,"gas":"0x2540be3eb","gasCost":"0x3","memSize":32,"stack":["0x20"],"depth":1,"refund":0,"opName":"PUSH1"}"#,
+                "\n",
+                r#"{"pc":12,"op":243,"gas":"0x2540be3e8","gasCost":"0x0","memSize":32,"stack":["0x20","0x0"],"depth":1,"refund":0,"opName":"RETURN"}"#,
+                "\n",
+                r#"{"output":"0000000000000000000000000000000000000000000000000000000000000003","gasUsed":"0x18"}"#,
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn failing_instruction_reports_error() {
+        // PUSH1 0x00 JUMP
+        let lines = trace(&[0x60, 0x00, 0x56], 100);
+        let lines: Vec<_> = lines.lines().collect();
+
+        assert_eq!(
+            lines[1],
+            r#"{"pc":2,"op":86,"gas":"0x61","gasCost":"0x8","memSize":0,"stack":["0x0"],"depth":1,"refund":0,"opName":"JUMP","error":"invalid jump destination"}"#
+        );
+        assert_eq!(
+            lines[2],
+            r#"{"output":"","gasUsed":"0x64","error":"invalid jump destination"}"#
+        );
+    }
+
+    #[test]
+    fn memory_and_refund_are_reported() {
+        // PUSH1 0x01 PUSH1 0x00 SSTORE PUSH1 0x00 PUSH1 0x00 SSTORE PUSH1 0x00 MLOAD
+        let code = [
+            0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0x55, 0x60, 0x00, 0x51,
+        ];
+        let mut tracer = Eip3155Tracer::new(Vec::new()).with_memory(true);
+        Vm::new(InMemoryDatabase::new())
+            .with_inspector(&mut tracer)
+            .exec(Message {
+                code: Bytes::copy_from_slice(&code),
+                gas: 100_000,
+                ..Message::default()
+            });
+        let output = String::from_utf8(tracer.into_inner().unwrap()).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+
+        // Setting the slot back to zero refunds the cost of setting it, less a warm access.
+        assert!(lines[7]
+            .starts_with(r#"{"pc":12,"op":81,"gas":"0x12fd9","gasCost":"0x6","memSize":0,"#));
+        assert!(lines[7].ends_with(r#""refund":19900,"opName":"MLOAD"}"#));
+        assert!(lines[8].starts_with(
+            r#"{"pc":13,"op":0,"gas":"0x12fd3","gasCost":"0x0","memory":"0x0000000000000000000000000000000000000000000000000000000000000000","memSize":32,"#
+        ));
+    }
+}
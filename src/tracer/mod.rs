@@ -0,0 +1,117 @@
+#[cfg(feature = "serde")]
+mod call_tracer;
+mod eip3155;
+#[cfg(feature = "serde")]
+mod parity;
+#[cfg(feature = "serde")]
+mod prestate_tracer;
+#[cfg(feature = "serde")]
+mod struct_logger;
+
+#[cfg(feature = "serde")]
+pub use call_tracer::{CallFrame, CallLog, CallTracer};
+pub use eip3155::Eip3155Tracer;
+#[cfg(feature = "serde")]
+pub use parity::{
+    AccountDiff, CallAction, CreateAction, Diff, MemoryDelta, ParityTracer, StateDiff,
+    StateDiffTracer, StorageDelta, SuicideAction, TraceAction, TraceResult, TransactionTrace,
+    VmExecutedOperation, VmInstruction, VmTrace, VmTracer,
+};
+#[cfg(feature = "serde")]
+pub use prestate_tracer::{PrestateAccount, PrestateResult, PrestateTracer};
+#[cfg(feature = "serde")]
+pub use struct_logger::{StructLog, StructLogResult, StructLogger};
+
+use crate::instruction::Instruction;
+#[cfg(feature = "serde")]
+use crate::message::CallKind;
+use crate::result::ExecutionStatus;
+#[cfg(feature = "serde")]
+use ethereum_types::U256;
+#[cfg(feature = "serde")]
+use serde::Serializer;
+
+/// Name of an opcode the way geth prints it in traces.
+pub(crate) fn opcode_name(opcode: u8) -> String {
+    match Instruction::try_from(opcode) {
+        Ok(instruction) => instruction.to_string(),
+        Err(_) => format!("opcode {:#04x} not defined", opcode),
+    }
+}
+
+/// Error a call or creation ended with the way geth reports it, `None` if it succeeded.
+pub(crate) fn status_error(status: &ExecutionStatus) -> Option<String> {
+    match status {
+        ExecutionStatus::Success(_) => None,
+        ExecutionStatus::Revert => Some("execution reverted".to_string()),
+        ExecutionStatus::Halt(error) => Some(error.kind.to_string()),
+    }
+}
+
+/// Write `value` as a JSON string, quotes included.
+pub(crate) fn write_json_string(out: &mut impl std::io::Write, value: &str) -> std::io::Result<()> {
+    out.write_all(b"\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => out.write_all(b"\\\"")?,
+            '\\' => out.write_all(b"\\\\")?,
+            '\n' => out.write_all(b"\\n")?,
+            '\r' => out.write_all(b"\\r")?,
+            '\t' => out.write_all(b"\\t")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+    out.write_all(b"\"")
+}
+
+/// Name of the kind of a message the way geth prints it in call traces.
+#[cfg(feature = "serde")]
+pub(crate) fn kind_name(kind: CallKind) -> &'static str {
+    match kind {
+        CallKind::Call => "CALL",
+        CallKind::CallCode => "CALLCODE",
+        CallKind::DelegateCall => "DELEGATECALL",
+        CallKind::StaticCall => "STATICCALL",
+        CallKind::Create => "CREATE",
+        CallKind::Create2 => "CREATE2",
+    }
+}
+
+/// Message of a revert with `Error(string)`, `None` if the output isn't one.
+#[cfg(feature = "serde")]
+pub(crate) fn revert_reason(output: &[u8]) -> Option<String> {
+    const SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+    let data = output.strip_prefix(&SELECTOR)?;
+    let word = |offset: usize| -> Option<usize> {
+        let word = U256::from_big_endian(data.get(offset..offset.checked_add(32)?)?);
+        (word <= U256::from(usize::MAX)).then(|| word.as_usize())
+    };
+
+    let offset = word(0)?;
+    let length = word(offset)?;
+    let start = offset.checked_add(32)?;
+    let message = data.get(start..start.checked_add(length)?)?;
+    String::from_utf8(message.to_vec()).ok()
+}
+
+/// Serialize bytes as `0x` prefixed hex.
+#[cfg(feature = "serde")]
+pub(crate) fn hex_bytes<S: Serializer>(
+    bytes: &impl AsRef<[u8]>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut hex = String::with_capacity(2 + bytes.as_ref().len() * 2);
+    hex.push_str("0x");
+    for byte in bytes.as_ref() {
+        hex.push_str(&format!("{:02x}", byte));
+    }
+    serializer.serialize_str(&hex)
+}
+
+/// Serialize a number as `0x` prefixed hex.
+#[cfg(feature = "serde")]
+pub(crate) fn hex_u64<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{:#x}", value))
+}
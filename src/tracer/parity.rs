@@ -0,0 +1,1074 @@
+use super::{hex_bytes, hex_u64, PrestateTracer};
+use crate::database::Database;
+use crate::env::Env;
+use crate::error::ErrorKind;
+use crate::inspector::{CallOutcome, FrameView, Inspector};
+use crate::instruction::Instruction;
+use crate::journal::JournaledState;
+use crate::message::{CallKind, Message};
+use crate::result::{ExecutionResult, ExecutionStatus};
+use crate::transaction::Transaction;
+use bytes::Bytes;
+use ethereum_types::{H160, H256, U256};
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Call made by a trace, as in Parity's `trace_*` output.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallAction {
+    pub from: H160,
+
+    /// `call`, `callcode`, `delegatecall` or `staticcall`.
+    pub call_type: String,
+
+    #[serde(serialize_with = "hex_u64")]
+    pub gas: u64,
+
+    #[serde(serialize_with = "hex_bytes")]
+    pub input: Bytes,
+
+    pub to: H160,
+    pub value: U256,
+}
+
+/// Creation made by a trace, as in Parity's `trace_*` output.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAction {
+    pub from: H160,
+
+    #[serde(serialize_with = "hex_u64")]
+    pub gas: u64,
+
+    #[serde(serialize_with = "hex_bytes")]
+    pub init: Bytes,
+
+    pub value: U256,
+
+    /// `create` or `create2`.
+    pub creation_method: String,
+}
+
+/// Self-destruct recorded by a trace, as in Parity's `trace_*` output.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuicideAction {
+    pub address: H160,
+    pub refund_address: H160,
+    pub balance: U256,
+}
+
+/// What a trace records.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum TraceAction {
+    Call(CallAction),
+    Create(CreateAction),
+    Suicide(SuicideAction),
+}
+
+impl TraceAction {
+    /// The `type` of the trace.
+    fn kind(&self) -> &'static str {
+        match self {
+            TraceAction::Call(_) => "call",
+            TraceAction::Create(_) => "create",
+            TraceAction::Suicide(_) => "suicide",
+        }
+    }
+}
+
+/// Outcome of a successful call or creation.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum TraceResult {
+    #[serde(rename_all = "camelCase")]
+    Call {
+        #[serde(serialize_with = "hex_u64")]
+        gas_used: u64,
+
+        #[serde(serialize_with = "hex_bytes")]
+        output: Bytes,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    Create {
+        address: H160,
+
+        /// Code deployed.
+        #[serde(serialize_with = "hex_bytes")]
+        code: Bytes,
+
+        #[serde(serialize_with = "hex_u64")]
+        gas_used: u64,
+    },
+}
+
+/// Call, creation or self-destruct in the flat list of traces built by `ParityTracer`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct TransactionTrace {
+    pub action: TraceAction,
+
+    /// Outcome of the call or creation, `None` if it failed or for a self-destruct.
+    pub result: Option<TraceResult>,
+
+    /// Why the call or creation failed, `Reverted` for example.
+    pub error: Option<String>,
+
+    /// Number of traces directly under this one.
+    pub subtraces: usize,
+
+    /// Path to the trace in the call tree, the index among its siblings at each level.
+    pub trace_address: Vec<usize>,
+}
+
+impl Serialize for TransactionTrace {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("action", &self.action)?;
+        match &self.error {
+            Some(error) => map.serialize_entry("error", error)?,
+            None => map.serialize_entry("result", &self.result)?,
+        }
+        map.serialize_entry("subtraces", &self.subtraces)?;
+        map.serialize_entry("traceAddress", &self.trace_address)?;
+        map.serialize_entry("type", self.action.kind())?;
+        map.end()
+    }
+}
+
+/// Inspector building the flat list of traces of Parity's `trace_replayTransaction`, one per call,
+/// creation and self-destruct in the order they started.
+#[derive(Debug, Default)]
+pub struct ParityTracer {
+    traces: Vec<TransactionTrace>,
+
+    /// Indices of the traces of the calls running, the outermost first.
+    stack: Vec<usize>,
+}
+
+impl ParityTracer {
+    pub fn new() -> ParityTracer {
+        ParityTracer::default()
+    }
+
+    /// The traces recorded so far.
+    pub fn traces(&self) -> &[TransactionTrace] {
+        &self.traces
+    }
+
+    /// Give up the tracer, returning the traces recorded.
+    pub fn into_traces(self) -> Vec<TransactionTrace> {
+        self.traces
+    }
+
+    /// Add a trace under the call running, returning its index.
+    fn push(&mut self, action: TraceAction) -> usize {
+        let trace_address = match self.stack.last() {
+            Some(&parent) => {
+                let parent = &mut self.traces[parent];
+                let mut trace_address = parent.trace_address.clone();
+                trace_address.push(parent.subtraces);
+                parent.subtraces += 1;
+                trace_address
+            }
+            None => Vec::new(),
+        };
+
+        self.traces.push(TransactionTrace {
+            action,
+            result: None,
+            error: None,
+            subtraces: 0,
+            trace_address,
+        });
+        self.traces.len() - 1
+    }
+
+    fn exit(&mut self, message: &Message, outcome: &CallOutcome<'_>) {
+        let Some(index) = self.stack.pop() else {
+            return;
+        };
+        let trace = &mut self.traces[index];
+        let output = Bytes::copy_from_slice(outcome.output);
+
+        match outcome.status {
+            ExecutionStatus::Success(_) if message.kind.is_create() => {
+                trace.result = Some(TraceResult::Create {
+                    address: message.address,
+                    code: output,
+                    gas_used: outcome.gas_used,
+                });
+            }
+            ExecutionStatus::Success(_) => {
+                trace.result = Some(TraceResult::Call {
+                    gas_used: outcome.gas_used,
+                    output,
+                });
+            }
+            ExecutionStatus::Revert => trace.error = Some("Reverted".to_string()),
+            ExecutionStatus::Halt(error) => trace.error = Some(error_name(error.kind).to_string()),
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for ParityTracer {
+    fn call(&mut self, message: &Message, _state: &JournaledState<DB>) {
+        let (from, to, call_type) = match message.kind {
+            CallKind::CallCode => (message.caller, message.code_address, "callcode"),
+            CallKind::DelegateCall => (message.address, message.code_address, "delegatecall"),
+            CallKind::StaticCall => (message.caller, message.address, "staticcall"),
+            _ => (message.caller, message.address, "call"),
+        };
+
+        let index = self.push(TraceAction::Call(CallAction {
+            from,
+            call_type: call_type.to_string(),
+            gas: message.gas,
+            input: message.data.clone(),
+            to,
+            value: message.value,
+        }));
+        self.stack.push(index);
+    }
+
+    fn call_end(
+        &mut self,
+        message: &Message,
+        outcome: &CallOutcome<'_>,
+        _state: &JournaledState<DB>,
+    ) {
+        self.exit(message, outcome);
+    }
+
+    fn create(&mut self, message: &Message, _state: &JournaledState<DB>) {
+        let creation_method = match message.kind {
+            CallKind::Create2 => "create2",
+            _ => "create",
+        };
+
+        let index = self.push(TraceAction::Create(CreateAction {
+            from: message.caller,
+            gas: message.gas,
+            init: message.code.clone(),
+            value: message.value,
+            creation_method: creation_method.to_string(),
+        }));
+        self.stack.push(index);
+    }
+
+    fn create_end(
+        &mut self,
+        message: &Message,
+        outcome: &CallOutcome<'_>,
+        _state: &JournaledState<DB>,
+    ) {
+        self.exit(message, outcome);
+    }
+
+    fn selfdestruct(&mut self, address: H160, beneficiary: H160, value: U256) {
+        self.push(TraceAction::Suicide(SuicideAction {
+            address,
+            refund_address: beneficiary,
+            balance: value,
+        }));
+    }
+}
+
+/// Error a call or creation halted with the way Parity reports it.
+fn error_name(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::StackUnderflow => "Stack underflow",
+        ErrorKind::StackOverflow => "Out of stack",
+        ErrorKind::InvalidOpcode => "Bad instruction",
+        ErrorKind::InvalidJump => "Bad jump destination",
+        ErrorKind::InvalidMemoryAccess | ErrorKind::OutOfGas => "Out of gas",
+        ErrorKind::StaticCallViolation => "Mutable Call In Static Context",
+        ErrorKind::InsufficientBalance => "Insufficient balance",
+        ErrorKind::ReturnDataOutOfBounds => "Out of bounds",
+        ErrorKind::CreateCollision => "Contract address collision",
+        ErrorKind::ContractSizeLimit => "Code size limit exceeded",
+        ErrorKind::InvalidContractPrefix => "Invalid code",
+        ErrorKind::InitCodeSizeLimit => "Init code size limit exceeded",
+        ErrorKind::PrecompileFailed => "Built-in failed",
+    }
+}
+
+/// Change of a value, serialized with Parity's markers: `=` if unchanged, `+` if it came into
+/// existence, `-` if it went out of existence and `*` if it changed.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum Diff<T> {
+    Same,
+    Born(T),
+    Died(T),
+    Changed { from: T, to: T },
+}
+
+impl<T: PartialEq> Diff<T> {
+    fn new(from: T, to: T) -> Diff<T> {
+        if from == to {
+            Diff::Same
+        } else {
+            Diff::Changed { from, to }
+        }
+    }
+
+    pub fn is_same(&self) -> bool {
+        matches!(self, Diff::Same)
+    }
+}
+
+impl<T> Diff<T> {
+    /// Serialize the diff with `value` standing in for the values changed.
+    fn serialize_with<'a, S: Serializer, V: Serialize>(
+        &'a self,
+        serializer: S,
+        value: impl Fn(&'a T) -> V,
+    ) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Change<V> {
+            from: V,
+            to: V,
+        }
+
+        fn entry<S: Serializer, V: Serialize>(
+            serializer: S,
+            marker: &str,
+            value: &V,
+        ) -> Result<S::Ok, S::Error> {
+            let mut map = serializer.serialize_map(Some(1))?;
+            map.serialize_entry(marker, value)?;
+            map.end()
+        }
+
+        match self {
+            Diff::Same => serializer.serialize_str("="),
+            Diff::Born(born) => entry(serializer, "+", &value(born)),
+            Diff::Died(died) => entry(serializer, "-", &value(died)),
+            Diff::Changed { from, to } => entry(
+                serializer,
+                "*",
+                &Change {
+                    from: value(from),
+                    to: value(to),
+                },
+            ),
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for Diff<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.serialize_with(serializer, |value| value)
+    }
+}
+
+/// How an account changed, as in the `stateDiff` of Parity's `trace_replayTransaction`.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+pub struct AccountDiff {
+    pub balance: Diff<U256>,
+
+    #[serde(serialize_with = "code_diff")]
+    pub code: Diff<Bytes>,
+
+    pub nonce: Diff<U256>,
+
+    /// Storage slots changed.
+    pub storage: BTreeMap<H256, Diff<H256>>,
+}
+
+/// Accounts changed by an execution.
+pub type StateDiff = BTreeMap<H160, AccountDiff>;
+
+/// Inspector working out the `stateDiff` of Parity's `trace_replayTransaction`, the accounts and
+/// storage slots an execution changed.
+#[derive(Debug, Default)]
+pub struct StateDiffTracer {
+    /// Accounts and storage slots touched, as they were before.
+    prestate: PrestateTracer,
+
+    result: Option<StateDiff>,
+}
+
+impl StateDiffTracer {
+    pub fn new() -> StateDiffTracer {
+        StateDiffTracer::default()
+    }
+
+    /// The accounts changed, `None` if the execution hasn't ended.
+    pub fn result(&self) -> Option<&StateDiff> {
+        self.result.as_ref()
+    }
+
+    /// Give up the tracer, returning the accounts changed.
+    pub fn into_result(self) -> Option<StateDiff> {
+        self.result
+    }
+
+    /// Compare the accounts touched with the state once the prestate tracer saw the execution end.
+    fn finish<DB: Database>(&mut self, state: &JournaledState<DB>) {
+        if self.result.is_some() || self.prestate.result().is_none() {
+            return;
+        }
+
+        let mut diff = StateDiff::new();
+        for (&address, before) in &self.prestate.accounts {
+            let slots = self.prestate.storage.get(&address);
+            let slots = slots.iter().flat_map(|slots| slots.iter());
+
+            let account = match (before, state.account(address)) {
+                (None, None) => continue,
+                (None, Some(after)) => {
+                    if after.is_empty() {
+                        continue;
+                    }
+
+                    AccountDiff {
+                        balance: Diff::Born(after.balance),
+                        code: Diff::Born(after.code),
+                        nonce: Diff::Born(U256::from(after.nonce)),
+                        storage: slots
+                            .map(|(&key, _)| (key, state.storage(address, key)))
+                            .filter(|(_, value)| !value.is_zero())
+                            .map(|(key, value)| (key, Diff::Born(value)))
+                            .collect(),
+                    }
+                }
+                (Some(before), None) => AccountDiff {
+                    balance: Diff::Died(before.balance),
+                    code: Diff::Died(before.code.clone()),
+                    nonce: Diff::Died(U256::from(before.nonce)),
+                    storage: slots
+                        .filter(|(_, value)| !value.is_zero())
+                        .map(|(&key, &value)| (key, Diff::Died(value)))
+                        .collect(),
+                },
+                (Some(before), Some(after)) => {
+                    let account = AccountDiff {
+                        balance: Diff::new(before.balance, after.balance),
+                        code: Diff::new(before.code.clone(), after.code),
+                        nonce: Diff::new(U256::from(before.nonce), U256::from(after.nonce)),
+                        storage: slots
+                            .map(|(&key, &value)| {
+                                (key, Diff::new(value, state.storage(address, key)))
+                            })
+                            .filter(|(_, diff)| !diff.is_same())
+                            .collect(),
+                    };
+                    if account.balance.is_same()
+                        && account.code.is_same()
+                        && account.nonce.is_same()
+                        && account.storage.is_empty()
+                    {
+                        continue;
+                    }
+
+                    account
+                }
+            };
+            diff.insert(address, account);
+        }
+
+        self.result = Some(diff);
+    }
+}
+
+impl<DB: Database> Inspector<DB> for StateDiffTracer {
+    fn transaction(
+        &mut self,
+        env: &Env,
+        sender: H160,
+        transaction: &Transaction,
+        state: &JournaledState<DB>,
+    ) {
+        self.prestate.transaction(env, sender, transaction, state);
+    }
+
+    fn transaction_end(&mut self, result: &ExecutionResult, state: &JournaledState<DB>) {
+        self.prestate.transaction_end(result, state);
+        self.finish(state);
+    }
+
+    fn step(&mut self, frame: &FrameView<'_>, state: &JournaledState<DB>) {
+        self.prestate.step(frame, state);
+    }
+
+    fn call(&mut self, message: &Message, state: &JournaledState<DB>) {
+        self.prestate.call(message, state);
+    }
+
+    fn call_end(
+        &mut self,
+        message: &Message,
+        outcome: &CallOutcome<'_>,
+        state: &JournaledState<DB>,
+    ) {
+        self.prestate.call_end(message, outcome, state);
+        self.finish(state);
+    }
+
+    fn create(&mut self, message: &Message, state: &JournaledState<DB>) {
+        self.prestate.create(message, state);
+    }
+
+    fn create_end(
+        &mut self,
+        message: &Message,
+        outcome: &CallOutcome<'_>,
+        state: &JournaledState<DB>,
+    ) {
+        self.prestate.create_end(message, outcome, state);
+        self.finish(state);
+    }
+}
+
+fn code_diff<S: Serializer>(diff: &Diff<Bytes>, serializer: S) -> Result<S::Ok, S::Error> {
+    struct Hex<'a>(&'a Bytes);
+
+    impl Serialize for Hex<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            hex_bytes(self.0, serializer)
+        }
+    }
+
+    diff.serialize_with(serializer, Hex)
+}
+
+/// Memory written by an instruction, in a Parity `vmTrace`.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+pub struct MemoryDelta {
+    #[serde(serialize_with = "hex_bytes")]
+    pub data: Bytes,
+
+    /// Offset in memory of the first byte written.
+    pub off: usize,
+}
+
+/// Storage slot written by an instruction, in a Parity `vmTrace`.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+pub struct StorageDelta {
+    pub key: U256,
+    pub val: U256,
+}
+
+/// What an instruction did, in a Parity `vmTrace`.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+pub struct VmExecutedOperation {
+    pub mem: Option<MemoryDelta>,
+
+    /// Items the instruction left on top of the stack, from the bottom up.
+    pub push: Vec<U256>,
+
+    pub store: Option<StorageDelta>,
+
+    /// Gas left after the instruction, after the call returned for a call or creation.
+    pub used: u64,
+}
+
+/// Instruction executed, in a Parity `vmTrace`.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+pub struct VmInstruction {
+    /// Gas charged, including the gas given to a call or creation.
+    pub cost: u64,
+
+    /// What the instruction did, `None` if it failed.
+    pub ex: Option<VmExecutedOperation>,
+
+    pub pc: usize,
+
+    /// Trace of the code run by a call or creation.
+    pub sub: Option<VmTrace>,
+}
+
+/// Instructions executed by a frame, as in the `vmTrace` of Parity's `trace_replayTransaction`.
+#[derive(Eq, PartialEq, Clone, Debug, Default, Serialize)]
+pub struct VmTrace {
+    #[serde(serialize_with = "hex_bytes")]
+    pub code: Bytes,
+
+    pub ops: Vec<VmInstruction>,
+}
+
+/// Instruction of a frame that started but wasn't traced yet.
+#[derive(Debug)]
+struct PendingInstruction {
+    instruction: Instruction,
+    pc: usize,
+
+    /// Gas left when the instruction started.
+    gas: u64,
+
+    /// Memory range the instruction writes to.
+    memory: Option<(usize, usize)>,
+
+    store: Option<StorageDelta>,
+}
+
+/// Frame being traced by `VmTracer`.
+#[derive(Debug, Default)]
+struct TracedFrame {
+    trace: VmTrace,
+
+    pending: Option<PendingInstruction>,
+
+    /// Call or creation whose effects are traced once the frame resumes.
+    resuming: Option<PendingInstruction>,
+}
+
+/// Inspector building the `vmTrace` of Parity's `trace_replayTransaction`, the instructions each
+/// frame executed with the stack items, memory and storage they wrote.
+#[derive(Debug, Default)]
+pub struct VmTracer {
+    /// Frames running, the outermost first.
+    frames: Vec<TracedFrame>,
+
+    result: Option<VmTrace>,
+}
+
+impl VmTracer {
+    pub fn new() -> VmTracer {
+        VmTracer::default()
+    }
+
+    /// The trace of the outermost frame, `None` if it hasn't ended.
+    pub fn result(&self) -> Option<&VmTrace> {
+        self.result.as_ref()
+    }
+
+    /// Give up the tracer, returning the trace of the outermost frame.
+    pub fn into_result(self) -> Option<VmTrace> {
+        self.result
+    }
+
+    fn enter(&mut self, message: &Message) {
+        self.frames.push(TracedFrame {
+            trace: VmTrace {
+                code: message.code.clone(),
+                ops: Vec::new(),
+            },
+            ..TracedFrame::default()
+        });
+    }
+
+    /// Attach the trace of the frame that ended to the call or creation that started it.
+    fn exit(&mut self) {
+        let Some(frame) = self.frames.pop() else {
+            return;
+        };
+
+        match self.frames.last_mut() {
+            Some(parent) => {
+                if let Some(op) = parent.trace.ops.last_mut() {
+                    op.sub = Some(frame.trace);
+                }
+            }
+            None => self.result = Some(frame.trace),
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for VmTracer {
+    fn step(&mut self, frame: &FrameView<'_>, _state: &JournaledState<DB>) {
+        let Some(traced) = self.frames.last_mut() else {
+            return;
+        };
+
+        // The call or creation before this step returned, so its effects are in the frame.
+        if let Some(pending) = traced.resuming.take() {
+            if let Some(op) = traced.trace.ops.last_mut() {
+                op.ex = Some(executed(&pending, frame, 1));
+            }
+        }
+
+        let instruction = match Instruction::try_from(frame.opcode) {
+            Ok(instruction) if frame.pc < frame.message.code.len() => instruction,
+            _ => return,
+        };
+
+        let stack = |index: usize| frame.stack.iter().rev().nth(index).copied();
+        let offset = |index: usize| {
+            stack(index)
+                .filter(|&value| value <= U256::from(usize::MAX))
+                .map(|value| value.as_usize())
+        };
+        let range = |start: usize, length: usize| offset(start).zip(offset(length));
+        let memory = match instruction {
+            Instruction::MStore => offset(0).map(|start| (start, 32)),
+            Instruction::MStore8 => offset(0).map(|start| (start, 1)),
+            Instruction::CallDataCopy
+            | Instruction::CodeCopy
+            | Instruction::ReturnDataCopy
+            | Instruction::MCopy => range(0, 2),
+            Instruction::ExtCodeCopy => range(1, 3),
+            Instruction::Call | Instruction::CallCode => range(5, 6),
+            Instruction::DelegateCall | Instruction::StaticCall => range(4, 5),
+            _ => None,
+        };
+        let store = match (instruction, stack(0), stack(1)) {
+            (Instruction::SStore, Some(key), Some(val)) => Some(StorageDelta { key, val }),
+            _ => None,
+        };
+
+        traced.pending = Some(PendingInstruction {
+            instruction,
+            pc: frame.pc,
+            gas: frame.gas,
+            memory,
+            store,
+        });
+    }
+
+    fn step_end(
+        &mut self,
+        frame: &FrameView<'_>,
+        error: Option<ErrorKind>,
+        _state: &JournaledState<DB>,
+    ) {
+        let Some(traced) = self.frames.last_mut() else {
+            return;
+        };
+        let Some(pending) = traced.pending.take() else {
+            return;
+        };
+
+        let mut op = VmInstruction {
+            cost: pending.gas.saturating_sub(frame.gas),
+            ex: None,
+            pc: pending.pc,
+            sub: None,
+        };
+        if error.is_none() {
+            if matches!(
+                pending.instruction,
+                Instruction::Call
+                    | Instruction::CallCode
+                    | Instruction::DelegateCall
+                    | Instruction::StaticCall
+                    | Instruction::Create
+                    | Instruction::Create2
+            ) {
+                traced.resuming = Some(pending);
+            } else {
+                let pushed = pending.instruction.info().outputs;
+                op.ex = Some(executed(&pending, frame, pushed));
+            }
+        }
+        traced.trace.ops.push(op);
+    }
+
+    fn call(&mut self, message: &Message, _state: &JournaledState<DB>) {
+        self.enter(message);
+    }
+
+    fn call_end(
+        &mut self,
+        _message: &Message,
+        _outcome: &CallOutcome<'_>,
+        _state: &JournaledState<DB>,
+    ) {
+        self.exit();
+    }
+
+    fn create(&mut self, message: &Message, _state: &JournaledState<DB>) {
+        self.enter(message);
+    }
+
+    fn create_end(
+        &mut self,
+        _message: &Message,
+        _outcome: &CallOutcome<'_>,
+        _state: &JournaledState<DB>,
+    ) {
+        self.exit();
+    }
+}
+
+/// Effects of `pending` seen in `frame`, with `pushed` items on top of the stack.
+fn executed(
+    pending: &PendingInstruction,
+    frame: &FrameView<'_>,
+    pushed: usize,
+) -> VmExecutedOperation {
+    let mem = pending
+        .memory
+        .filter(|&(_, length)| length > 0)
+        .and_then(|(offset, length)| {
+            let data = frame.memory.get(offset..offset.checked_add(length)?)?;
+            Some(MemoryDelta {
+                data: Bytes::copy_from_slice(data),
+                off: offset,
+            })
+        });
+
+    VmExecutedOperation {
+        mem,
+        push: frame.stack[frame.stack.len().saturating_sub(pushed)..].to_vec(),
+        store: pending.store.clone(),
+        used: frame.gas,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Account, InMemoryDatabase};
+    use crate::vm::Vm;
+    use serde_json::json;
+
+    #[test]
+    fn traces_are_flat_with_addresses() {
+        let mut db = InMemoryDatabase::new();
+        // PUSH2 0xbeef SELFDESTRUCT
+        db.insert_account(
+            H160::from_low_u64_be(0xca11),
+            Account::new(
+                U256::from(7),
+                0,
+                Bytes::from_static(&[0x61, 0xbe, 0xef, 0xff]),
+            ),
+        );
+        // PUSH1 0x00 PUSH1 0x00 REVERT
+        db.insert_account(
+            H160::from_low_u64_be(0xdead),
+            Account::new(
+                U256::zero(),
+                0,
+                Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xfd]),
+            ),
+        );
+
+        // (PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH2 address GAS CALL POP) for
+        // 0xca11 then 0xdead
+        let mut code = Vec::new();
+        for address in [[0xca, 0x11], [0xde, 0xad]] {
+            code.extend([
+                0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x61,
+            ]);
+            code.extend(address);
+            code.extend([0x5a, 0xf1, 0x50]);
+        }
+        let mut tracer = ParityTracer::new();
+        let result = Vm::new(&mut db).with_inspector(&mut tracer).exec(Message {
+            caller: H160::from_low_u64_be(0xcafe),
+            code: Bytes::from(code),
+            data: Bytes::from_static(&[0xab]),
+            gas: 100_000,
+            ..Message::default()
+        });
+        assert!(result.is_success());
+
+        let traces = tracer.into_traces();
+        let addresses: Vec<_> = traces
+            .iter()
+            .map(|trace| (trace.trace_address.clone(), trace.subtraces))
+            .collect();
+        assert_eq!(
+            addresses,
+            vec![(vec![], 2), (vec![0], 1), (vec![0, 0], 0), (vec![1], 0)]
+        );
+
+        assert_eq!(
+            serde_json::to_value(&traces[0]).unwrap(),
+            json!({
+                "action": {
+                    "from": "0x000000000000000000000000000000000000cafe",
+                    "callType": "call",
+                    "gas": "0x186a0",
+                    "input": "0xab",
+                    "to": "0x0000000000000000000000000000000000000000",
+                    "value": "0x0",
+                },
+                "result": {"gasUsed": format!("{:#x}", result.gas_used), "output": "0x"},
+                "subtraces": 2,
+                "traceAddress": [],
+                "type": "call",
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(&traces[2]).unwrap(),
+            json!({
+                "action": {
+                    "address": "0x000000000000000000000000000000000000ca11",
+                    "refundAddress": "0x000000000000000000000000000000000000beef",
+                    "balance": "0x7",
+                },
+                "result": null,
+                "subtraces": 0,
+                "traceAddress": [0, 0],
+                "type": "suicide",
+            })
+        );
+        let TraceAction::Call(call) = &traces[3].action else {
+            panic!("not a call");
+        };
+        assert_eq!(
+            serde_json::to_value(&traces[3]).unwrap(),
+            json!({
+                "action": {
+                    "from": "0x0000000000000000000000000000000000000000",
+                    "callType": "call",
+                    "gas": format!("{:#x}", call.gas),
+                    "input": "0x",
+                    "to": "0x000000000000000000000000000000000000dead",
+                    "value": "0x0",
+                },
+                "error": "Reverted",
+                "subtraces": 0,
+                "traceAddress": [1],
+                "type": "call",
+            })
+        );
+    }
+
+    #[test]
+    fn creation_has_address_and_code() {
+        // PUSH1 0xfe PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+        let init = [0x60, 0xfe, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3];
+        let address = H160::from_low_u64_be(0xc0de);
+        let mut tracer = ParityTracer::new();
+        Vm::new(InMemoryDatabase::new())
+            .with_inspector(&mut tracer)
+            .exec(Message {
+                kind: CallKind::Create,
+                caller: H160::from_low_u64_be(0xcafe),
+                address,
+                code: Bytes::copy_from_slice(&init),
+                gas: 100_000,
+                ..Message::default()
+            });
+
+        let trace = &tracer.traces()[0];
+        assert_eq!(trace.action.kind(), "create");
+        let Some(TraceResult::Create {
+            address: created,
+            code,
+            ..
+        }) = &trace.result
+        else {
+            panic!("not a creation");
+        };
+        assert_eq!(*created, address);
+        assert_eq!(code.as_ref(), [0xfe]);
+    }
+
+    #[test]
+    fn vm_trace_records_effects_and_calls() {
+        let mut db = InMemoryDatabase::new();
+        // PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+        db.insert_account(
+            H160::from_low_u64_be(0xca11),
+            Account::new(
+                U256::zero(),
+                0,
+                Bytes::from_static(&[0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3]),
+            ),
+        );
+
+        // PUSH1 0x20 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH2 0xca11 GAS CALL
+        // PUSH1 0x07 SSTORE
+        let code = [
+            0x60, 0x20, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x61, 0xca, 0x11, 0x5a,
+            0xf1, 0x60, 0x07, 0x55,
+        ];
+        let mut tracer = VmTracer::new();
+        let result = Vm::new(&mut db).with_inspector(&mut tracer).exec(Message {
+            code: Bytes::copy_from_slice(&code),
+            gas: 100_000,
+            ..Message::default()
+        });
+        assert!(result.is_success());
+
+        let trace = tracer.into_result().unwrap();
+        let pcs: Vec<_> = trace.ops.iter().map(|op| op.pc).collect();
+        assert_eq!(pcs, [0, 2, 4, 6, 8, 10, 13, 14, 15, 17]);
+
+        // The call is traced once it returned, with the output it wrote and its success.
+        let call = &trace.ops[7];
+        let output = format!("{:#066x}", 0x2a);
+        assert_eq!(
+            serde_json::to_value(call.ex.as_ref().unwrap()).unwrap(),
+            json!({
+                "mem": {"data": output, "off": 0},
+                "push": ["0x1"],
+                "store": null,
+                "used": 97_359,
+            })
+        );
+        let sub = call.sub.as_ref().unwrap();
+        assert_eq!(
+            sub.code.as_ref(),
+            [0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3]
+        );
+        assert_eq!(
+            serde_json::to_value(&sub.ops[2]).unwrap()["ex"]["mem"],
+            json!({"data": output, "off": 0})
+        );
+
+        assert_eq!(
+            serde_json::to_value(&trace.ops[9]).unwrap(),
+            json!({
+                "cost": 22_100,
+                "ex": {"mem": null, "push": [], "store": {"key": "0x7", "val": "0x1"}, "used": 75_256},
+                "pc": 17,
+                "sub": null,
+            })
+        );
+    }
+
+    #[test]
+    fn state_diff_marks_changes() {
+        let contract = H160::from_low_u64_be(0xc0de);
+        let slot = H256::from_low_u64_be;
+        // PUSH1 0x00 SLOAD PUSH1 0x01 ADD PUSH1 0x00 SSTORE PUSH1 0x00 PUSH1 0x01 SSTORE
+        // PUSH1 0x2a PUSH1 0x02 SSTORE PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x05
+        // PUSH2 0xbeef GAS CALL
+        let code = [
+            0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x01, 0x55,
+            0x60, 0x2a, 0x60, 0x02, 0x55, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60,
+            0x05, 0x61, 0xbe, 0xef, 0x5a, 0xf1,
+        ];
+        let mut db = InMemoryDatabase::new();
+        db.insert_account(
+            contract,
+            Account::new(U256::from(9), 1, Bytes::copy_from_slice(&code)),
+        );
+        db.set_storage(contract, slot(0), slot(7));
+        db.set_storage(contract, slot(1), slot(9));
+
+        let mut tracer = StateDiffTracer::new();
+        let result = Vm::new(&mut db).with_inspector(&mut tracer).exec(Message {
+            caller: H160::from_low_u64_be(0xcafe),
+            address: contract,
+            code_address: contract,
+            code: Bytes::copy_from_slice(&code),
+            gas: 100_000,
+            ..Message::default()
+        });
+        assert!(result.is_success());
+
+        let word = |value: u64| format!("{:#066x}", value);
+        assert_eq!(
+            serde_json::to_value(tracer.result().unwrap()).unwrap(),
+            json!({
+                "0x000000000000000000000000000000000000beef": {
+                    "balance": {"+": "0x5"},
+                    "code": {"+": "0x"},
+                    "nonce": {"+": "0x0"},
+                    "storage": {},
+                },
+                "0x000000000000000000000000000000000000c0de": {
+                    "balance": {"*": {"from": "0x9", "to": "0x4"}},
+                    "code": "=",
+                    "nonce": "=",
+                    "storage": {
+                        word(0): {"*": {"from": word(7), "to": word(8)}},
+                        word(1): {"*": {"from": word(9), "to": word(0)}},
+                        word(2): {"*": {"from": word(0), "to": word(42)}},
+                    },
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,450 @@
+use super::hex_bytes;
+use crate::database::{Account, Database};
+use crate::env::Env;
+use crate::inspector::{CallOutcome, FrameView, Inspector};
+use crate::instruction::Instruction;
+use crate::journal::JournaledState;
+use crate::message::Message;
+use crate::result::ExecutionResult;
+use crate::transaction::{Transaction, TxKind};
+use bytes::Bytes;
+use ethereum_types::{BigEndianHash, H160, H256, U256};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// Account as in geth's `prestateTracer` output.
+///
+/// In the state after a transaction, only the fields that changed are set.
+#[derive(Eq, PartialEq, Clone, Debug, Default, Serialize)]
+pub struct PrestateAccount {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<U256>,
+
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "optional_hex_bytes"
+    )]
+    pub code: Option<Bytes>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<H256, H256>,
+}
+
+/// Accounts touched by an execution, as geth's `prestateTracer` reports them.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum PrestateResult {
+    /// Each account as it was before, with the storage slots accessed.
+    Pre(BTreeMap<H160, PrestateAccount>),
+
+    /// The accounts that changed, before and after.
+    Diff {
+        pre: BTreeMap<H160, PrestateAccount>,
+        post: BTreeMap<H160, PrestateAccount>,
+    },
+}
+
+/// Inspector recording the accounts and storage slots an execution touches as they were before,
+/// as geth's `prestateTracer` does. In diff mode only what changed is kept, along with what it
+/// changed to.
+#[derive(Debug, Default)]
+pub struct PrestateTracer {
+    diff_mode: bool,
+
+    /// Accounts touched as they were first seen, `None` if they didn't exist.
+    pub(super) accounts: BTreeMap<H160, Option<Account>>,
+
+    /// Storage slots touched as they were first seen.
+    pub(super) storage: BTreeMap<H160, BTreeMap<H256, H256>>,
+
+    /// Addresses of the contracts created.
+    created: HashSet<H160>,
+
+    in_transaction: bool,
+    result: Option<PrestateResult>,
+}
+
+impl PrestateTracer {
+    pub fn new() -> PrestateTracer {
+        PrestateTracer::default()
+    }
+
+    /// Report only the accounts that changed, before and after.
+    pub fn with_diff_mode(mut self, enabled: bool) -> PrestateTracer {
+        self.diff_mode = enabled;
+        self
+    }
+
+    /// The accounts touched, `None` if the execution hasn't ended.
+    pub fn result(&self) -> Option<&PrestateResult> {
+        self.result.as_ref()
+    }
+
+    /// Give up the tracer, returning the accounts touched.
+    pub fn into_result(self) -> Option<PrestateResult> {
+        self.result
+    }
+
+    fn lookup_account<DB: Database>(&mut self, address: H160, state: &JournaledState<DB>) {
+        self.accounts
+            .entry(address)
+            .or_insert_with(|| state.account(address));
+    }
+
+    fn lookup_storage<DB: Database>(
+        &mut self,
+        address: H160,
+        key: H256,
+        state: &JournaledState<DB>,
+    ) {
+        self.lookup_account(address, state);
+        self.storage
+            .entry(address)
+            .or_default()
+            .entry(key)
+            .or_insert_with(|| state.storage(address, key));
+    }
+
+    /// Account at `address` as it was first seen.
+    fn pre_account(&self, address: H160) -> PrestateAccount {
+        let account = self.accounts[&address].clone().unwrap_or_default();
+
+        PrestateAccount {
+            balance: Some(account.balance),
+            code: (!account.code.is_empty()).then_some(account.code),
+            nonce: (account.nonce != 0).then_some(account.nonce),
+            storage: self.storage.get(&address).cloned().unwrap_or_default(),
+        }
+    }
+
+    fn finish<DB: Database>(&mut self, state: &JournaledState<DB>) {
+        let mut pre = BTreeMap::new();
+        let mut post = BTreeMap::new();
+
+        for (&address, before) in &self.accounts {
+            // Contracts created by the execution had nothing before it.
+            if before.is_none() && self.created.contains(&address) {
+                continue;
+            }
+
+            let mut pre_account = self.pre_account(address);
+            if !self.diff_mode {
+                pre.insert(address, pre_account);
+                continue;
+            }
+
+            // Deleted accounts only show up before.
+            let Some(after) = state.account(address) else {
+                if before.is_some() {
+                    pre.insert(address, pre_account);
+                }
+                continue;
+            };
+
+            let before = before.clone().unwrap_or_default();
+            let mut post_account = PrestateAccount::default();
+            if after.balance != before.balance {
+                post_account.balance = Some(after.balance);
+            }
+            if after.nonce != before.nonce {
+                post_account.nonce = (after.nonce != 0).then_some(after.nonce);
+            }
+            if after.code != before.code {
+                post_account.code = (!after.code.is_empty()).then_some(after.code.clone());
+            }
+            let mut modified = post_account != PrestateAccount::default();
+
+            pre_account.storage.retain(|&key, value| {
+                let current = state.storage(address, key);
+                if current == *value {
+                    return false;
+                }
+
+                modified = true;
+                if !current.is_zero() {
+                    post_account.storage.insert(key, current);
+                }
+                !value.is_zero()
+            });
+
+            if modified {
+                pre.insert(address, pre_account);
+                post.insert(address, post_account);
+            }
+        }
+
+        self.result = Some(if self.diff_mode {
+            PrestateResult::Diff { pre, post }
+        } else {
+            PrestateResult::Pre(pre)
+        });
+    }
+}
+
+impl<DB: Database> Inspector<DB> for PrestateTracer {
+    fn transaction(
+        &mut self,
+        env: &Env,
+        sender: H160,
+        transaction: &Transaction,
+        state: &JournaledState<DB>,
+    ) {
+        self.in_transaction = true;
+
+        self.lookup_account(sender, state);
+        self.lookup_account(env.block.coinbase, state);
+        if let TxKind::Call(to) = transaction.to() {
+            self.lookup_account(to, state);
+        }
+    }
+
+    fn transaction_end(&mut self, _result: &ExecutionResult, state: &JournaledState<DB>) {
+        self.finish(state);
+    }
+
+    fn step(&mut self, frame: &FrameView<'_>, state: &JournaledState<DB>) {
+        let Ok(instruction) = Instruction::try_from(frame.opcode) else {
+            return;
+        };
+        let top = |depth: usize| {
+            frame
+                .stack
+                .len()
+                .checked_sub(depth + 1)
+                .map(|index| frame.stack[index])
+        };
+        let address = |word: U256| H160::from(H256::from_uint(&word));
+
+        match instruction {
+            Instruction::SLoad | Instruction::SStore => {
+                if let Some(key) = top(0) {
+                    self.lookup_storage(frame.message.address, H256::from_uint(&key), state);
+                }
+            }
+            Instruction::Balance
+            | Instruction::ExtCodeSize
+            | Instruction::ExtCodeCopy
+            | Instruction::ExtCodeHash
+            | Instruction::SelfDestruct => {
+                if let Some(word) = top(0) {
+                    self.lookup_account(address(word), state);
+                }
+            }
+            Instruction::Call
+            | Instruction::CallCode
+            | Instruction::DelegateCall
+            | Instruction::StaticCall => {
+                if let Some(word) = top(1) {
+                    self.lookup_account(address(word), state);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn call(&mut self, message: &Message, state: &JournaledState<DB>) {
+        self.lookup_account(message.caller, state);
+        self.lookup_account(message.address, state);
+        self.lookup_account(message.code_address, state);
+    }
+
+    fn call_end(
+        &mut self,
+        message: &Message,
+        _outcome: &CallOutcome<'_>,
+        state: &JournaledState<DB>,
+    ) {
+        if message.depth == 0 && !self.in_transaction {
+            self.finish(state);
+        }
+    }
+
+    fn create(&mut self, message: &Message, state: &JournaledState<DB>) {
+        self.lookup_account(message.caller, state);
+        self.lookup_account(message.address, state);
+        self.created.insert(message.address);
+    }
+
+    fn create_end(
+        &mut self,
+        message: &Message,
+        _outcome: &CallOutcome<'_>,
+        state: &JournaledState<DB>,
+    ) {
+        if message.depth == 0 && !self.in_transaction {
+            self.finish(state);
+        }
+    }
+}
+
+fn optional_hex_bytes<S: serde::Serializer>(
+    bytes: &Option<Bytes>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match bytes {
+        Some(bytes) => hex_bytes(bytes, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::InMemoryDatabase;
+    use crate::env::BlockEnv;
+    use crate::transaction::SignedTransaction;
+    use crate::vm::Vm;
+    use serde_json::json;
+    use std::str::FromStr;
+
+    /// Legacy transaction from 0x9d8a..4f sending 1 ether to 0x3535..35 with nonce 9, a gas
+    /// price of 20 gwei and a gas limit of 21000.
+    const LEGACY: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+    // PUSH1 0x00 SLOAD PUSH1 0x01 ADD PUSH1 0x00 SSTORE PUSH1 0x00 PUSH1 0x01 SSTORE
+    const CODE: [u8; 14] = [
+        0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x01, 0x55,
+    ];
+
+    fn contract() -> H160 {
+        H160::from_low_u64_be(0xc0de)
+    }
+
+    fn slot(value: u64) -> H256 {
+        H256::from_low_u64_be(value)
+    }
+
+    /// Runs a contract adding one to slot 0 and clearing slot 1.
+    fn trace(tracer: PrestateTracer) -> PrestateResult {
+        let mut db = InMemoryDatabase::new();
+        db.insert_account(
+            contract(),
+            Account::new(U256::zero(), 0, Bytes::from_static(&CODE)),
+        );
+        db.set_storage(contract(), slot(0), slot(7));
+        db.set_storage(contract(), slot(1), slot(9));
+
+        let mut tracer = tracer;
+        let result = Vm::new(&mut db).with_inspector(&mut tracer).exec(Message {
+            caller: H160::from_low_u64_be(0xcafe),
+            address: contract(),
+            code_address: contract(),
+            code: Bytes::from_static(&CODE),
+            gas: 100_000,
+            ..Message::default()
+        });
+        assert!(result.is_success());
+
+        tracer.into_result().unwrap()
+    }
+
+    #[test]
+    fn prestate_matches_geth() {
+        assert_eq!(
+            serde_json::to_value(trace(PrestateTracer::new())).unwrap(),
+            json!({
+                "0x000000000000000000000000000000000000c0de": {
+                    "balance": "0x0",
+                    "code": "0x6000546001016000556000600155",
+                    "storage": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000000":
+                            "0x0000000000000000000000000000000000000000000000000000000000000007",
+                        "0x0000000000000000000000000000000000000000000000000000000000000001":
+                            "0x0000000000000000000000000000000000000000000000000000000000000009",
+                    },
+                },
+                "0x000000000000000000000000000000000000cafe": {"balance": "0x0"},
+            })
+        );
+    }
+
+    #[test]
+    fn diff_mode_matches_geth() {
+        assert_eq!(
+            serde_json::to_value(trace(PrestateTracer::new().with_diff_mode(true))).unwrap(),
+            json!({
+                "pre": {
+                    "0x000000000000000000000000000000000000c0de": {
+                        "balance": "0x0",
+                        "code": "0x6000546001016000556000600155",
+                        "storage": {
+                            "0x0000000000000000000000000000000000000000000000000000000000000000":
+                                "0x0000000000000000000000000000000000000000000000000000000000000007",
+                            "0x0000000000000000000000000000000000000000000000000000000000000001":
+                                "0x0000000000000000000000000000000000000000000000000000000000000009",
+                        },
+                    },
+                },
+                "post": {
+                    "0x000000000000000000000000000000000000c0de": {
+                        "storage": {
+                            "0x0000000000000000000000000000000000000000000000000000000000000000":
+                                "0x0000000000000000000000000000000000000000000000000000000000000008",
+                        },
+                    },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn diff_mode_covers_transaction_accounts() {
+        let sender = H160::from_str("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f").unwrap();
+        let recipient = H160::repeat_byte(0x35);
+        let coinbase = H160::from_low_u64_be(0xc0ffee);
+        let ether = U256::exp10(18);
+        let fee = U256::from(21000u64 * 20_000_000_000);
+
+        let mut db = InMemoryDatabase::new();
+        db.insert_account(sender, Account::new(ether * 2, 9, Bytes::new()));
+        let env = Env {
+            block: BlockEnv {
+                coinbase,
+                ..BlockEnv::default()
+            },
+            ..Env::default()
+        };
+        let transaction = SignedTransaction::decode(&hex::decode(LEGACY).unwrap()).unwrap();
+        let mut tracer = PrestateTracer::new().with_diff_mode(true);
+        let result = Vm::with_env(&mut db, env)
+            .with_inspector(&mut tracer)
+            .transact(&transaction)
+            .unwrap();
+        assert!(result.is_success());
+
+        let balance = |balance: U256| PrestateAccount {
+            balance: Some(balance),
+            ..PrestateAccount::default()
+        };
+        assert_eq!(
+            tracer.into_result().unwrap(),
+            PrestateResult::Diff {
+                pre: BTreeMap::from([
+                    (
+                        sender,
+                        PrestateAccount {
+                            nonce: Some(9),
+                            ..balance(ether * 2)
+                        }
+                    ),
+                    (recipient, balance(U256::zero())),
+                    (coinbase, balance(U256::zero())),
+                ]),
+                post: BTreeMap::from([
+                    (
+                        sender,
+                        PrestateAccount {
+                            nonce: Some(10),
+                            ..balance(ether - fee)
+                        }
+                    ),
+                    (recipient, balance(ether)),
+                    (coinbase, balance(fee)),
+                ]),
+            }
+        );
+    }
+}
@@ -0,0 +1,366 @@
+use super::{hex_bytes, opcode_name};
+use crate::database::Database;
+use crate::env::Env;
+use crate::error::ErrorKind;
+use crate::inspector::{CallOutcome, FrameView, Inspector};
+use crate::instruction::Instruction;
+use crate::journal::JournaledState;
+use crate::message::Message;
+use crate::result::{ExecutionResult, ExecutionStatus};
+use crate::transaction::Transaction;
+use bytes::Bytes;
+use ethereum_types::{BigEndianHash, H160, H256, U256};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// State of the VM before an instruction, as in geth's default `debug_traceTransaction` output.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+    pub pc: usize,
+    pub op: String,
+    pub gas: u64,
+    pub gas_cost: u64,
+    pub depth: usize,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Stack from the bottom up.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<Vec<U256>>,
+
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "optional_hex_bytes"
+    )]
+    pub return_data: Option<Bytes>,
+
+    /// Memory in 32 byte words, each in hex without a prefix.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<Vec<String>>,
+
+    /// Storage slots of the contract read or written so far, in hex without a prefix. Only set
+    /// for `SLOAD` and `SSTORE`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<String, String>>,
+
+    #[serde(rename = "refund", skip_serializing_if = "is_zero")]
+    pub refund: u64,
+}
+
+/// Outcome of an execution along with the state before each instruction.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLogResult {
+    /// Gas used, for a transaction the gas paid for.
+    pub gas: u64,
+
+    pub failed: bool,
+
+    #[serde(serialize_with = "hex_bytes")]
+    pub return_value: Bytes,
+
+    pub struct_logs: Vec<StructLog>,
+}
+
+/// Inspector recording the state of the VM before every instruction, as geth's default tracer
+/// does.
+///
+/// The stack and storage are recorded by default, memory and return data only if enabled.
+#[derive(Debug)]
+pub struct StructLogger {
+    memory: bool,
+    stack: bool,
+    storage: bool,
+    return_data: bool,
+    logs: Vec<StructLog>,
+
+    /// Gas left before the last instruction, to work out its cost.
+    gas_before: u64,
+
+    /// Storage slots read or written, by contract.
+    slots: HashMap<H160, BTreeMap<H256, H256>>,
+
+    /// Refunds of the frames running, by depth.
+    refunds: Vec<i64>,
+
+    in_transaction: bool,
+    result: Option<(u64, bool, Bytes)>,
+}
+
+impl Default for StructLogger {
+    fn default() -> Self {
+        StructLogger::new()
+    }
+}
+
+impl StructLogger {
+    pub fn new() -> StructLogger {
+        StructLogger {
+            memory: false,
+            stack: true,
+            storage: true,
+            return_data: false,
+            logs: Vec::new(),
+            gas_before: 0,
+            slots: HashMap::new(),
+            refunds: Vec::new(),
+            in_transaction: false,
+            result: None,
+        }
+    }
+
+    /// Record the memory before each instruction.
+    pub fn with_memory(mut self, enabled: bool) -> StructLogger {
+        self.memory = enabled;
+        self
+    }
+
+    /// Record the stack before each instruction.
+    pub fn with_stack(mut self, enabled: bool) -> StructLogger {
+        self.stack = enabled;
+        self
+    }
+
+    /// Record the storage slots read or written at `SLOAD` and `SSTORE`.
+    pub fn with_storage(mut self, enabled: bool) -> StructLogger {
+        self.storage = enabled;
+        self
+    }
+
+    /// Record the output of the last call made by the frame before each instruction.
+    pub fn with_return_data(mut self, enabled: bool) -> StructLogger {
+        self.return_data = enabled;
+        self
+    }
+
+    /// The instructions recorded so far.
+    pub fn logs(&self) -> &[StructLog] {
+        &self.logs
+    }
+
+    /// The trace of the execution, `None` if it hasn't ended.
+    pub fn result(&self) -> Option<StructLogResult> {
+        let (gas, failed, return_value) = self.result.clone()?;
+
+        Some(StructLogResult {
+            gas,
+            failed,
+            return_value,
+            struct_logs: self.logs.clone(),
+        })
+    }
+
+    /// Storage slots of `address` to record before `frame` executes an `SLOAD` or `SSTORE`.
+    fn storage<DB: Database>(
+        &mut self,
+        frame: &FrameView<'_>,
+        state: &JournaledState<DB>,
+    ) -> Option<BTreeMap<String, String>> {
+        let instruction = Instruction::try_from(frame.opcode).ok()?;
+        let top = |depth: usize| {
+            frame
+                .stack
+                .len()
+                .checked_sub(depth + 1)
+                .map(|index| H256::from_uint(&frame.stack[index]))
+        };
+        let address = frame.message.address;
+
+        let (key, value) = match instruction {
+            Instruction::SLoad => {
+                let key = top(0)?;
+                (key, state.storage(address, key))
+            }
+            Instruction::SStore => (top(0)?, top(1)?),
+            _ => return None,
+        };
+
+        let slots = self.slots.entry(address).or_default();
+        slots.insert(key, value);
+        Some(
+            slots
+                .iter()
+                .map(|(key, value)| (format!("{:x}", key), format!("{:x}", value)))
+                .collect(),
+        )
+    }
+
+    fn finish(&mut self, gas_used: u64, status: &ExecutionStatus, output: &[u8]) {
+        self.result = Some((
+            gas_used,
+            !status.is_success(),
+            Bytes::copy_from_slice(output),
+        ));
+    }
+}
+
+impl<DB: Database> Inspector<DB> for StructLogger {
+    fn transaction(
+        &mut self,
+        _env: &Env,
+        _sender: H160,
+        _transaction: &Transaction,
+        _state: &JournaledState<DB>,
+    ) {
+        self.in_transaction = true;
+    }
+
+    fn transaction_end(&mut self, result: &ExecutionResult, _state: &JournaledState<DB>) {
+        self.finish(result.gas_used, &result.status, &result.output);
+    }
+
+    fn step(&mut self, frame: &FrameView<'_>, state: &JournaledState<DB>) {
+        self.refunds.resize(frame.depth, 0);
+        self.refunds[frame.depth - 1] = frame.refund;
+        self.gas_before = frame.gas;
+
+        let storage = if self.storage {
+            self.storage(frame, state)
+        } else {
+            None
+        };
+
+        self.logs.push(StructLog {
+            pc: frame.pc,
+            op: opcode_name(frame.opcode),
+            gas: frame.gas,
+            gas_cost: 0,
+            depth: frame.depth,
+            error: None,
+            stack: self.stack.then(|| frame.stack.to_vec()),
+            return_data: (self.return_data && !frame.return_data.is_empty())
+                .then(|| Bytes::copy_from_slice(frame.return_data)),
+            memory: self.memory.then(|| {
+                frame
+                    .memory
+                    .chunks(32)
+                    .map(|word| word.iter().map(|byte| format!("{:02x}", byte)).collect())
+                    .collect()
+            }),
+            storage,
+            refund: self.refunds.iter().sum::<i64>().max(0) as u64,
+        });
+    }
+
+    fn step_end(
+        &mut self,
+        frame: &FrameView<'_>,
+        error: Option<ErrorKind>,
+        _state: &JournaledState<DB>,
+    ) {
+        if let Some(log) = self.logs.last_mut() {
+            log.gas_cost = self.gas_before.saturating_sub(frame.gas);
+            log.error = error.map(|error| error.to_string());
+        }
+    }
+
+    fn call_end(
+        &mut self,
+        message: &Message,
+        outcome: &CallOutcome<'_>,
+        _state: &JournaledState<DB>,
+    ) {
+        if message.depth == 0 && !self.in_transaction {
+            self.finish(outcome.gas_used, &outcome.status, outcome.output);
+        }
+    }
+
+    fn create_end(
+        &mut self,
+        message: &Message,
+        outcome: &CallOutcome<'_>,
+        _state: &JournaledState<DB>,
+    ) {
+        if message.depth == 0 && !self.in_transaction {
+            self.finish(outcome.gas_used, &outcome.status, outcome.output);
+        }
+    }
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+fn optional_hex_bytes<S: serde::Serializer>(
+    bytes: &Option<Bytes>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match bytes {
+        Some(bytes) => hex_bytes(bytes, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::InMemoryDatabase;
+    use crate::vm::Vm;
+    use serde_json::json;
+
+    #[test]
+    fn struct_logs_match_geth() {
+        // PUSH1 0x2a PUSH1 0x00 SSTORE PUSH1 0x00 SLOAD PUSH1 0x00 PUSH1 0x00 REVERT
+        let code = [
+            0x60, 0x2a, 0x60, 0x00, 0x55, 0x60, 0x00, 0x54, 0x60, 0x00, 0x60, 0x00, 0xfd,
+        ];
+        let mut logger = StructLogger::new();
+        Vm::new(InMemoryDatabase::new())
+            .with_inspector(&mut logger)
+            .exec(Message {
+                code: Bytes::copy_from_slice(&code),
+                gas: 100_000,
+                ..Message::default()
+            });
+
+        let slot = "0000000000000000000000000000000000000000000000000000000000000000";
+        let value = "000000000000000000000000000000000000000000000000000000000000002a";
+        assert_eq!(
+            serde_json::to_value(logger.result().unwrap()).unwrap(),
+            json!({
+                "gas": 22215,
+                "failed": true,
+                "returnValue": "0x",
+                "structLogs": [
+                    {"pc": 0, "op": "PUSH1", "gas": 100000, "gasCost": 3, "depth": 1, "stack": []},
+                    {"pc": 2, "op": "PUSH1", "gas": 99997, "gasCost": 3, "depth": 1, "stack": ["0x2a"]},
+                    {
+                        "pc": 4, "op": "SSTORE", "gas": 99994, "gasCost": 22100, "depth": 1,
+                        "stack": ["0x2a", "0x0"], "storage": {slot: value}
+                    },
+                    {"pc": 5, "op": "PUSH1", "gas": 77894, "gasCost": 3, "depth": 1, "stack": []},
+                    {
+                        "pc": 7, "op": "SLOAD", "gas": 77891, "gasCost": 100, "depth": 1,
+                        "stack": ["0x0"], "storage": {slot: value}
+                    },
+                    {"pc": 8, "op": "PUSH1", "gas": 77791, "gasCost": 3, "depth": 1, "stack": ["0x2a"]},
+                    {"pc": 10, "op": "PUSH1", "gas": 77788, "gasCost": 3, "depth": 1, "stack": ["0x2a", "0x0"]},
+                    {"pc": 12, "op": "REVERT", "gas": 77785, "gasCost": 0, "depth": 1, "stack": ["0x2a", "0x0", "0x0"]},
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn failing_instruction_reports_error() {
+        let mut logger = StructLogger::new().with_stack(false);
+        // POP
+        Vm::new(InMemoryDatabase::new())
+            .with_inspector(&mut logger)
+            .exec(Message {
+                code: Bytes::from_static(&[0x50]),
+                gas: 100,
+                ..Message::default()
+            });
+
+        let result = logger.result().unwrap();
+        assert!(result.failed);
+        assert_eq!(result.gas, 100);
+        assert_eq!(
+            serde_json::to_value(&result.struct_logs).unwrap(),
+            json!([{"pc": 0, "op": "POP", "gas": 100, "gasCost": 2, "depth": 1, "error": "stack underflow"}])
+        );
+    }
+}
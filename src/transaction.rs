@@ -1,29 +1,819 @@
+use crate::hash::keccak256;
+use crate::rlp::{self, Decodable, DecodeError, Encodable, Header};
+use crate::signature::{Signature, SignatureError};
 use bytes::Bytes;
+use ethereum_types::{H160, H256, U256};
 
-/// Transaction format accepted by the EVM.
-///
-/// This isn't the format of a transaction that is given to JSONRPC but rather the format we will
-/// take with the VM. For now, it will closely follow the testing format.
+/// Destination of a transaction.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum TxKind {
+    /// Create a contract with the data as init code.
+    Create,
+    /// Call the account at the address.
+    Call(H160),
+}
+
+impl Encodable for TxKind {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            TxKind::Create => [][..].encode(out),
+            TxKind::Call(address) => address.encode(out),
+        }
+    }
+}
+
+impl Decodable for TxKind {
+    fn decode(buf: &mut &[u8]) -> Result<TxKind, DecodeError> {
+        match rlp::decode_bytes(buf)? {
+            [] => Ok(TxKind::Create),
+            bytes if bytes.len() == H160::len_bytes() => Ok(TxKind::Call(H160::from_slice(bytes))),
+            _ => Err(DecodeError::InvalidLength),
+        }
+    }
+}
+
+/// Account and storage slots a transaction declares it will access (EIP-2930).
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct AccessListItem {
+    pub address: H160,
+    pub storage_keys: Vec<H256>,
+}
+
+impl Encodable for AccessListItem {
+    fn encode(&self, out: &mut Vec<u8>) {
+        rlp::encode_list(&[&self.address, &self.storage_keys], out)
+    }
+}
+
+impl Decodable for AccessListItem {
+    fn decode(buf: &mut &[u8]) -> Result<AccessListItem, DecodeError> {
+        rlp::decode_list(buf, |payload| {
+            Ok(AccessListItem {
+                address: H160::decode(payload)?,
+                storage_keys: Vec::decode(payload)?,
+            })
+        })
+    }
+}
+
+/// Signed permission for an account to delegate to the code at `address` (EIP-7702).
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Authorization {
+    /// Chain the authorization is valid on, zero for any chain.
+    pub chain_id: U256,
+
+    /// Account whose code is delegated to.
+    pub address: H160,
+
+    /// Nonce the authority must have for the authorization to apply.
+    pub nonce: u64,
+
+    pub y_parity: u8,
+    pub r: U256,
+    pub s: U256,
+}
+
+impl Encodable for Authorization {
+    fn encode(&self, out: &mut Vec<u8>) {
+        rlp::encode_list(
+            &[
+                &self.chain_id,
+                &self.address,
+                &self.nonce,
+                &self.y_parity,
+                &self.r,
+                &self.s,
+            ],
+            out,
+        )
+    }
+}
+
+impl Authorization {
+    /// Hash the authority signs, over the chain id, address and nonce (EIP-7702).
+    pub fn signing_hash(&self) -> H256 {
+        let mut encoded = vec![0x05];
+        rlp::encode_list(&[&self.chain_id, &self.address, &self.nonce], &mut encoded);
+        keccak256(&encoded)
+    }
+
+    /// Account granting the authorization.
+    pub fn recover_authority(&self) -> Result<H160, SignatureError> {
+        Signature {
+            y_parity: self.y_parity,
+            r: self.r,
+            s: self.s,
+        }
+        .recover_address(self.signing_hash())
+    }
+}
+
+impl Decodable for Authorization {
+    fn decode(buf: &mut &[u8]) -> Result<Authorization, DecodeError> {
+        rlp::decode_list(buf, |payload| {
+            Ok(Authorization {
+                chain_id: U256::decode(payload)?,
+                address: H160::decode(payload)?,
+                nonce: u64::decode(payload)?,
+                y_parity: u8::decode(payload)?,
+                r: U256::decode(payload)?,
+                s: U256::decode(payload)?,
+            })
+        })
+    }
+}
+
+/// Transaction from before typed transactions, optionally replay protected (EIP-155).
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct LegacyTransaction {
+    /// Chain the transaction is valid on, `None` if valid on any chain.
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    pub gas_price: U256,
+    pub gas_limit: u64,
+    pub to: TxKind,
+    pub value: U256,
+    pub data: Bytes,
+}
+
+impl LegacyTransaction {
+    fn encode_fields(&self, out: &mut Vec<u8>) {
+        self.nonce.encode(out);
+        self.gas_price.encode(out);
+        self.gas_limit.encode(out);
+        self.to.encode(out);
+        self.value.encode(out);
+        self.data.encode(out);
+    }
+
+    /// Decode the fields, the chain id is filled in from the signature.
+    fn decode_fields(buf: &mut &[u8]) -> Result<LegacyTransaction, DecodeError> {
+        Ok(LegacyTransaction {
+            chain_id: None,
+            nonce: u64::decode(buf)?,
+            gas_price: U256::decode(buf)?,
+            gas_limit: u64::decode(buf)?,
+            to: TxKind::decode(buf)?,
+            value: U256::decode(buf)?,
+            data: Bytes::decode(buf)?,
+        })
+    }
+}
+
+/// Transaction with an access list (EIP-2930).
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct AccessListTransaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub gas_price: U256,
+    pub gas_limit: u64,
+    pub to: TxKind,
+    pub value: U256,
+    pub data: Bytes,
+    pub access_list: Vec<AccessListItem>,
+}
+
+impl AccessListTransaction {
+    fn encode_fields(&self, out: &mut Vec<u8>) {
+        self.chain_id.encode(out);
+        self.nonce.encode(out);
+        self.gas_price.encode(out);
+        self.gas_limit.encode(out);
+        self.to.encode(out);
+        self.value.encode(out);
+        self.data.encode(out);
+        self.access_list.encode(out);
+    }
+
+    fn decode_fields(buf: &mut &[u8]) -> Result<AccessListTransaction, DecodeError> {
+        Ok(AccessListTransaction {
+            chain_id: u64::decode(buf)?,
+            nonce: u64::decode(buf)?,
+            gas_price: U256::decode(buf)?,
+            gas_limit: u64::decode(buf)?,
+            to: TxKind::decode(buf)?,
+            value: U256::decode(buf)?,
+            data: Bytes::decode(buf)?,
+            access_list: Vec::decode(buf)?,
+        })
+    }
+}
+
+/// Transaction paying a priority fee on top of the base fee (EIP-1559).
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct FeeMarketTransaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas_limit: u64,
+    pub to: TxKind,
+    pub value: U256,
+    pub data: Bytes,
+    pub access_list: Vec<AccessListItem>,
+}
+
+impl FeeMarketTransaction {
+    fn encode_fields(&self, out: &mut Vec<u8>) {
+        self.chain_id.encode(out);
+        self.nonce.encode(out);
+        self.max_priority_fee_per_gas.encode(out);
+        self.max_fee_per_gas.encode(out);
+        self.gas_limit.encode(out);
+        self.to.encode(out);
+        self.value.encode(out);
+        self.data.encode(out);
+        self.access_list.encode(out);
+    }
+
+    fn decode_fields(buf: &mut &[u8]) -> Result<FeeMarketTransaction, DecodeError> {
+        Ok(FeeMarketTransaction {
+            chain_id: u64::decode(buf)?,
+            nonce: u64::decode(buf)?,
+            max_priority_fee_per_gas: U256::decode(buf)?,
+            max_fee_per_gas: U256::decode(buf)?,
+            gas_limit: u64::decode(buf)?,
+            to: TxKind::decode(buf)?,
+            value: U256::decode(buf)?,
+            data: Bytes::decode(buf)?,
+            access_list: Vec::decode(buf)?,
+        })
+    }
+}
+
+/// Transaction carrying blobs (EIP-4844).
 ///
-/// This is intentionally incomplete and we will slowly expand it as we require the fields.
-#[derive(Debug)]
-pub struct Transaction {
-    /// Code to be executed.
-    pub code: Bytes,
+/// Only the versioned hashes of the blobs are part of the transaction. Blob transactions can't
+/// create contracts.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct BlobTransaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas_limit: u64,
+    pub to: H160,
+    pub value: U256,
+    pub data: Bytes,
+    pub access_list: Vec<AccessListItem>,
+    pub max_fee_per_blob_gas: U256,
+    pub blob_versioned_hashes: Vec<H256>,
+}
+
+impl BlobTransaction {
+    fn encode_fields(&self, out: &mut Vec<u8>) {
+        self.chain_id.encode(out);
+        self.nonce.encode(out);
+        self.max_priority_fee_per_gas.encode(out);
+        self.max_fee_per_gas.encode(out);
+        self.gas_limit.encode(out);
+        self.to.encode(out);
+        self.value.encode(out);
+        self.data.encode(out);
+        self.access_list.encode(out);
+        self.max_fee_per_blob_gas.encode(out);
+        self.blob_versioned_hashes.encode(out);
+    }
 
-    /// Call data.
+    fn decode_fields(buf: &mut &[u8]) -> Result<BlobTransaction, DecodeError> {
+        Ok(BlobTransaction {
+            chain_id: u64::decode(buf)?,
+            nonce: u64::decode(buf)?,
+            max_priority_fee_per_gas: U256::decode(buf)?,
+            max_fee_per_gas: U256::decode(buf)?,
+            gas_limit: u64::decode(buf)?,
+            to: H160::decode(buf)?,
+            value: U256::decode(buf)?,
+            data: Bytes::decode(buf)?,
+            access_list: Vec::decode(buf)?,
+            max_fee_per_blob_gas: U256::decode(buf)?,
+            blob_versioned_hashes: Vec::decode(buf)?,
+        })
+    }
+}
+
+/// Transaction setting the code of accounts to delegate to other accounts (EIP-7702).
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct SetCodeTransaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas_limit: u64,
+    pub to: H160,
+    pub value: U256,
     pub data: Bytes,
+    pub access_list: Vec<AccessListItem>,
+    pub authorization_list: Vec<Authorization>,
+}
+
+impl SetCodeTransaction {
+    fn encode_fields(&self, out: &mut Vec<u8>) {
+        self.chain_id.encode(out);
+        self.nonce.encode(out);
+        self.max_priority_fee_per_gas.encode(out);
+        self.max_fee_per_gas.encode(out);
+        self.gas_limit.encode(out);
+        self.to.encode(out);
+        self.value.encode(out);
+        self.data.encode(out);
+        self.access_list.encode(out);
+        self.authorization_list.encode(out);
+    }
+
+    fn decode_fields(buf: &mut &[u8]) -> Result<SetCodeTransaction, DecodeError> {
+        Ok(SetCodeTransaction {
+            chain_id: u64::decode(buf)?,
+            nonce: u64::decode(buf)?,
+            max_priority_fee_per_gas: U256::decode(buf)?,
+            max_fee_per_gas: U256::decode(buf)?,
+            gas_limit: u64::decode(buf)?,
+            to: H160::decode(buf)?,
+            value: U256::decode(buf)?,
+            data: Bytes::decode(buf)?,
+            access_list: Vec::decode(buf)?,
+            authorization_list: Vec::decode(buf)?,
+        })
+    }
+}
+
+/// Transaction of any supported type, without its signature.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum Transaction {
+    Legacy(LegacyTransaction),
+    AccessList(AccessListTransaction),
+    FeeMarket(FeeMarketTransaction),
+    Blob(BlobTransaction),
+    SetCode(SetCodeTransaction),
+}
+
+impl Transaction {
+    /// EIP-2718 type, zero for legacy transactions.
+    pub fn tx_type(&self) -> u8 {
+        match self {
+            Transaction::Legacy(_) => 0,
+            Transaction::AccessList(_) => 1,
+            Transaction::FeeMarket(_) => 2,
+            Transaction::Blob(_) => 3,
+            Transaction::SetCode(_) => 4,
+        }
+    }
+
+    /// Chain the transaction is valid on, `None` for legacy transactions valid on any chain.
+    pub fn chain_id(&self) -> Option<u64> {
+        match self {
+            Transaction::Legacy(tx) => tx.chain_id,
+            Transaction::AccessList(tx) => Some(tx.chain_id),
+            Transaction::FeeMarket(tx) => Some(tx.chain_id),
+            Transaction::Blob(tx) => Some(tx.chain_id),
+            Transaction::SetCode(tx) => Some(tx.chain_id),
+        }
+    }
+
+    pub fn nonce(&self) -> u64 {
+        match self {
+            Transaction::Legacy(tx) => tx.nonce,
+            Transaction::AccessList(tx) => tx.nonce,
+            Transaction::FeeMarket(tx) => tx.nonce,
+            Transaction::Blob(tx) => tx.nonce,
+            Transaction::SetCode(tx) => tx.nonce,
+        }
+    }
+
+    pub fn gas_limit(&self) -> u64 {
+        match self {
+            Transaction::Legacy(tx) => tx.gas_limit,
+            Transaction::AccessList(tx) => tx.gas_limit,
+            Transaction::FeeMarket(tx) => tx.gas_limit,
+            Transaction::Blob(tx) => tx.gas_limit,
+            Transaction::SetCode(tx) => tx.gas_limit,
+        }
+    }
+
+    /// Most the sender pays per unit of gas, the gas price for transactions without a fee market.
+    pub fn max_fee_per_gas(&self) -> U256 {
+        match self {
+            Transaction::Legacy(tx) => tx.gas_price,
+            Transaction::AccessList(tx) => tx.gas_price,
+            Transaction::FeeMarket(tx) => tx.max_fee_per_gas,
+            Transaction::Blob(tx) => tx.max_fee_per_gas,
+            Transaction::SetCode(tx) => tx.max_fee_per_gas,
+        }
+    }
+
+    /// Most the sender pays per unit of gas on top of the base fee, `None` without a fee market.
+    pub fn max_priority_fee_per_gas(&self) -> Option<U256> {
+        match self {
+            Transaction::Legacy(_) | Transaction::AccessList(_) => None,
+            Transaction::FeeMarket(tx) => Some(tx.max_priority_fee_per_gas),
+            Transaction::Blob(tx) => Some(tx.max_priority_fee_per_gas),
+            Transaction::SetCode(tx) => Some(tx.max_priority_fee_per_gas),
+        }
+    }
+
+    pub fn to(&self) -> TxKind {
+        match self {
+            Transaction::Legacy(tx) => tx.to,
+            Transaction::AccessList(tx) => tx.to,
+            Transaction::FeeMarket(tx) => tx.to,
+            Transaction::Blob(tx) => TxKind::Call(tx.to),
+            Transaction::SetCode(tx) => TxKind::Call(tx.to),
+        }
+    }
+
+    pub fn value(&self) -> U256 {
+        match self {
+            Transaction::Legacy(tx) => tx.value,
+            Transaction::AccessList(tx) => tx.value,
+            Transaction::FeeMarket(tx) => tx.value,
+            Transaction::Blob(tx) => tx.value,
+            Transaction::SetCode(tx) => tx.value,
+        }
+    }
+
+    /// Call data, or the init code when creating a contract.
+    pub fn data(&self) -> &Bytes {
+        match self {
+            Transaction::Legacy(tx) => &tx.data,
+            Transaction::AccessList(tx) => &tx.data,
+            Transaction::FeeMarket(tx) => &tx.data,
+            Transaction::Blob(tx) => &tx.data,
+            Transaction::SetCode(tx) => &tx.data,
+        }
+    }
+
+    /// Access list, empty for legacy transactions.
+    pub fn access_list(&self) -> &[AccessListItem] {
+        match self {
+            Transaction::Legacy(_) => &[],
+            Transaction::AccessList(tx) => &tx.access_list,
+            Transaction::FeeMarket(tx) => &tx.access_list,
+            Transaction::Blob(tx) => &tx.access_list,
+            Transaction::SetCode(tx) => &tx.access_list,
+        }
+    }
+
+    /// Most the sender pays per unit of blob gas, `None` if the transaction carries no blobs.
+    pub fn max_fee_per_blob_gas(&self) -> Option<U256> {
+        match self {
+            Transaction::Blob(tx) => Some(tx.max_fee_per_blob_gas),
+            _ => None,
+        }
+    }
+
+    /// Versioned hashes of the blobs carried by the transaction.
+    pub fn blob_versioned_hashes(&self) -> &[H256] {
+        match self {
+            Transaction::Blob(tx) => &tx.blob_versioned_hashes,
+            _ => &[],
+        }
+    }
+
+    /// Authorizations to set the code of accounts.
+    pub fn authorization_list(&self) -> &[Authorization] {
+        match self {
+            Transaction::SetCode(tx) => &tx.authorization_list,
+            _ => &[],
+        }
+    }
+
+    /// Hash the sender signs.
+    ///
+    /// Legacy transactions with a chain id append it and two zeros to the fields, which stops them
+    /// from being replayed on other chains (EIP-155). Typed transactions prefix their type.
+    pub fn signing_hash(&self) -> H256 {
+        let mut payload = Vec::new();
+        self.encode_fields(&mut payload);
+
+        if let Transaction::Legacy(LegacyTransaction {
+            chain_id: Some(chain_id),
+            ..
+        }) = self
+        {
+            chain_id.encode(&mut payload);
+            0u8.encode(&mut payload);
+            0u8.encode(&mut payload);
+        }
+
+        let mut encoded = Vec::with_capacity(payload.len() + 10);
+        if self.tx_type() != 0 {
+            encoded.push(self.tx_type());
+        }
+        Header {
+            list: true,
+            payload_length: payload.len(),
+        }
+        .encode(&mut encoded);
+        encoded.extend(payload);
+
+        keccak256(&encoded)
+    }
+
+    /// Append the fields of the transaction, without any signature, to `out`.
+    fn encode_fields(&self, out: &mut Vec<u8>) {
+        match self {
+            Transaction::Legacy(tx) => tx.encode_fields(out),
+            Transaction::AccessList(tx) => tx.encode_fields(out),
+            Transaction::FeeMarket(tx) => tx.encode_fields(out),
+            Transaction::Blob(tx) => tx.encode_fields(out),
+            Transaction::SetCode(tx) => tx.encode_fields(out),
+        }
+    }
+}
+
+/// Transaction along with the signature of its sender.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct SignedTransaction {
+    pub transaction: Transaction,
+    pub signature: Signature,
+}
+
+impl SignedTransaction {
+    /// Decode a raw transaction, either an RLP list for legacy transactions or the type byte
+    /// followed by the RLP payload for typed transactions (EIP-2718).
+    ///
+    /// Blob transactions are expected without their blobs, as they appear in blocks.
+    pub fn decode(raw: &[u8]) -> Result<SignedTransaction, DecodeError> {
+        let (tx_type, mut buf) = match raw.first() {
+            None => return Err(DecodeError::InputTooShort),
+            Some(0xc0..=0xff) => (0, raw),
+            Some(tx_type @ 0x01..=0x7f) => (*tx_type, &raw[1..]),
+            Some(_) => return Err(DecodeError::Custom("unsupported transaction type")),
+        };
+
+        let signed = rlp::decode_list(&mut buf, |payload| {
+            let transaction = match tx_type {
+                0 => Transaction::Legacy(LegacyTransaction::decode_fields(payload)?),
+                1 => Transaction::AccessList(AccessListTransaction::decode_fields(payload)?),
+                2 => Transaction::FeeMarket(FeeMarketTransaction::decode_fields(payload)?),
+                3 => Transaction::Blob(BlobTransaction::decode_fields(payload)?),
+                4 => Transaction::SetCode(SetCodeTransaction::decode_fields(payload)?),
+                _ => return Err(DecodeError::Custom("unsupported transaction type")),
+            };
+
+            Ok((
+                transaction,
+                u64::decode(payload)?,
+                U256::decode(payload)?,
+                U256::decode(payload)?,
+            ))
+        });
+        let (mut transaction, v, r, s) = signed?;
+
+        if !buf.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+
+        // An out of range parity still decodes, it fails when recovering the sender.
+        let y_parity = match &mut transaction {
+            Transaction::Legacy(tx) => match v {
+                27..=34 => (v - 27) as u8,
+                35.. => {
+                    tx.chain_id = Some((v - 35) / 2);
+                    ((v - 35) % 2) as u8
+                }
+                _ => return Err(DecodeError::Custom("invalid signature v")),
+            },
+            _ => u8::try_from(v).map_err(|_| DecodeError::Overflow)?,
+        };
+
+        Ok(SignedTransaction {
+            transaction,
+            signature: Signature { y_parity, r, s },
+        })
+    }
+
+    /// Raw encoding of the transaction as accepted by `decode`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        self.transaction.encode_fields(&mut payload);
+
+        match &self.transaction {
+            Transaction::Legacy(tx) => self.signature.legacy_v(tx.chain_id).encode(&mut payload),
+            _ => self.signature.y_parity.encode(&mut payload),
+        }
+        self.signature.r.encode(&mut payload);
+        self.signature.s.encode(&mut payload);
+
+        let mut out = Vec::with_capacity(payload.len() + 10);
+        if self.transaction.tx_type() != 0 {
+            out.push(self.transaction.tx_type());
+        }
+        Header {
+            list: true,
+            payload_length: payload.len(),
+        }
+        .encode(&mut out);
+        out.extend(payload);
+        out
+    }
+
+    /// Transaction hash, the hash of the raw encoding.
+    pub fn hash(&self) -> H256 {
+        keccak256(&self.encode())
+    }
+
+    /// Address of the account that signed the transaction.
+    pub fn recover_sender(&self) -> Result<H160, SignatureError> {
+        self.signature
+            .recover_address(self.transaction.signing_hash())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::str::FromStr;
+
+    /// Decode `raw`, check it encodes back the same and has the given hash.
+    fn decode(raw: &str, hash: &str) -> SignedTransaction {
+        let raw = hex::decode(raw).unwrap();
+        let signed = SignedTransaction::decode(&raw).unwrap();
+
+        assert_eq!(signed.encode(), raw);
+        assert_eq!(signed.hash(), H256::from_str(hash).unwrap());
+        signed
+    }
+
+    fn to() -> H160 {
+        H160::repeat_byte(0x35)
+    }
+
+    fn access_list() -> Vec<AccessListItem> {
+        vec![AccessListItem {
+            address: H160::from_low_u64_be(0x0100),
+            storage_keys: vec![H256::zero(), H256::from_low_u64_be(1)],
+        }]
+    }
 
     #[test]
-    pub fn can_construct_transaction() {
-        let _ = Transaction {
-            code: Bytes::from("hello world"),
-            data: Bytes::from("asd"),
-        };
+    fn legacy_with_chain_id() {
+        // The example from EIP-155.
+        let signed = decode(
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83",
+            "33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788",
+        );
+
+        assert_eq!(
+            signed.transaction,
+            Transaction::Legacy(LegacyTransaction {
+                chain_id: Some(1),
+                nonce: 9,
+                gas_price: U256::from(20_000_000_000u64),
+                gas_limit: 21000,
+                to: TxKind::Call(to()),
+                value: U256::from(1_000_000_000_000_000_000u64),
+                data: Bytes::new(),
+            })
+        );
+        assert_eq!(signed.signature.y_parity, 0);
+        assert_eq!(
+            signed.transaction.signing_hash(),
+            H256::from_str("daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53")
+                .unwrap()
+        );
+        assert_eq!(
+            signed.recover_sender(),
+            Ok(H160::from_str("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f").unwrap())
+        );
+    }
+
+    #[test]
+    fn legacy_contract_creation() {
+        let signed = decode(
+            "f8518001830186a080808560006000551ca0cb8f9f1ef5f34cca80c5af391994befc52b75a4e4b01b36c8a64be256027228ea05e75f7a2e5e56bf34318e0006eefaeb7f9e40b3281e925459340e5c82e43e8a5",
+            "9c2e51b90cf65397d939fe7ddd5843904f0193cbb7b349da467840f52833a5a3",
+        );
+
+        assert_eq!(signed.transaction.chain_id(), None);
+        assert_eq!(signed.transaction.to(), TxKind::Create);
+        assert_eq!(
+            signed.transaction.data(),
+            &Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0x55])
+        );
+        assert_eq!(signed.signature.y_parity, 1);
+    }
+
+    #[test]
+    fn access_list_transaction() {
+        let signed = decode(
+            "01f8c001010a82c3509435353535353535353535353535353535353535350583abcdeff85bf859940000000000000000000000000000000000000100f842a00000000000000000000000000000000000000000000000000000000000000000a0000000000000000000000000000000000000000000000000000000000000000101a0562ccff02dbded0dcade4f378b439608f79acbf2b94bad362b195210d24bf0d4a061472fbb8701d67d02860c5d8038e0b9f0610816d9fad948eaf9e25c96ff8adc",
+            "702ea24bf5da1380ddae84c50483ad41ee3b33d852183dac1237f0ef9de23061",
+        );
+
+        assert_eq!(
+            signed.transaction,
+            Transaction::AccessList(AccessListTransaction {
+                chain_id: 1,
+                nonce: 1,
+                gas_price: U256::from(10),
+                gas_limit: 50000,
+                to: TxKind::Call(to()),
+                value: U256::from(5),
+                data: Bytes::from_static(&[0xab, 0xcd, 0xef]),
+                access_list: access_list(),
+            })
+        );
+    }
+
+    #[test]
+    fn fee_market_transaction() {
+        let signed = decode(
+            "02f8c70102847735940085174876e80082ea609435353535353535353535353535353535353535350701f85bf859940000000000000000000000000000000000000100f842a00000000000000000000000000000000000000000000000000000000000000000a0000000000000000000000000000000000000000000000000000000000000000180a0091de516fe3fe3727db564c755dd0dfe126dab03229a1f34358d89d4e6b08fdfa05533899d1cf8309cc28afbe06a322254eaeea487b4f61bde3344a622f4cf90d0",
+            "da9a57901df3c546a756adb7a0f567d9d37b17bf6782ffed16c3be24047e3051",
+        );
+
+        assert_eq!(signed.transaction.tx_type(), 2);
+        assert_eq!(
+            signed.transaction.max_priority_fee_per_gas(),
+            Some(U256::from(2_000_000_000u64))
+        );
+        assert_eq!(
+            signed.transaction.max_fee_per_gas(),
+            U256::from(100_000_000_000u64)
+        );
+        assert_eq!(signed.transaction.access_list(), &access_list()[..]);
+    }
+
+    #[test]
+    fn blob_transaction() {
+        let signed = decode(
+            "03f885010301648252089435353535353535353535353535353535353535358080c003e1a0010000000000000000000000000000000000000000000000000000000000000180a01baa52eceab81f91af8acbb6e9edab66b50ee007e6f2cc7f7bbbbd731801b815a05cba7e5f473275db34b697e038fef93bfc12fd1430fdac68e7c5b5bd8a65940d",
+            "8e86e28527e2d1b25881d87db3efe3a4840d3615f7b191b6638802595a29d2ae",
+        );
+
+        assert_eq!(
+            signed.transaction.max_fee_per_blob_gas(),
+            Some(U256::from(3))
+        );
+        assert_eq!(
+            signed.transaction.blob_versioned_hashes(),
+            &[
+                H256::from_str("0100000000000000000000000000000000000000000000000000000000000001")
+                    .unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn set_code_transaction() {
+        let signed = decode(
+            "04f8c101040164830138809435353535353535353535353535353535353535358080c0f85cf85a019400000000000000000000000000000000000002000501a07841ba47d60bdaf403e9563b979404d7260534ce32088a904e9e1a93198495f5a00a75cd9aa6b9bbd01fd18c75a636d8a8ad532f8e9ca89b32a1befe206f1c3c5580a02f50ec6d5a6aa9f882e46cb6aa6e329a67157e66b6819636bb09d65102192260a06cc21cbd67ad3da43055eb8c1934ff5c2340a0df5a94c0ac346072d99129ca83",
+            "a27e6124390d156e17dad1a051acb9fb620c576e25dca7bb3512dcc4679e545d",
+        );
+
+        let authorizations = signed.transaction.authorization_list();
+        assert_eq!(authorizations.len(), 1);
+        assert_eq!(authorizations[0].chain_id, U256::one());
+        assert_eq!(authorizations[0].address, H160::from_low_u64_be(0x0200));
+        assert_eq!(authorizations[0].nonce, 5);
+        assert_eq!(
+            authorizations[0].signing_hash(),
+            H256::from_str("38dc835793fe1b2005baef2620f869774f4137069ea98621bb338ff640a4b6a4")
+                .unwrap()
+        );
+        assert_eq!(
+            authorizations[0].recover_authority(),
+            signed.recover_sender()
+        );
+    }
+
+    #[test]
+    fn invalid_envelopes() {
+        // Unknown type.
+        assert_eq!(
+            SignedTransaction::decode(&[0x05, 0xc0]),
+            Err(DecodeError::Custom("unsupported transaction type"))
+        );
+        // A string instead of a list.
+        assert_eq!(
+            SignedTransaction::decode(&[0x80]),
+            Err(DecodeError::Custom("unsupported transaction type"))
+        );
+        assert_eq!(
+            SignedTransaction::decode(&[]),
+            Err(DecodeError::InputTooShort)
+        );
+
+        let mut raw = hex::decode("f8518001830186a080808560006000551ca0cb8f9f1ef5f34cca80c5af391994befc52b75a4e4b01b36c8a64be256027228ea05e75f7a2e5e56bf34318e0006eefaeb7f9e40b3281e925459340e5c82e43e8a5").unwrap();
+        raw.push(0x00);
+        assert_eq!(
+            SignedTransaction::decode(&raw),
+            Err(DecodeError::TrailingBytes)
+        );
+
+        // v of 26 is below any parity.
+        let mut raw = hex::decode("f8518001830186a080808560006000551ca0cb8f9f1ef5f34cca80c5af391994befc52b75a4e4b01b36c8a64be256027228ea05e75f7a2e5e56bf34318e0006eefaeb7f9e40b3281e925459340e5c82e43e8a5").unwrap();
+        raw[16] = 0x1a;
+        assert_eq!(
+            SignedTransaction::decode(&raw),
+            Err(DecodeError::Custom("invalid signature v"))
+        );
+
+        // v of 29 is neither pre nor post EIP-155, it decodes but has no sender.
+        raw[16] = 0x1d;
+        let signed = SignedTransaction::decode(&raw).unwrap();
+        assert_eq!(signed.encode(), raw);
+        assert_eq!(signed.recover_sender(), Err(SignatureError::InvalidParity));
     }
 }
@@ -0,0 +1,326 @@
+use crate::database::Account;
+use crate::hash::keccak256;
+use crate::rlp::{self, Header};
+use ethereum_types::{BigEndianHash, H160, H256};
+use std::collections::BTreeMap;
+
+/// Root of the trie with no entries, `keccak256(rlp(""))`.
+pub const EMPTY_ROOT: H256 = H256([
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// Merkle Patricia Trie mapping byte strings to byte strings.
+///
+/// Only the contents are kept, the nodes are built when the root is requested. The root depends
+/// on nothing but the contents, so the order of inserts and removes doesn't matter.
+#[derive(Clone, Debug, Default)]
+pub struct Trie {
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl Trie {
+    pub fn new() -> Trie {
+        Trie::default()
+    }
+
+    /// Value stored under `key`.
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.entries.get(key).map(Vec::as_slice)
+    }
+
+    /// Store `value` under `key`, an empty value removes the key.
+    pub fn insert(&mut self, key: &[u8], value: &[u8]) {
+        if value.is_empty() {
+            self.remove(key);
+        } else {
+            self.entries.insert(key.to_vec(), value.to_vec());
+        }
+    }
+
+    /// Remove `key` from the trie.
+    pub fn remove(&mut self, key: &[u8]) {
+        self.entries.remove(key);
+    }
+
+    /// Hash of the root node.
+    pub fn root(&self) -> H256 {
+        if self.entries.is_empty() {
+            return EMPTY_ROOT;
+        }
+
+        let entries: Vec<(Vec<u8>, &[u8])> = self
+            .entries
+            .iter()
+            .map(|(key, value)| (to_nibbles(key), value.as_slice()))
+            .collect();
+
+        keccak256(&encode_node(&entries, 0))
+    }
+}
+
+/// Split bytes into nibbles, high nibble first.
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+/// Hex-prefix encoding of a nibble path, flagging whether it ends in a leaf.
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 0x20 } else { 0x00 };
+    let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+
+    let rest = if nibbles.len() % 2 == 1 {
+        encoded.push(flag | 0x10 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        encoded.push(flag);
+        nibbles
+    };
+
+    encoded.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+    encoded
+}
+
+/// RLP encoding of the node holding `entries`, whose keys all share their first `depth` nibbles.
+///
+/// `entries` must be sorted by key and non-empty.
+fn encode_node(entries: &[(Vec<u8>, &[u8])], depth: usize) -> Vec<u8> {
+    if let [(key, value)] = entries {
+        return encode_node_list(&[
+            rlp::encode(&hex_prefix(&key[depth..], true)[..]),
+            rlp::encode(*value),
+        ]);
+    }
+
+    // Sorted keys share a prefix exactly when the first and the last share it.
+    let first = &entries[0].0[depth..];
+    let last = &entries[entries.len() - 1].0[depth..];
+    let shared = first
+        .iter()
+        .zip(last.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    if shared > 0 {
+        return encode_node_list(&[
+            rlp::encode(&hex_prefix(&first[..shared], false)[..]),
+            node_reference(encode_node(entries, depth + shared)),
+        ]);
+    }
+
+    // A key ending here sorts first and becomes the value of the branch.
+    let (value, children) = match entries.split_first() {
+        Some(((key, value), rest)) if key.len() == depth => (rlp::encode(*value), rest),
+        _ => (rlp::encode(&[][..]), entries),
+    };
+
+    let mut items = Vec::with_capacity(17);
+    let mut remaining = children;
+    for nibble in 0..16 {
+        let count = remaining
+            .iter()
+            .take_while(|(key, _)| key[depth] == nibble)
+            .count();
+        let (child, rest) = remaining.split_at(count);
+
+        items.push(if child.is_empty() {
+            rlp::encode(&[][..])
+        } else {
+            node_reference(encode_node(child, depth + 1))
+        });
+        remaining = rest;
+    }
+    items.push(value);
+
+    encode_node_list(&items)
+}
+
+/// Encoding of a list of already encoded items.
+fn encode_node_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload = items.concat();
+
+    let mut encoded = Vec::with_capacity(payload.len() + 9);
+    Header {
+        list: true,
+        payload_length: payload.len(),
+    }
+    .encode(&mut encoded);
+    encoded.extend(payload);
+    encoded
+}
+
+/// How a parent refers to a child: nodes shorter than a hash are embedded, others are hashed.
+fn node_reference(encoded: Vec<u8>) -> Vec<u8> {
+    if encoded.len() < 32 {
+        encoded
+    } else {
+        rlp::encode(&keccak256(&encoded))
+    }
+}
+
+/// Root of the storage trie of an account.
+///
+/// Slots are keyed by the hash of the slot and hold the RLP of the value with zero slots left
+/// out.
+pub fn storage_root<'a>(storage: impl IntoIterator<Item = (&'a H256, &'a H256)>) -> H256 {
+    let mut trie = Trie::new();
+
+    for (key, value) in storage {
+        if !value.is_zero() {
+            trie.insert(
+                keccak256(key.as_bytes()).as_bytes(),
+                &rlp::encode(&value.into_uint()),
+            );
+        }
+    }
+
+    trie.root()
+}
+
+/// RLP of an account as stored in the state trie.
+fn encode_account(account: &Account, storage_root: H256) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    rlp::encode_list(
+        &[
+            &account.nonce,
+            &account.balance,
+            &storage_root,
+            &account.code_hash,
+        ],
+        &mut encoded,
+    );
+    encoded
+}
+
+/// Root of the state trie given each account with the root of its storage.
+pub fn state_root<'a>(accounts: impl IntoIterator<Item = (&'a H160, &'a Account, H256)>) -> H256 {
+    let mut trie = Trie::new();
+
+    for (address, account, storage_root) in accounts {
+        trie.insert(
+            keccak256(address.as_bytes()).as_bytes(),
+            &encode_account(account, storage_root),
+        );
+    }
+
+    trie.root()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Database, InMemoryDatabase, IterableDatabase};
+    use crate::rlp::Header;
+    use bytes::Bytes;
+    use ethereum_types::U256;
+
+    #[test]
+    fn hex_prefix_flags() {
+        assert_eq!(hex_prefix(&[1, 2, 3, 4, 5], false), vec![0x11, 0x23, 0x45]);
+        assert_eq!(
+            hex_prefix(&[0, 1, 2, 3, 4, 5], false),
+            vec![0x00, 0x01, 0x23, 0x45]
+        );
+        assert_eq!(
+            hex_prefix(&[0, 15, 1, 12, 11, 8], true),
+            vec![0x20, 0x0f, 0x1c, 0xb8]
+        );
+        assert_eq!(
+            hex_prefix(&[15, 1, 12, 11, 8], true),
+            vec![0x3f, 0x1c, 0xb8]
+        );
+        assert_eq!(hex_prefix(&[], true), vec![0x20]);
+    }
+
+    #[test]
+    fn empty_trie() {
+        assert_eq!(Trie::new().root(), keccak256(&rlp::encode(&[][..])));
+        assert_eq!(InMemoryDatabase::new().state_root(), EMPTY_ROOT);
+    }
+
+    /// Root of a trie of two hashed keys with different first nibbles, built node by node: a
+    /// branch holding a leaf for each key, at the index of its first nibble.
+    fn two_leaf_root(leaves: [(H256, Vec<u8>); 2]) -> H256 {
+        assert_ne!(leaves[0].0[0] >> 4, leaves[1].0[0] >> 4);
+
+        let mut children = vec![rlp::encode(&[][..]); 17];
+        for (key, value) in leaves {
+            // The 63 nibbles left below the branch, an odd leaf path so prefixed with 0x3.
+            let mut path = vec![0x30 | (key[0] & 0x0f)];
+            path.extend_from_slice(&key[1..]);
+
+            let mut leaf = Vec::new();
+            rlp::encode_list(&[&Bytes::from(path), &Bytes::from(value)], &mut leaf);
+            assert!(
+                leaf.len() >= 32,
+                "leaves of hashed keys are referenced by hash"
+            );
+
+            children[(key[0] >> 4) as usize] = rlp::encode(&keccak256(&leaf));
+        }
+
+        let payload = children.concat();
+        let mut branch = Vec::new();
+        Header {
+            list: true,
+            payload_length: payload.len(),
+        }
+        .encode(&mut branch);
+        branch.extend(payload);
+        keccak256(&branch)
+    }
+
+    #[test]
+    fn state_root_of_accounts_with_storage() {
+        let mut db = InMemoryDatabase::new();
+        let address = H160::from_low_u64_be(0x0100);
+
+        db.insert_account(
+            address,
+            Account::new(
+                U256::from(1_000_000_000_u64),
+                1,
+                Bytes::from_static(&[0x60, 0x00, 0x54, 0x00]),
+            ),
+        );
+        db.insert_account(
+            H160::from_low_u64_be(0x0200),
+            Account::new(U256::one(), 0, Bytes::new()),
+        );
+        db.set_storage(address, H256::zero(), H256::from_low_u64_be(0x2a));
+        db.set_storage(address, H256::from_low_u64_be(1), H256::repeat_byte(0xff));
+
+        let slot =
+            |key: H256, value: H256| (keccak256(key.as_bytes()), rlp::encode(&value.into_uint()));
+        let storage_root = two_leaf_root([
+            slot(H256::zero(), H256::from_low_u64_be(0x2a)),
+            slot(H256::from_low_u64_be(1), H256::repeat_byte(0xff)),
+        ]);
+        assert_eq!(db.storage_root(address), storage_root);
+
+        let account = |address: H160, storage_root: H256| {
+            let account = db.account(address).unwrap();
+            let mut encoded = Vec::new();
+            rlp::encode_list(
+                &[
+                    &account.nonce,
+                    &account.balance,
+                    &storage_root,
+                    &account.code_hash,
+                ],
+                &mut encoded,
+            );
+            (keccak256(address.as_bytes()), encoded)
+        };
+        assert_eq!(
+            db.state_root(),
+            two_leaf_root([
+                account(address, storage_root),
+                account(H160::from_low_u64_be(0x0200), EMPTY_ROOT),
+            ])
+        );
+    }
+}
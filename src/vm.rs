@@ -1,54 +1,177 @@
-use crate::instruction::Instruction;
-use crate::Transaction;
+use crate::database::Database;
+use crate::env::{Env, Fork};
+use crate::error::{Error, ErrorKind};
+use crate::frame::{Buffers, Control, Exit, Frame, FrameResult};
+use crate::gas::{self, Gas};
+use crate::inspector::{CallOutcome, Inspector, NoopInspector};
+use crate::journal::{Checkpoint, JournaledState};
+use crate::message::{CallKind, Message};
+use crate::precompile::{Entry, PrecompileContext, Precompiles};
+use crate::result::{ExecutionResult, ExecutionStatus, SuccessReason};
 use bytes::Bytes;
-use ethereum_types::{BigEndianHash, H256, U256};
-use log::{debug, error, info, trace};
-use std::collections::HashMap;
-use std::vec::Vec;
+use ethereum_types::{H160, H256, U256};
+use log::info;
+
+/// What entering a message leads to.
+enum Entered {
+    /// A frame to run the code of the message in.
+    Frame(Box<Frame>),
+    /// The message already ran, it called a precompiled contract.
+    Done(FrameResult),
+}
 
-#[derive(Debug)]
-pub enum Error {
-    /// Invalid Instruction.
-    InvalidInstructionError,
-    /// Stack error, likely popping too much or peeking too much.
-    StackError,
+/// Instruction executed by `Vm::step`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Step {
+    pub opcode: u8,
+    pub status: StepStatus,
+}
+
+/// Where the execution stands after a step.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum StepStatus {
+    /// There are more instructions to execute.
+    Running,
+    /// The outermost frame halted, ending the execution.
+    Halted(ExecutionResult),
 }
 
 /// EVM Implementation
+///
+/// Calls and creations made by contracts run in frames kept on a stack rather than through
+/// recursion, so deeply nested calls can't overflow the native stack.
+///
+/// A VM can execute any number of messages and transactions one after the other, reusing the
+/// stacks and memories of its frames. It owns its database, which can be a `&mut` borrow of one
+/// for a VM that only lives for a few calls.
+///
+/// The inspector is called back as the code executes. The default one does nothing, and costs
+/// nothing.
 #[derive(Debug)]
-pub struct Vm<'a> {
-    /// The code we're executing.
-    code: Bytes,
+pub struct Vm<DB: Database, I: Inspector<DB> = NoopInspector> {
+    /// State the VM executes against.
+    pub(crate) state: JournaledState<DB>,
+
+    /// Block and transaction the code executes in.
+    pub(crate) env: Env,
+
+    /// Contracts run as native code when called.
+    pub(crate) precompiles: Precompiles,
 
-    /// CallData bytes.
-    data: Bytes,
+    /// Frames waiting on the calls they made and the one running, empty between executions.
+    frames: Vec<Frame>,
 
-    /// EVM Stack
-    stack: Stack,
+    /// Gas limit of the message being executed.
+    gas_limit: u64,
 
-    /// Memory
-    memory: Vec<u8>,
+    /// Stacks and memories of frames that halted.
+    buffers: Vec<Buffers>,
 
-    /// Storage referenced by this VM.
-    storage: &'a mut HashMap<H256, H256>,
+    /// Hooks called as the code executes.
+    pub(crate) inspector: I,
+}
+
+impl<DB: Database> Vm<DB> {
+    pub fn new(db: DB) -> Vm<DB> {
+        Vm::with_env(db, Env::default())
+    }
 
-    /// The program counter into code in bytes.
-    pc: usize,
+    /// VM executing in the given block and transaction, with the precompiled contracts of its
+    /// fork.
+    pub fn with_env(db: DB, env: Env) -> Vm<DB> {
+        let precompiles = Precompiles::new(env.fork);
+        Vm::with_precompiles(db, env, precompiles)
+    }
+
+    /// VM executing in the given block and transaction, with custom precompiled contracts.
+    pub fn with_precompiles(db: DB, env: Env, precompiles: Precompiles) -> Vm<DB> {
+        Vm {
+            state: JournaledState::new(db),
+            env,
+            precompiles,
+            frames: Vec::new(),
+            gas_limit: 0,
+            buffers: Vec::new(),
+            inspector: NoopInspector,
+        }
+    }
 }
 
-impl<'a> Vm<'a> {
-    pub fn new(storage: &'a mut HashMap<H256, H256>) -> Vm<'a> {
+impl<DB: Database, I: Inspector<DB>> Vm<DB, I> {
+    /// The same VM, calling back `inspector` as the code executes.
+    pub fn with_inspector<J: Inspector<DB>>(self, inspector: J) -> Vm<DB, J> {
         Vm {
-            storage,
-            code: Bytes::new(),
-            data: Bytes::new(),
-            memory: [0; 1_048_576].to_vec(),
-            stack: Stack::new(),
-            pc: 0,
+            state: self.state,
+            env: self.env,
+            precompiles: self.precompiles,
+            frames: self.frames,
+            gas_limit: self.gas_limit,
+            buffers: self.buffers,
+            inspector,
+        }
+    }
+
+    pub fn inspector(&self) -> &I {
+        &self.inspector
+    }
+
+    pub fn inspector_mut(&mut self) -> &mut I {
+        &mut self.inspector
+    }
+
+    /// The database the VM executes against.
+    pub fn db(&self) -> &DB {
+        self.state.db()
+    }
+
+    /// The database the VM executes against, to change between executions.
+    pub fn db_mut(&mut self) -> &mut DB {
+        self.state.db_mut()
+    }
+
+    /// Give up the VM, returning its database.
+    pub fn into_db(self) -> DB {
+        self.state.into_db()
+    }
+
+    /// Block and transaction the code executes in.
+    pub fn env(&self) -> &Env {
+        &self.env
+    }
+
+    /// Block and transaction the code executes in, to change between executions.
+    ///
+    /// The precompiled contracts stay the ones the VM was created with, even if the fork changes.
+    pub fn env_mut(&mut self) -> &mut Env {
+        &mut self.env
+    }
+
+    /// Run the code of `message` as the account at its address.
+    ///
+    /// Unlike a transaction, no value is transferred and nothing is paid for gas, only the code
+    /// runs. A failing execution, including one that reverts, leaves no changes behind. Refunds
+    /// are reported but not taken off the gas used. Accounts aren't deleted afterwards, even if
+    /// they self-destructed, but nothing else carries over to the next execution.
+    pub fn exec(&mut self, message: Message) -> ExecutionResult {
+        self.start(message);
+
+        loop {
+            let step = self.step().expect("execution is in progress");
+            if let StepStatus::Halted(result) = step.status {
+                return result;
+            }
         }
     }
 
-    pub fn exec(mut self, transaction: Transaction) -> Result<Option<Vec<u8>>, Error> {
+    /// Start running the code of `message` like `exec`, one instruction per call to `step`.
+    ///
+    /// # Panics
+    ///
+    /// If the previous execution hasn't halted yet, or the fork is older than London.
+    pub fn start(&mut self, message: Message) {
+        assert!(self.frames.is_empty(), "execution is already in progress");
+        self.assert_supported_fork();
+
         info!(
             "
 
@@ -57,531 +180,887 @@ impl<'a> Vm<'a> {
         ############################
             "
         );
-        self.code = transaction.code;
-        self.data = transaction.data;
-        self.pc = 0;
 
-        if self.code.is_empty() {
-            info!("No code provided, exiting");
-            return Ok(None);
-        }
-
-        loop {
-            if self.pc >= self.code.len() {
-                return Ok(None);
-            }
+        self.inspect_enter(&message);
+        self.gas_limit = message.gas;
+        let checkpoint = self.state.checkpoint();
+        let frame = self.new_frame(message, checkpoint);
+        self.frames.push(frame);
+    }
 
-            let instruction = Instruction::try_from(self.code[self.pc]).map_err(|err| {
-                error!(
-                    "Unexpected instruction 0x{:x} err: {:?}",
-                    self.code[self.pc], err
-                );
-                Error::InvalidInstructionError
-            })?;
+    /// Execute the next instruction of the execution started by `start`, `None` if there is none
+    /// in progress.
+    ///
+    /// An instruction that calls or creates is followed by the first instruction of the callee,
+    /// unless it's a precompiled contract, which runs right away.
+    pub fn step(&mut self) -> Option<Step> {
+        if self.frames.is_empty() {
+            return None;
+        }
 
-            debug!("{:?}", instruction);
-            trace!("Pc: {:?}", self.pc);
-            trace!("Storage: {:?}", self.storage);
-            trace!("Stack: {:?}", self.stack);
+        let (opcode, result) = self.execute_instruction();
+        let status = match result {
+            None => StepStatus::Running,
+            Some(result) => StepStatus::Halted(self.finish(result)),
+        };
 
-            self.pc += 1;
+        Some(Step { opcode, status })
+    }
 
-            match instruction {
-                Instruction::Stop => return Ok(None),
-                Instruction::Add => {
-                    let a = self.stack.pop()?;
-                    let b = self.stack.pop()?;
+    /// Number of frames running, one more for each call the execution is inside of.
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
 
-                    self.stack.push(a.overflowing_add(b).0);
-                }
+    /// Message run by the innermost frame.
+    pub fn message(&self) -> Option<&Message> {
+        self.frames.last().map(|frame| &frame.message)
+    }
 
-                Instruction::Mul => {
-                    let a = self.stack.pop()?;
-                    let b = self.stack.pop()?;
+    /// Program counter of the innermost frame.
+    pub fn pc(&self) -> Option<usize> {
+        self.frames.last().map(|frame| frame.pc)
+    }
 
-                    self.stack.push(a.overflowing_mul(b).0);
-                }
+    /// Stack of the innermost frame, from the bottom up.
+    pub fn stack(&self) -> Option<&[U256]> {
+        self.frames.last().map(|frame| frame.stack.as_slice())
+    }
 
-                Instruction::Sub => {
-                    let a = self.stack.pop()?;
-                    let b = self.stack.pop()?;
+    /// Memory of the innermost frame.
+    pub fn memory(&self) -> Option<&[u8]> {
+        self.frames.last().map(|frame| frame.memory.as_slice())
+    }
 
-                    self.stack.push(a.overflowing_sub(b).0);
-                }
+    /// Gas left in the innermost frame.
+    pub fn gas(&self) -> Option<u64> {
+        self.frames.last().map(|frame| frame.gas.remaining())
+    }
 
-                Instruction::Div => {
-                    let a = self.stack.pop()?;
-                    let b = self.stack.pop()?;
+    /// Value of the storage slot `key` of the account at `address`, as the execution sees it.
+    pub fn storage(&self, address: H160, key: H256) -> H256 {
+        self.state.storage(address, key)
+    }
 
-                    self.stack
-                        .push(if b.is_zero() { U256::zero() } else { a / b });
-                }
+    /// Result of the execution whose outermost frame halted with `result`.
+    fn finish(&mut self, result: FrameResult) -> ExecutionResult {
+        let success = result.is_success();
+
+        let logs = self.state.take_logs();
+        self.state.clear_transaction();
+
+        ExecutionResult {
+            status: result.status,
+            gas_used: self.gas_limit - result.gas.remaining(),
+            gas_refunded: if success {
+                result.gas.refunded().max(0) as u64
+            } else {
+                0
+            },
+            logs,
+            created_address: (success && result.kind.is_create()).then_some(result.address),
+            output: result.output,
+        }
+    }
 
-                Instruction::SDiv => {
-                    let (a_abs, sign_a) = get_and_clear_sign(self.stack.pop()?);
-                    let (b_abs, sign_b) = get_and_clear_sign(self.stack.pop()?);
+    /// Panic unless the fork is one whose rules are implemented, London or later.
+    pub(crate) fn assert_supported_fork(&self) {
+        assert!(
+            self.env.fork >= Fork::London,
+            "execution before London isn't supported, got {:?}",
+            self.env.fork
+        );
+    }
 
-                    // Minimum value representable
-                    let min = (U256::one() << 255) - U256::one();
+    /// Frame for `message`, reusing the buffers of a frame that halted if there is one.
+    fn new_frame(&mut self, message: Message, checkpoint: Checkpoint) -> Frame {
+        let buffers = self.buffers.pop().unwrap_or_default();
+        Frame::new(message, checkpoint, buffers)
+    }
 
-                    // If it's zero, just return zero.
-                    self.stack.push(if b_abs.is_zero() {
-                        U256::zero()
-                    } else if a_abs == min && b_abs == !U256::zero() {
-                        min
-                    } else {
-                        let mut result = a_abs / b_abs;
-                        let should_negate = sign_a ^ sign_b;
+    /// Run `message` in a new frame, transferring its value first.
+    pub(crate) fn call(&mut self, message: Message) -> FrameResult {
+        match self.enter(message) {
+            Entered::Frame(frame) => self.run(*frame),
+            Entered::Done(result) => result,
+        }
+    }
 
-                        if should_negate {
-                            result = twos_complement(result)
-                        }
+    /// Set up the frame for a message, transferring its value and creating the contract for a
+    /// creation.
+    ///
+    /// The caller checked the balance covers the value, the checkpoint undoes the transfer if the
+    /// frame fails. Calls to a precompiled contract run right away instead.
+    fn enter(&mut self, message: Message) -> Entered {
+        self.inspect_enter(&message);
+        let checkpoint = self.state.checkpoint();
+
+        if message.kind.is_create() {
+            self.state.create_contract(message.address);
+        }
 
-                        result
-                    })
-                }
+        if message.kind != CallKind::DelegateCall {
+            self.state
+                .transfer(message.caller, message.address, message.value)
+                .expect("caller covers the value");
+        }
 
-                Instruction::Mod => {
-                    let a = self.stack.pop()?;
-                    let b = self.stack.pop()?;
+        if !message.kind.is_create() {
+            if let Some(precompile) = self.precompiles.get(message.code_address).cloned() {
+                return Entered::Done(self.run_precompile(&precompile, message, checkpoint));
+            }
+        }
 
-                    self.stack
-                        .push(if b.is_zero() { U256::zero() } else { a % b });
-                }
+        Entered::Frame(Box::new(self.new_frame(message, checkpoint)))
+    }
 
-                Instruction::SMod => {
-                    let (a_abs, signed) = get_and_clear_sign(self.stack.pop()?);
-                    let (b_abs, _) = get_and_clear_sign(self.stack.pop()?);
+    /// Run a precompiled contract on the data of `message`.
+    ///
+    /// There's no code to point at, so failures have no pc or opcode.
+    fn run_precompile(
+        &mut self,
+        precompile: &Entry,
+        message: Message,
+        checkpoint: Checkpoint,
+    ) -> FrameResult {
+        let result = match precompile {
+            Entry::Stateless(precompile) => precompile.run(&message.data, message.gas),
+            Entry::Stateful(precompile) => precompile.run(
+                &message.data,
+                message.gas,
+                &mut PrecompileContext {
+                    caller: message.caller,
+                    address: message.address,
+                    value: message.value,
+                    is_static: message.is_static,
+                    state: &mut self.state,
+                },
+            ),
+        };
+
+        // Custom contracts can't be trusted to stay within their gas.
+        let mut gas = Gas::new(message.gas);
+        let result = result.and_then(|output| {
+            gas.charge(output.gas_used)?;
+            Ok(output.output)
+        });
+
+        let (status, output) = match result {
+            Ok(output) => {
+                self.state.commit(checkpoint);
+                (ExecutionStatus::Success(SuccessReason::Return), output)
+            }
+            Err(kind) => {
+                gas.spend_all();
+                self.state.revert_to(checkpoint);
+                (ExecutionStatus::Halt(Error::from(kind)), Bytes::new())
+            }
+        };
+
+        let result = FrameResult {
+            kind: message.kind,
+            address: message.address,
+            status,
+            output,
+            gas,
+        };
+        self.inspect_exit(&message, &result);
+        result
+    }
 
-                    self.stack.push(if b_abs.is_zero() {
-                        U256::zero()
-                    } else {
-                        let mut result = a_abs % b_abs;
+    /// Tell the inspector a call or creation is about to run.
+    fn inspect_enter(&mut self, message: &Message) {
+        if message.kind.is_create() {
+            self.inspector.create(message, &self.state);
+        } else {
+            self.inspector.call(message, &self.state);
+        }
+    }
 
-                        if signed {
-                            result = twos_complement(result);
-                        }
+    /// Tell the inspector a call or creation ended.
+    fn inspect_exit(&mut self, message: &Message, result: &FrameResult) {
+        let outcome = CallOutcome {
+            status: result.status,
+            output: &result.output,
+            gas_used: message.gas - result.gas.remaining(),
+        };
+
+        if message.kind.is_create() {
+            self.inspector.create_end(message, &outcome, &self.state);
+        } else {
+            self.inspector.call_end(message, &outcome, &self.state);
+        }
+    }
 
-                        // TODO(jqphu): run a test for signed mod.
-                        result
-                    })
-                }
+    /// Execute frames until `frame` halts, running the calls and creations it makes along the
+    /// way.
+    fn run(&mut self, frame: Frame) -> FrameResult {
+        assert!(self.frames.is_empty(), "execution is already in progress");
+        self.frames.push(frame);
 
-                Instruction::AddMod => {
-                    let a = self.stack.pop()?;
-                    let b = self.stack.pop()?;
-                    let modulus = self.stack.pop()?;
-
-                    self.stack.push(if modulus.is_zero() {
-                        U256::zero()
-                    } else {
-                        // Need to do the modulus separately to be careful of overflowing U256.
-                        ((a % modulus) + (b % modulus)) % modulus
-                    });
-                }
+        loop {
+            if let (_, Some(result)) = self.execute_instruction() {
+                return result;
+            }
+        }
+    }
 
-                Instruction::MulMod => {
-                    let a = self.stack.pop()?;
-                    let b = self.stack.pop()?;
-                    let modulus = self.stack.pop()?;
-
-                    self.stack.push(if modulus.is_zero() {
-                        U256::zero()
-                    } else {
-                        // Need to do the modulus separately to be careful of overflowing U256.
-                        ((a % modulus) * (b % modulus)) % modulus
-                    });
-                }
+    /// Execute the instruction at the program counter of the innermost frame, entering a new
+    /// frame for a call or creation and wrapping up the frame if it halts.
+    ///
+    /// Returns the opcode, and the result of the outermost frame once it halted.
+    fn execute_instruction(&mut self) -> (u8, Option<FrameResult>) {
+        let depth = self.frames.len();
+        let frame = self.frames.last_mut().expect("a frame is running");
+
+        let pc = frame.pc;
+        let opcode = frame.code.byte(pc);
+
+        self.inspector.step(&frame.view(depth, opcode), &self.state);
+
+        let control = if pc >= frame.code.len() {
+            Ok(Control::Exit(Exit::EndOfCode))
+        } else {
+            frame.execute(opcode, &mut self.state, &self.env, &mut self.inspector)
+        };
+
+        self.inspector.step_end(
+            &frame.view(depth, opcode),
+            control.as_ref().err().copied(),
+            &self.state,
+        );
 
-                Instruction::Exp => {
-                    let a = self.stack.pop()?;
-                    let b = self.stack.pop()?;
+        let exit = match control {
+            Ok(Control::Continue) => return (opcode, None),
+            Ok(Control::Call(message, return_range)) => {
+                frame.return_range = return_range;
+                self.enter_from_frame(*message);
+                return (opcode, None);
+            }
+            Ok(Control::Create(message)) => {
+                self.enter_from_frame(*message);
+                return (opcode, None);
+            }
+            Ok(Control::Exit(exit)) => Ok(exit),
+            Err(kind) => Err(kind),
+        };
 
-                    self.stack.push(a.overflowing_pow(b).0);
-                }
-                Instruction::SignExtend => {
-                    let b = self.stack.pop()?;
-                    let x = self.stack.pop()?;
-                    debug!("b: 0x{:x}, x: 0x{:x}", b, x);
-
-                    if b >= U256::from(32) {
-                        self.stack.push(x)
-                    } else {
-                        let original_length_bits = b
-                            .overflowing_add(U256::from(1_u8))
-                            .0
-                            .overflowing_mul(U256::from(8_u8))
-                            .0;
-
-                        let is_leading_set = x.bit(original_length_bits.as_usize() - 1);
-                        debug!(
-                            "original_length_bits {}, is leading set {}",
-                            original_length_bits, is_leading_set
-                        );
-
-                        let mask = (U256::one() << original_length_bits) - U256::one();
-
-                        debug!("mask: {:?}", mask);
-                        self.stack.push(if is_leading_set {
-                            // Set everything as 1s.
-                            x | !mask
-                        } else {
-                            // Set everything else to 0s
-                            x & mask
-                        });
-                    }
-                }
+        let frame = self.frames.pop().expect("a frame is running");
+        let result = self.exit(frame, exit, pc, opcode);
 
-                Instruction::Lt => {
-                    let a = self.stack.pop()?;
-                    let b = self.stack.pop()?;
+        match self.frames.last_mut() {
+            Some(parent) => {
+                parent.resume(result);
+                (opcode, None)
+            }
+            None => (opcode, Some(result)),
+        }
+    }
 
-                    self.stack.push(U256::from((a < b) as u8));
-                }
+    /// Enter a call or creation made by the innermost frame.
+    fn enter_from_frame(&mut self, message: Message) {
+        match self.enter(message) {
+            Entered::Frame(child) => self.frames.push(*child),
+            Entered::Done(result) => self
+                .frames
+                .last_mut()
+                .expect("a frame is running")
+                .resume(result),
+        }
+    }
 
-                Instruction::Gt => {
-                    let a = self.stack.pop()?;
-                    let b = self.stack.pop()?;
+    /// Wrap up a frame that halted, deploying the code of a creation and committing or reverting
+    /// its changes.
+    fn exit(
+        &mut self,
+        mut frame: Frame,
+        exit: Result<Exit, ErrorKind>,
+        pc: usize,
+        opcode: u8,
+    ) -> FrameResult {
+        let is_create = frame.message.kind.is_create();
+
+        let exit = match exit {
+            Ok(Exit::Return(code)) if is_create => {
+                self.deploy(&mut frame, &code).map(|_| Exit::Return(code))
+            }
+            exit => exit,
+        };
+
+        let (status, output) = match exit {
+            Ok(Exit::Stop) => (ExecutionStatus::Success(SuccessReason::Stop), Bytes::new()),
+            Ok(Exit::Return(output)) => (ExecutionStatus::Success(SuccessReason::Return), output),
+            Ok(Exit::Revert(output)) => (ExecutionStatus::Revert, output),
+            Ok(Exit::SelfDestruct) => (
+                ExecutionStatus::Success(SuccessReason::SelfDestruct),
+                Bytes::new(),
+            ),
+            Ok(Exit::EndOfCode) => (
+                ExecutionStatus::Success(SuccessReason::EndOfCode),
+                Bytes::new(),
+            ),
+            Err(kind) => {
+                frame.gas.spend_all();
+                (
+                    ExecutionStatus::Halt(Error::new(pc, opcode, kind)),
+                    Bytes::new(),
+                )
+            }
+        };
 
-                    self.stack.push(U256::from((a > b) as u8));
-                }
+        if status.is_success() {
+            self.state.commit(frame.checkpoint);
+        } else {
+            self.state.revert_to(frame.checkpoint);
+        }
 
-                Instruction::Slt => {
-                    let (a_abs, a_sign) = get_and_clear_sign(self.stack.pop()?);
-                    let (b_abs, b_sign) = get_and_clear_sign(self.stack.pop()?);
-
-                    self.stack.push(
-                        // Both positive
-                        if !a_sign && !b_sign {
-                            U256::from((a_abs < b_abs) as u8)
-                            // A is negative.
-                        } else if a_sign && !b_sign {
-                            U256::from(true as u8)
-                            // A is positive, b is negative
-                        } else if !a_sign && b_sign {
-                            U256::from(false as u8)
-                            // Both negative, so reverse the check.
-                        } else {
-                            U256::from((b_abs < a_abs) as u8)
-                        },
-                    );
-                }
+        self.buffers.push(Buffers {
+            stack: frame.stack,
+            memory: frame.memory,
+        });
+
+        let result = FrameResult {
+            kind: frame.message.kind,
+            address: frame.message.address,
+            status,
+            output,
+            gas: frame.gas,
+        };
+        self.inspect_exit(&frame.message, &result);
+        result
+    }
 
-                Instruction::Sgt => {
-                    let (a_abs, a_sign) = get_and_clear_sign(self.stack.pop()?);
-                    let (b_abs, b_sign) = get_and_clear_sign(self.stack.pop()?);
-
-                    self.stack.push(
-                        // Both positive
-                        if !a_sign && !b_sign {
-                            U256::from((a_abs > b_abs) as u8)
-                            // A is negative.
-                        } else if a_sign && !b_sign {
-                            U256::from(false as u8)
-                            // A is positive, b is negative
-                        } else if !a_sign && b_sign {
-                            U256::from(true as u8)
-                            // Both negative, so reverse the check.
-                        } else {
-                            U256::from((b_abs > a_abs) as u8)
-                        },
-                    );
-                }
+    /// Deploy the code returned by the init code of a creation.
+    fn deploy(&mut self, frame: &mut Frame, code: &[u8]) -> Result<(), ErrorKind> {
+        if code.len() > gas::MAX_CODE_SIZE {
+            return Err(ErrorKind::ContractSizeLimit);
+        }
 
-                Instruction::Eq => {
-                    let a = self.stack.pop()?;
-                    let b = self.stack.pop()?;
+        // Reserved for the EVM object format (EIP-3541).
+        if code.first() == Some(&0xef) {
+            return Err(ErrorKind::InvalidContractPrefix);
+        }
 
-                    self.stack.push(U256::from((a == b) as u8));
-                }
+        frame.gas.charge(gas::CODE_DEPOSIT * code.len() as u64)?;
+        self.state
+            .set_code(frame.message.address, Bytes::copy_from_slice(code));
 
-                Instruction::IsZero => {
-                    let a = self.stack.pop()?;
-                    self.stack.push(U256::from(a.is_zero() as u8));
-                }
+        Ok(())
+    }
+}
 
-                Instruction::And => {
-                    let a = self.stack.pop()?;
-                    let b = self.stack.pop()?;
-                    self.stack.push(a & b);
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Account, InMemoryDatabase};
+    use crate::instruction::Instruction;
+    use crate::precompile::{PrecompileOutput, PrecompileResult, StatefulPrecompile};
+    use proptest::prelude::*;
+
+    /// Gas for running test code, plenty for anything short of a loop.
+    const GAS: u64 = 10_000_000;
+
+    /// Run `code` against an empty database, returning the result and the final state.
+    fn run(code: &[u8]) -> (ExecutionResult, InMemoryDatabase) {
+        let mut db = InMemoryDatabase::new();
+        let result = Vm::new(&mut db).exec(Message {
+            code: Bytes::copy_from_slice(code),
+            gas: GAS,
+            ..Message::default()
+        });
+
+        (result, db)
+    }
 
-                Instruction::Or => {
-                    let a = self.stack.pop()?;
-                    let b = self.stack.pop()?;
-                    self.stack.push(a | b);
-                }
+    /// Error the execution halted with, `None` if it didn't halt exceptionally.
+    fn halt_reason(result: &ExecutionResult) -> Option<ErrorKind> {
+        match result.status {
+            ExecutionStatus::Halt(error) => Some(error.kind),
+            _ => None,
+        }
+    }
 
-                Instruction::Xor => {
-                    let a = self.stack.pop()?;
-                    let b = self.stack.pop()?;
-                    self.stack.push(a ^ b);
-                }
+    /// Run `code` at address zero, with an account at `callee` holding `callee_code`.
+    fn run_with_callee(
+        code: &[u8],
+        callee: H160,
+        callee_code: &'static [u8],
+    ) -> (ExecutionResult, InMemoryDatabase) {
+        let mut db = InMemoryDatabase::new();
+        db.insert_account(
+            callee,
+            Account::new(U256::zero(), 0, Bytes::from_static(callee_code)),
+        );
 
-                Instruction::Not => {
-                    let a = self.stack.pop()?;
-                    self.stack.push(!a);
-                }
+        let result = Vm::new(&mut db).exec(Message {
+            code: Bytes::copy_from_slice(code),
+            gas: GAS,
+            ..Message::default()
+        });
 
-                Instruction::Byte => {
-                    let i = self.stack.pop()?;
-                    let x = self.stack.pop()?;
-                    self.stack.push(U256::from(x.byte(i.as_usize())));
-                }
+        (result, db)
+    }
 
-                Instruction::Shl => {
-                    let shift = self.stack.pop()?;
-                    let value = self.stack.pop()?;
-                    // TODO(jqphu): check if overflow already handled.
-                    self.stack.push(value << shift.as_usize());
-                }
+    #[test]
+    fn instructions_follow_the_fork() {
+        let run_on = |fork: Fork, code: &'static [u8]| {
+            let env = Env {
+                fork,
+                ..Env::default()
+            };
+            Vm::with_env(InMemoryDatabase::new(), env).exec(Message {
+                code: Bytes::from_static(code),
+                gas: GAS,
+                ..Message::default()
+            })
+        };
+
+        // PUSH0 comes with Shanghai (EIP-3855).
+        assert_eq!(
+            halt_reason(&run_on(Fork::Paris, &[0x5f])),
+            Some(ErrorKind::InvalidOpcode)
+        );
+        assert!(run_on(Fork::Shanghai, &[0x5f]).is_success());
+    }
 
-                Instruction::Shr => {
-                    let shift = self.stack.pop()?;
-                    let value = self.stack.pop()?;
-                    self.stack.push(value >> shift.as_usize());
-                }
+    #[test]
+    #[should_panic(expected = "execution before London isn't supported")]
+    fn forks_before_london_are_rejected() {
+        let env = Env {
+            fork: Fork::Berlin,
+            ..Env::default()
+        };
+        Vm::with_env(InMemoryDatabase::new(), env).exec(Message::default());
+    }
 
-                Instruction::Sar => {
-                    let shift = self.stack.pop()?;
-                    let (value_abs, value_sign) = get_and_clear_sign(self.stack.pop()?);
+    #[test]
+    fn contracts_have_separate_storage() {
+        let mut db = InMemoryDatabase::new();
+        let first = H160::from_low_u64_be(1);
+        let second = H160::from_low_u64_be(2);
+
+        for (address, value) in [(first, 0x01), (second, 0x02)] {
+            // PUSH1 value PUSH1 0x00 SSTORE
+            let result = Vm::new(&mut db).exec(Message {
+                address,
+                code: Bytes::copy_from_slice(&[0x60, value, 0x60, 0x00, 0x55]),
+                gas: GAS,
+                ..Message::default()
+            });
+            assert!(result.is_success());
+        }
 
-                    let shifted_value = value_abs << shift;
-                    let top_bit = U256::one() << 255;
+        assert_eq!(db.storage(first, H256::zero()), H256::from_low_u64_be(1));
+        assert_eq!(db.storage(second, H256::zero()), H256::from_low_u64_be(2));
+    }
 
-                    self.stack.push(if value_sign {
-                        shifted_value | top_bit
-                    } else {
-                        shifted_value
-                    });
-                }
+    #[test]
+    fn exec_reports_output_gas_and_logs() {
+        // PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x00 PUSH1 0x00 LOG0 PUSH1 0x20 PUSH1 0x00 RETURN
+        let (result, _) = run(&[
+            0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x00, 0x60, 0x00, 0xa0, 0x60, 0x20, 0x60, 0x00,
+            0xf3,
+        ]);
+
+        assert_eq!(
+            result.status,
+            ExecutionStatus::Success(SuccessReason::Return)
+        );
+        assert_eq!(
+            result.output,
+            Bytes::from(H256::from_low_u64_be(0x2a).0.to_vec())
+        );
+        assert_eq!(result.gas_used, 399);
+        assert_eq!(result.logs.len(), 1);
+        assert_eq!(result.created_address, None);
 
-                Instruction::CallDataLoad => {
-                    let i = self.stack.pop()?.as_usize();
+        // STOP
+        let (result, _) = run(&[0x00]);
+        assert_eq!(result.status, ExecutionStatus::Success(SuccessReason::Stop));
+    }
 
-                    self.stack.push(U256::from(&self.data[i..i + 32]));
-                }
+    #[test]
+    fn failure_reverts_storage_writes() {
+        // PUSH1 0x01 PUSH1 0x00 SSTORE INVALID
+        let (result, db) = run(&[0x60, 0x01, 0x60, 0x00, 0x55, 0xfe]);
 
-                Instruction::CallDataSize => {
-                    self.stack.push(U256::from(self.data.len()));
-                }
+        assert_eq!(halt_reason(&result), Some(ErrorKind::InvalidOpcode));
+        assert_eq!(db.account_storage(H160::zero()).count(), 0);
+    }
 
-                Instruction::Pop => {
-                    self.stack.pop()?;
-                }
+    #[test]
+    fn revert_reverts_storage_writes() {
+        // PUSH1 0x01 PUSH1 0x00 SSTORE PUSH1 0x00 PUSH1 0x00 REVERT
+        let (result, db) = run(&[0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0xfd]);
 
-                Instruction::MLoad => {
-                    let offset = self.stack.pop()?.as_usize();
+        assert_eq!(result.status, ExecutionStatus::Revert);
+        assert_eq!(result.gas_used, 22112);
+        assert_eq!(db.account_storage(H160::zero()).count(), 0);
+    }
 
-                    self.stack
-                        .push(U256::from(&self.memory[offset..offset + 32]));
-                }
+    #[test]
+    fn truncated_push_stops_execution() {
+        // PUSH1 0x01 PUSH32 0xff
+        let (result, db) = run(&[0x60, 0x01, 0x7f, 0xff]);
 
-                Instruction::MStore => {
-                    let offset = self.stack.pop()?.as_usize();
-                    let value = self.stack.pop()?;
+        assert_eq!(
+            result.status,
+            ExecutionStatus::Success(SuccessReason::EndOfCode)
+        );
+        assert_eq!(db.account_storage(H160::zero()).count(), 0);
+    }
 
-                    value.to_big_endian(&mut self.memory[offset..offset + 32]);
-                }
+    #[test]
+    fn jump_into_push_data_fails() {
+        // PUSH1 0x04 JUMP PUSH1 0x5b
+        let (result, _) = run(&[0x60, 0x04, 0x56, 0x60, 0x5b]);
 
-                Instruction::MStore8 => {
-                    let offset = self.stack.pop()?.as_usize();
-                    let value = self.stack.pop()?;
+        assert_eq!(halt_reason(&result), Some(ErrorKind::InvalidJump));
+    }
 
-                    self.memory[offset] = value.low_u32() as u8;
-                }
+    #[test]
+    fn jump_to_jump_destination() {
+        // PUSH1 0x04 JUMP INVALID JUMPDEST PUSH1 0x01 PUSH1 0x00 SSTORE
+        let (result, db) = run(&[0x60, 0x04, 0x56, 0xfe, 0x5b, 0x60, 0x01, 0x60, 0x00, 0x55]);
 
-                Instruction::SLoad => {
-                    let key = self.stack.pop()?;
+        assert_eq!(
+            result.status,
+            ExecutionStatus::Success(SuccessReason::EndOfCode)
+        );
+        assert_eq!(
+            db.storage(H160::zero(), H256::zero()),
+            H256::from_low_u64_be(1)
+        );
+    }
 
-                    self.stack
-                        .push(self.storage[&H256::from_uint(&key)].into_uint());
-                }
+    #[test]
+    fn out_of_bounds_memory_fails() {
+        // PUSH1 0x00 PUSH32 0xff..ff MSTORE
+        let mut code = vec![0x60, 0x00, 0x7f];
+        code.extend_from_slice(&[0xff; 32]);
+        code.push(0x52);
 
-                Instruction::SStore => {
-                    let key = self.stack.peek(0)?;
-                    let value = self.stack.peek(1)?;
+        let (result, _) = run(&code);
 
-                    self.storage
-                        .insert(H256::from_uint(&key), H256::from_uint(&value));
-                }
+        assert_eq!(halt_reason(&result), Some(ErrorKind::InvalidMemoryAccess));
+    }
 
-                Instruction::Jump => {
-                    let destination = self.stack.pop()?;
+    #[test]
+    fn large_memory_expansion_runs_out_of_gas() {
+        // PUSH1 0x00 PUSH5 0xffffffffff MSTORE
+        let (result, _) = run(&[0x60, 0x00, 0x64, 0xff, 0xff, 0xff, 0xff, 0xff, 0x52]);
 
-                    self.pc = destination.as_usize();
-                }
+        assert_eq!(halt_reason(&result), Some(ErrorKind::OutOfGas));
+    }
 
-                Instruction::JumpI => {
-                    let destination = self.stack.pop()?;
-                    let condition = self.stack.pop()?;
+    #[test]
+    fn stack_overflow_fails() {
+        // JUMPDEST PUSH1 0x00 PUSH1 0x00 JUMP
+        let (result, _) = run(&[0x5b, 0x60, 0x00, 0x60, 0x00, 0x56]);
 
-                    if !condition.is_zero() {
-                        self.pc = destination.as_usize();
-                    }
-                }
+        assert_eq!(halt_reason(&result), Some(ErrorKind::StackOverflow));
+    }
 
-                Instruction::PC => {
-                    // Remove the additional bump of the PC above.
-                    self.stack.push(U256::from(self.pc - 1));
-                }
+    #[test]
+    fn vm_is_reused_across_executions() {
+        // PUSH1 0x00 SLOAD PUSH1 0x01 ADD PUSH1 0x00 SSTORE
+        let increment = Message {
+            code: Bytes::from_static(&[0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55]),
+            gas: GAS,
+            ..Message::default()
+        };
+        let mut vm = Vm::new(InMemoryDatabase::new());
+
+        assert!(vm.exec(increment.clone()).is_success());
+        let result = vm.exec(increment);
+
+        // The slot is cold again and starts out non-zero, nothing carried over but storage.
+        assert!(result.is_success());
+        assert_eq!(result.gas_used, 3 + 2100 + 3 + 3 + 3 + 2900);
+        assert_eq!(
+            vm.into_db().storage(H160::zero(), H256::zero()),
+            H256::from_low_u64_be(2)
+        );
+    }
 
-                // No-op
-                Instruction::JumpDest => {}
-                // TODO(jqphu): macroify all of this.
-                Instruction::Push1
-                | Instruction::Push2
-                | Instruction::Push3
-                | Instruction::Push4
-                | Instruction::Push5
-                | Instruction::Push6
-                | Instruction::Push7
-                | Instruction::Push8
-                | Instruction::Push9
-                | Instruction::Push10
-                | Instruction::Push11
-                | Instruction::Push12
-                | Instruction::Push13
-                | Instruction::Push14
-                | Instruction::Push15
-                | Instruction::Push16
-                | Instruction::Push17
-                | Instruction::Push18
-                | Instruction::Push19
-                | Instruction::Push20
-                | Instruction::Push21
-                | Instruction::Push22
-                | Instruction::Push23
-                | Instruction::Push24
-                | Instruction::Push25
-                | Instruction::Push26
-                | Instruction::Push27
-                | Instruction::Push28
-                | Instruction::Push29
-                | Instruction::Push30
-                | Instruction::Push31
-                | Instruction::Push32 => {
-                    let bytes = instruction.push_bytes().unwrap();
-
-                    let value = self.read_bytes(bytes);
-                    self.stack.push(value);
-
-                    self.pc += bytes;
-                }
+    #[test]
+    fn step_exposes_frame_state() {
+        // PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x2a PUSH1 0x01 SSTORE
+        let code = [0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x2a, 0x60, 0x01, 0x55];
+        let mut vm = Vm::new(InMemoryDatabase::new());
+        assert_eq!(vm.step(), None);
+
+        vm.start(Message {
+            code: Bytes::copy_from_slice(&code),
+            gas: GAS,
+            ..Message::default()
+        });
+        assert_eq!(vm.pc(), Some(0));
+        assert_eq!(vm.depth(), 1);
+
+        let step = vm.step().unwrap();
+        assert_eq!(step.opcode, 0x60);
+        assert_eq!(step.status, StepStatus::Running);
+        assert_eq!(vm.pc(), Some(2));
+        assert_eq!(vm.stack(), Some(&[U256::from(0x2a)][..]));
+        assert_eq!(vm.gas(), Some(GAS - 3));
+
+        vm.step();
+        assert_eq!(vm.step().unwrap().opcode, 0x52);
+        assert_eq!(vm.stack(), Some(&[][..]));
+        assert_eq!(vm.memory().unwrap()[31], 0x2a);
+
+        vm.step();
+        vm.step();
+        assert_eq!(vm.step().unwrap().opcode, 0x55);
+        assert_eq!(
+            vm.storage(H160::zero(), H256::from_low_u64_be(1)),
+            H256::from_low_u64_be(0x2a)
+        );
 
-                Instruction::Swap1
-                | Instruction::Swap2
-                | Instruction::Swap3
-                | Instruction::Swap4
-                | Instruction::Swap5
-                | Instruction::Swap6
-                | Instruction::Swap7
-                | Instruction::Swap8
-                | Instruction::Swap9
-                | Instruction::Swap10
-                | Instruction::Swap11
-                | Instruction::Swap12
-                | Instruction::Swap13
-                | Instruction::Swap14
-                | Instruction::Swap15
-                | Instruction::Swap16 => {
-                    let position = instruction.swap_position().unwrap();
-
-                    self.stack.inner.swap(0, position);
-                }
+        // Running past the end of the code halts.
+        let step = vm.step().unwrap();
+        assert_eq!(step.opcode, 0x00);
+        let StepStatus::Halted(result) = step.status else {
+            panic!("execution didn't halt");
+        };
+        assert_eq!(
+            result.status,
+            ExecutionStatus::Success(SuccessReason::EndOfCode)
+        );
+        assert_eq!(vm.pc(), None);
+        assert_eq!(vm.step(), None);
+    }
 
-                Instruction::Dup1
-                | Instruction::Dup2
-                | Instruction::Dup3
-                | Instruction::Dup4
-                | Instruction::Dup5
-                | Instruction::Dup6
-                | Instruction::Dup7
-                | Instruction::Dup8
-                | Instruction::Dup9
-                | Instruction::Dup10
-                | Instruction::Dup11
-                | Instruction::Dup12
-                | Instruction::Dup13
-                | Instruction::Dup14
-                | Instruction::Dup15
-                | Instruction::Dup16 => {
-                    let position = instruction.dup_position().unwrap();
-
-                    self.stack.push(self.stack.peek(position).unwrap());
-                }
+    #[test]
+    fn step_enters_callee() {
+        // PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH2 0xca11 GAS CALL
+        let code = [
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x61, 0xca, 0x11, 0x5a,
+            0xf1,
+        ];
+        let callee = H160::from_low_u64_be(0xca11);
+        let mut db = InMemoryDatabase::new();
+        // PUSH1 0x01 STOP
+        db.insert_account(
+            callee,
+            Account::new(U256::zero(), 0, Bytes::from_static(&[0x60, 0x01, 0x00])),
+        );
+        let mut vm = Vm::new(&mut db);
+        vm.start(Message {
+            code: Bytes::copy_from_slice(&code),
+            gas: GAS,
+            ..Message::default()
+        });
+
+        let opcodes: Vec<_> = std::iter::from_fn(|| {
+            let step = vm.step()?;
+            Some((step.opcode, vm.depth()))
+        })
+        .collect();
+
+        assert_eq!(
+            opcodes,
+            vec![
+                (0x60, 1),
+                (0x60, 1),
+                (0x60, 1),
+                (0x60, 1),
+                (0x60, 1),
+                (0x61, 1),
+                (0x5a, 1),
+                (0xf1, 2),
+                (0x60, 2),
+                (0x00, 1),
+                (0x00, 0),
+            ]
+        );
+    }
 
-                Instruction::Return => {
-                    let offset = self.stack.pop()?.as_usize();
-                    let length = self.stack.pop()?.as_usize();
+    #[test]
+    fn infinite_loop_runs_out_of_gas() {
+        // JUMPDEST PUSH1 0x00 JUMP
+        let (result, _) = run(&[0x5b, 0x60, 0x00, 0x56]);
 
-                    return Ok(Some(self.memory[offset..offset + length].to_vec()));
-                }
-            }
-        }
+        assert_eq!(halt_reason(&result), Some(ErrorKind::OutOfGas));
     }
 
-    /// Read a given number of bytes
-    fn read_bytes(&self, bytes: usize) -> U256 {
-        assert!(self.pc + bytes <= self.code.len());
+    #[test]
+    fn sstore_needs_more_than_the_stipend() {
+        let mut db = InMemoryDatabase::new();
 
-        U256::from(&self.code[self.pc..self.pc + bytes])
-    }
-}
+        // PUSH1 0x01 PUSH1 0x00 SSTORE
+        let result = Vm::new(&mut db).exec(Message {
+            code: Bytes::from_static(&[0x60, 0x01, 0x60, 0x00, 0x55]),
+            gas: 6 + gas::SSTORE_SENTRY,
+            ..Message::default()
+        });
 
-fn get_and_clear_sign(value: U256) -> (U256, bool) {
-    let signed = value.bit(255);
+        assert_eq!(halt_reason(&result), Some(ErrorKind::OutOfGas));
+    }
 
-    if signed {
-        // Negate and add 1 to make it unsigned.
-        (twos_complement(value), true)
-    } else {
-        (value, false)
+    #[test]
+    fn call_returns_output_of_callee() {
+        // PUSH1 0x20 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH2 0xca11 GAS CALL
+        // PUSH1 0x00 MLOAD ADD PUSH1 0x00 SSTORE
+        let code = [
+            0x60, 0x20, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x61, 0xca, 0x11, 0x5a,
+            0xf1, 0x60, 0x00, 0x51, 0x01, 0x60, 0x00, 0x55,
+        ];
+        // PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+        let callee_code = &[0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+
+        let (result, db) = run_with_callee(&code, H160::from_low_u64_be(0xca11), callee_code);
+        assert!(result.is_success());
+
+        // The output plus one for the successful call.
+        assert_eq!(
+            db.storage(H160::zero(), H256::zero()),
+            H256::from_low_u64_be(0x2b)
+        );
     }
-}
 
-/// Get the two's complement of a number.
-fn twos_complement(value: U256) -> U256 {
-    (!U256::zero() ^ value).overflowing_add(U256::one()).0
-}
+    #[test]
+    fn call_to_precompile_runs_native_code() {
+        // PUSH1 0x2a PUSH1 0x00 MSTORE
+        // PUSH1 0x20 PUSH1 0x20 PUSH1 0x20 PUSH1 0x00 PUSH1 0x04 GAS STATICCALL
+        // PUSH1 0x20 MLOAD ADD PUSH1 0x00 SSTORE
+        let code = [
+            0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x20, 0x60, 0x20, 0x60, 0x00, 0x60,
+            0x04, 0x5a, 0xfa, 0x60, 0x20, 0x51, 0x01, 0x60, 0x00, 0x55,
+        ];
+
+        let (result, db) = run(&code);
+        assert!(result.is_success());
+
+        // The identity precompile copied its input, plus one for the successful call.
+        assert_eq!(
+            db.storage(H160::zero(), H256::zero()),
+            H256::from_low_u64_be(0x2b)
+        );
+    }
 
-/// EVM Stack used for convenience.
-#[derive(Debug)]
-struct Stack {
-    /// Stack holding variables, function call arguments and return addressed.
-    ///
-    /// A stack of uint256
-    inner: Vec<U256>,
-}
+    /// Stateful precompile counting the calls made to it in its storage.
+    struct Counter;
+
+    impl StatefulPrecompile for Counter {
+        fn run(
+            &self,
+            _input: &[u8],
+            _gas_limit: u64,
+            context: &mut PrecompileContext<'_>,
+        ) -> PrecompileResult {
+            let count = context.state.storage(context.address, H256::zero());
+            context.state.set_storage(
+                context.address,
+                H256::zero(),
+                H256::from_low_u64_be(count.to_low_u64_be() + 1),
+            );
+
+            Ok(PrecompileOutput {
+                output: Bytes::new(),
+                gas_used: 5000,
+            })
+        }
+    }
 
-impl Default for Stack {
-    fn default() -> Self {
-        Stack::new()
+    #[test]
+    fn stateful_precompile_writes_storage() {
+        let counter = H160::from_low_u64_be(0x0100);
+        let mut precompiles = Precompiles::new(Fork::default());
+        precompiles.insert_stateful(counter, Counter);
+
+        // (PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH2 0x0100 GAS CALL POP) * 2
+        let call = [
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x61, 0x01, 0x00, 0x5a,
+            0xf1, 0x50,
+        ];
+        let mut db = InMemoryDatabase::new();
+        let result = Vm::with_precompiles(&mut db, Env::default(), precompiles).exec(Message {
+            code: Bytes::from([call, call].concat()),
+            gas: GAS,
+            ..Message::default()
+        });
+        assert!(result.is_success());
+
+        assert_eq!(db.storage(counter, H256::zero()), H256::from_low_u64_be(2));
     }
-}
 
-impl Stack {
-    pub fn new() -> Self {
-        Stack { inner: Vec::new() }
+    #[test]
+    fn reverted_call_only_undoes_callee_changes() {
+        // PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH2 0xca11 GAS CALL
+        // ISZERO PUSH1 0x01 SSTORE
+        let code = [
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x61, 0xca, 0x11, 0x5a,
+            0xf1, 0x15, 0x60, 0x01, 0x55,
+        ];
+        // PUSH1 0x01 PUSH1 0x00 SSTORE PUSH1 0x00 PUSH1 0x00 REVERT
+        let callee_code = &[0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0xfd];
+
+        let callee = H160::from_low_u64_be(0xca11);
+        let (result, db) = run_with_callee(&code, callee, callee_code);
+        assert!(result.is_success());
+
+        assert_eq!(
+            db.storage(H160::zero(), H256::from_low_u64_be(1)),
+            H256::from_low_u64_be(1)
+        );
+        assert_eq!(db.account_storage(callee).count(), 0);
     }
 
-    pub fn push(&mut self, value: U256) {
-        self.inner.push(value);
+    #[test]
+    fn create_deploys_returned_code() {
+        let mut db = InMemoryDatabase::new();
+
+        // Init code returning the single byte 0xfe:
+        // PUSH1 0xfe PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+        let init = [0x60, 0xfe, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3];
+
+        // PUSH10 init PUSH1 0x00 MSTORE PUSH1 0x0a PUSH1 0x16 PUSH1 0x00 CREATE PUSH1 0x00 SSTORE
+        let mut code = vec![0x69];
+        code.extend_from_slice(&init);
+        code.extend_from_slice(&[
+            0x60, 0x00, 0x52, 0x60, 0x0a, 0x60, 0x16, 0x60, 0x00, 0xf0, 0x60, 0x00, 0x55,
+        ]);
+
+        let creator = H160::from_low_u64_be(0xc0de);
+        let result = Vm::new(&mut db).exec(Message {
+            address: creator,
+            code: Bytes::from(code),
+            gas: GAS,
+            ..Message::default()
+        });
+        assert!(result.is_success());
+
+        let address = crate::frame::create_address(creator, 0);
+        let created = db.account(address).unwrap();
+        assert_eq!(&created.code[..], &[0xfe]);
+        assert_eq!(created.nonce, 1);
+        assert_eq!(db.account(creator).unwrap().nonce, 1);
+        assert_eq!(db.storage(creator, H256::zero()), H256::from(address));
     }
 
-    pub fn pop(&mut self) -> Result<U256, Error> {
-        let result = self.inner.pop();
-        match result {
-            None => Err(Error::StackError),
-            Some(result) => Ok(result),
-        }
+    /// Arbitrary code with the jumps replaced, so most programs run straight through.
+    fn code_without_jumps() -> impl Strategy<Value = Vec<u8>> {
+        proptest::collection::vec(any::<u8>(), 0..512).prop_map(|mut code| {
+            for byte in code.iter_mut() {
+                if *byte == Instruction::Jump as u8 || *byte == Instruction::JumpI as u8 {
+                    *byte = Instruction::JumpDest as u8;
+                }
+            }
+            code
+        })
     }
 
-    pub fn peek(&self, offset_from_top: usize) -> Result<U256, Error> {
-        if offset_from_top >= self.inner.len() {
-            return Err(Error::StackError);
+    proptest! {
+        #[test]
+        fn arbitrary_code_never_panics(code in code_without_jumps()) {
+            let _ = run(&code);
         }
 
-        Ok(self.inner[self.inner.len() - offset_from_top - 1])
+        #[test]
+        fn arbitrary_code_with_jumps_halts(code in proptest::collection::vec(any::<u8>(), 0..512)) {
+            let _ = run(&code);
+        }
     }
 }
@@ -0,0 +1,7 @@
+use crate::common::run_vm_tests;
+
+/// All the arithmetic tests.
+#[test]
+fn arithmetic() {
+    run_vm_tests("tests/arithmetic/*.json");
+}
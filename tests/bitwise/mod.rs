@@ -0,0 +1,7 @@
+use crate::common::run_vm_tests;
+
+/// Bitwise logic tests.
+#[test]
+fn bitwise() {
+    run_vm_tests("tests/bitwise/*.json");
+}
@@ -0,0 +1,175 @@
+use bytes::Bytes;
+use ethereum_types::{H160, H256, U256};
+use evm_rust::{Account, BlockEnv, Database, Env, InMemoryDatabase, Message, TxEnv, Vm};
+use glob::glob;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::str::FromStr;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockState {
+    current_coinbase: String,
+    current_gas_limit: String,
+    current_number: String,
+    current_timestamp: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Exec {
+    address: String,
+    caller: String,
+    code: String,
+    data: String,
+    gas: String,
+    gas_price: String,
+    origin: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct AccountState {
+    balance: String,
+    code: String,
+    nonce: String,
+    storage: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct TestCase {
+    env: BlockState,
+    exec: Exec,
+    pre: HashMap<String, AccountState>,
+    /// Missing when the test expects execution to fail.
+    post: Option<HashMap<String, AccountState>>,
+}
+
+fn parse_bytes(value: &str) -> Bytes {
+    Bytes::from(hex::decode(value.split_at(2).1).unwrap())
+}
+
+fn parse_address(value: &str) -> H160 {
+    H160::from_str(value.split_at(2).1).unwrap()
+}
+
+fn parse_h256(value: &str) -> H256 {
+    H256::from_str(&format!("{:0>64}", value.split_at(2).1)).unwrap()
+}
+
+fn parse_u256(value: &str) -> U256 {
+    U256::from_str(value.split_at(2).1).unwrap()
+}
+
+/// Check the storage of the account at `address` is exactly `expected`.
+fn assert_storage(db: &InMemoryDatabase, address: H160, expected: &HashMap<String, String>) {
+    for (key, value) in expected {
+        assert_eq!(db.storage(address, parse_h256(key)), parse_h256(value));
+    }
+
+    // Zero slots are cleared, so the remaining slots must all be expected.
+    assert_eq!(db.account_storage(address).count(), expected.len());
+}
+
+/// Run every VM test fixture matching `pattern`.
+///
+/// Fixtures follow the ethereum/tests VMTests format. A fixture without a `post` section expects
+/// execution to fail, otherwise the storage of every account in `post` must match exactly.
+pub fn run_vm_tests(pattern: &str) {
+    let _ = env_logger::try_init();
+
+    for entry in glob(pattern).expect("Failed to read glob pattern") {
+        let path = entry.unwrap();
+        println!("{}", path.display());
+
+        let filename_without_extension = path.file_stem().unwrap();
+
+        let TestCase {
+            env,
+            exec,
+            pre,
+            post,
+        } = {
+            let data = fs::read_to_string(&path).expect("Unable to read file");
+
+            let res: Value = serde_json::from_str(&data).expect("Unable to parse");
+
+            let test_case = &res[&filename_without_extension.to_str().unwrap()];
+
+            serde_json::from_value(test_case.clone()).unwrap()
+        };
+
+        let mut db = InMemoryDatabase::new();
+        for (address, state) in &pre {
+            let address = parse_address(address);
+
+            db.insert_account(
+                address,
+                Account::new(
+                    parse_u256(&state.balance),
+                    parse_u256(&state.nonce).as_u64(),
+                    parse_bytes(&state.code),
+                ),
+            );
+
+            for (key, value) in &state.storage {
+                db.set_storage(address, parse_h256(key), parse_h256(value));
+            }
+        }
+
+        let env = Env {
+            block: BlockEnv {
+                number: parse_u256(&env.current_number).as_u64(),
+                coinbase: parse_address(&env.current_coinbase),
+                timestamp: parse_u256(&env.current_timestamp).as_u64(),
+                gas_limit: parse_u256(&env.current_gas_limit).as_u64(),
+                ..BlockEnv::default()
+            },
+            tx: TxEnv {
+                origin: parse_address(&exec.origin),
+                gas_price: parse_u256(&exec.gas_price),
+                ..TxEnv::default()
+            },
+            ..Env::default()
+        };
+
+        let mut vm = Vm::with_env(&mut db, env);
+        let result = vm.exec(Message {
+            caller: parse_address(&exec.caller),
+            address: parse_address(&exec.address),
+            code_address: parse_address(&exec.address),
+            value: parse_u256(&exec.value),
+            code: parse_bytes(&exec.code),
+            data: parse_bytes(&exec.data),
+            gas: parse_u256(&exec.gas).as_u64(),
+            ..Message::default()
+        });
+
+        let post = match post {
+            Some(post) => post,
+            None => {
+                assert!(!result.is_success(), "{} should fail", path.display());
+
+                // A failed execution leaves the state as it was.
+                for (address, state) in &pre {
+                    assert_storage(&db, parse_address(address), &state.storage);
+                }
+
+                continue;
+            }
+        };
+
+        assert!(
+            result.is_success(),
+            "{} failed: {:?}",
+            path.display(),
+            result.status
+        );
+
+        for (address, expected) in &post {
+            assert_storage(&db, parse_address(address), &expected.storage);
+        }
+    }
+}
@@ -1 +1,8 @@
+mod common;
+
 mod arithmetic;
+mod bitwise;
+mod rlp;
+mod stack;
+mod transaction;
+mod trie;
@@ -0,0 +1,95 @@
+use evm_rust::rlp::{self, Decodable, DecodeError, Encodable, Header};
+use num_bigint::BigUint;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+
+#[derive(Deserialize)]
+struct TestCase {
+    #[serde(rename = "in")]
+    input: Value,
+    out: String,
+}
+
+/// Any RLP item, decoded without knowing its type.
+#[derive(Eq, PartialEq, Debug)]
+enum Item {
+    String(Vec<u8>),
+    List(Vec<Item>),
+}
+
+impl Encodable for Item {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Item::String(bytes) => bytes[..].encode(out),
+            Item::List(items) => items.encode(out),
+        }
+    }
+}
+
+impl Decodable for Item {
+    fn decode(buf: &mut &[u8]) -> Result<Item, DecodeError> {
+        if Header::decode(&mut &buf[..])?.list {
+            Vec::decode(buf).map(Item::List)
+        } else {
+            rlp::decode_bytes(buf).map(|bytes| Item::String(bytes.to_vec()))
+        }
+    }
+}
+
+/// Strings are raw bytes, except `#` which prefixes a big integer in decimal.
+fn parse(value: &Value) -> Item {
+    match value {
+        Value::String(string) => match string.strip_prefix('#') {
+            Some(digits) => Item::String(integer_bytes(digits.parse().unwrap())),
+            None => Item::String(string.chars().map(|c| c as u8).collect()),
+        },
+        Value::Number(number) => Item::String(integer_bytes(number.as_u64().unwrap().into())),
+        Value::Array(items) => Item::List(items.iter().map(parse).collect()),
+        _ => panic!("Unexpected input {}", value),
+    }
+}
+
+/// Big endian bytes without leading zeros, empty for zero.
+fn integer_bytes(value: BigUint) -> Vec<u8> {
+    if value == BigUint::default() {
+        Vec::new()
+    } else {
+        value.to_bytes_be()
+    }
+}
+
+fn load(path: &str) -> BTreeMap<String, TestCase> {
+    let data = fs::read_to_string(path).expect("Unable to read file");
+    serde_json::from_str(&data).expect("Unable to parse")
+}
+
+#[test]
+fn rlp_valid() {
+    for (name, case) in load("tests/rlp/rlptest.json") {
+        println!("{}", name);
+
+        let expected = hex::decode(&case.out[2..]).unwrap();
+        let item = parse(&case.input);
+
+        assert_eq!(rlp::encode(&item), expected, "{}", name);
+        assert_eq!(rlp::decode::<Item>(&expected), Ok(item), "{}", name);
+
+        if let Some(value) = case.input.as_u64() {
+            assert_eq!(rlp::encode(&value), expected, "{}", name);
+            assert_eq!(rlp::decode::<u64>(&expected), Ok(value), "{}", name);
+        }
+    }
+}
+
+#[test]
+fn rlp_invalid() {
+    for (name, case) in load("tests/rlp/invalidRLPTest.json") {
+        println!("{}", name);
+
+        let encoded = hex::decode(&case.out[2..]).unwrap();
+
+        assert!(rlp::decode::<Item>(&encoded).is_err(), "{}", name);
+    }
+}
@@ -0,0 +1,7 @@
+use crate::common::run_vm_tests;
+
+/// DUPn and SWAPn tests.
+#[test]
+fn stack() {
+    run_vm_tests("tests/stack/*.json");
+}
@@ -0,0 +1,147 @@
+use bytes::Bytes;
+use ethereum_types::{H160, H256, U256};
+use evm_rust::{
+    Account, Database, Env, InMemoryDatabase, InvalidTransaction, SignedTransaction, Vm,
+};
+use glob::glob;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::str::FromStr;
+
+/// Fork whose results are checked, the one this crate implements.
+const FORK: &str = "Prague";
+
+#[derive(Deserialize)]
+struct Expected {
+    hash: Option<String>,
+    sender: Option<String>,
+    /// Set when the transaction must be rejected.
+    exception: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TestCase {
+    result: HashMap<String, Expected>,
+    txbytes: String,
+}
+
+/// Why a transaction was rejected.
+#[derive(Eq, PartialEq, Clone, Debug)]
+enum Rejection {
+    /// The bytes aren't a well-formed transaction.
+    Decode,
+    /// The sender can't be recovered from the signature.
+    Signature,
+    /// The transaction can't be included whatever the state of the sender.
+    Invalid(InvalidTransaction),
+}
+
+/// Rejection for an exception of the ethereum/tests `TransactionException` enum.
+fn rejection(exception: &str) -> Rejection {
+    let name = exception
+        .strip_prefix("TransactionException.")
+        .unwrap_or(exception);
+
+    match name {
+        name if name.starts_with("RLP_") => Rejection::Decode,
+        "TYPE_NOT_SUPPORTED" | "TYPE_3_TX_CONTRACT_CREATION" | "TYPE_4_TX_CONTRACT_CREATION" => {
+            Rejection::Decode
+        }
+        "INVALID_SIGNATURE_VRS" => Rejection::Signature,
+        "INVALID_CHAINID" => Rejection::Invalid(InvalidTransaction::ChainIdMismatch),
+        "INTRINSIC_GAS_TOO_LOW" | "INTRINSIC_GAS_BELOW_FLOOR_GAS_COST" => {
+            Rejection::Invalid(InvalidTransaction::IntrinsicGasTooLow)
+        }
+        "NONCE_IS_MAX" => Rejection::Invalid(InvalidTransaction::NonceMax),
+        "GASLIMIT_PRICE_PRODUCT_OVERFLOW" | "INSUFFICIENT_ACCOUNT_FUNDS" => {
+            Rejection::Invalid(InvalidTransaction::InsufficientFunds)
+        }
+        "PRIORITY_GREATER_THAN_MAX_FEE_PER_GAS" => {
+            Rejection::Invalid(InvalidTransaction::PriorityFeeTooHigh)
+        }
+        "INITCODE_SIZE_EXCEEDED" => Rejection::Invalid(InvalidTransaction::InitCodeSizeLimit),
+        "TYPE_3_TX_ZERO_BLOBS" | "TYPE_3_TX_BLOB_COUNT_EXCEEDED" => {
+            Rejection::Invalid(InvalidTransaction::InvalidBlobCount)
+        }
+        "TYPE_3_TX_INVALID_BLOB_VERSIONED_HASH" => {
+            Rejection::Invalid(InvalidTransaction::InvalidBlobVersionedHash)
+        }
+        "TYPE_4_EMPTY_AUTHORIZATION_LIST" => {
+            Rejection::Invalid(InvalidTransaction::EmptyAuthorizationList)
+        }
+        _ => panic!("unknown exception {}", exception),
+    }
+}
+
+/// Decode the transaction, recover its sender and check it on chain 1 with a sender that can
+/// afford it.
+fn check(raw: &[u8]) -> Result<(H256, H160), Rejection> {
+    let signed = SignedTransaction::decode(raw).map_err(|_| Rejection::Decode)?;
+    let sender = signed.recover_sender().map_err(|_| Rejection::Signature)?;
+    let tx = &signed.transaction;
+
+    let mut db = InMemoryDatabase::new();
+    db.insert_account(sender, Account::new(U256::MAX, tx.nonce(), Bytes::new()));
+    Vm::with_env(db, Env::default())
+        .transact(&signed)
+        .map_err(Rejection::Invalid)?;
+
+    Ok((signed.hash(), sender))
+}
+
+/// Transactions in the ethereum/tests TransactionTests format, in any subdirectory like the
+/// upstream `ttNonce` or `ttSignature` ones.
+#[test]
+fn transactions() {
+    for entry in glob("tests/transaction/**/*.json").expect("Failed to read glob pattern") {
+        let path = entry.unwrap();
+        println!("{}", path.display());
+
+        let data = fs::read_to_string(&path).expect("Unable to read file");
+        let cases: HashMap<String, TestCase> =
+            serde_json::from_str(&data).expect("Unable to parse");
+
+        for (name, case) in cases {
+            let expected = match case.result.get(FORK) {
+                Some(expected) => expected,
+                None => continue,
+            };
+
+            let raw = hex::decode(&case.txbytes[2..]).unwrap();
+            let result = check(&raw);
+
+            // Several exceptions separated by `|` mean any of them is right.
+            if let Some(exception) = &expected.exception {
+                let rejections: Vec<_> = exception.split('|').map(rejection).collect();
+                let actual = result.err();
+                assert!(
+                    actual
+                        .as_ref()
+                        .is_some_and(|actual| rejections.contains(actual)),
+                    "{} should fail with {}, got {:?}",
+                    name,
+                    exception,
+                    actual
+                );
+                continue;
+            }
+
+            let (hash, sender) = result.unwrap_or_else(|error| panic!("{}: {:?}", name, error));
+            let expected_hash = expected.hash.as_ref().unwrap();
+            let expected_sender = expected.sender.as_ref().unwrap();
+            assert_eq!(
+                hash,
+                H256::from_str(&expected_hash[2..]).unwrap(),
+                "{}",
+                name
+            );
+            assert_eq!(
+                sender,
+                H160::from_str(&expected_sender[2..]).unwrap(),
+                "{}",
+                name
+            );
+        }
+    }
+}
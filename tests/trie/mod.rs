@@ -0,0 +1,99 @@
+use evm_rust::Trie;
+use glob::glob;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use tiny_keccak::{Hasher, Keccak};
+
+#[derive(Deserialize)]
+struct TestCase {
+    #[serde(rename = "in")]
+    input: Value,
+    root: String,
+}
+
+/// Keys and values starting with `0x` are hex, anything else is the raw string.
+fn parse(value: &str) -> Vec<u8> {
+    match value.strip_prefix("0x") {
+        Some(hex) => hex::decode(hex).unwrap(),
+        None => value.as_bytes().to_vec(),
+    }
+}
+
+fn keccak256(data: &[u8]) -> Vec<u8> {
+    let mut output = [0u8; 32];
+    let mut hasher = Keccak::v256();
+    hasher.update(data);
+    hasher.finalize(&mut output);
+    output.to_vec()
+}
+
+/// Run the trie tests of the files matching `pattern`. Files named like the upstream
+/// `*_secureTrie.json` ones key the trie by the hash of each key, as the state and storage tries
+/// do.
+fn run_trie_tests(pattern: &str) {
+    for entry in glob(pattern).expect("Failed to read glob pattern") {
+        let path = entry.unwrap();
+        let secure = path.to_string_lossy().to_lowercase().contains("securetrie");
+
+        let data = fs::read_to_string(&path).expect("Unable to read file");
+        let cases: BTreeMap<String, TestCase> =
+            serde_json::from_str(&data).expect("Unable to parse");
+
+        for (name, case) in cases {
+            println!("{}: {}", path.display(), name);
+            run_trie_test(&name, case, secure);
+        }
+    }
+}
+
+fn run_trie_test(name: &str, case: TestCase, secure: bool) {
+    let key = |value: &str| {
+        if secure {
+            keccak256(&parse(value))
+        } else {
+            parse(value)
+        }
+    };
+
+    let mut trie = Trie::new();
+
+    match case.input {
+        // Inserts in any order.
+        Value::Object(entries) => {
+            for (raw_key, value) in entries {
+                trie.insert(&key(&raw_key), &parse(value.as_str().unwrap()));
+            }
+        }
+        // Inserts in order, a null value removes the key.
+        Value::Array(entries) => {
+            for entry in entries {
+                let key = key(entry[0].as_str().unwrap());
+
+                match entry[1].as_str() {
+                    Some(value) => trie.insert(&key, &parse(value)),
+                    None => trie.remove(&key),
+                }
+            }
+        }
+        _ => panic!("Unexpected input in {}", name),
+    }
+
+    assert_eq!(format!("{:?}", trie.root()), case.root, "{}", name);
+}
+
+#[test]
+fn trie_any_order() {
+    run_trie_tests("tests/trie/trieanyorder*.json");
+}
+
+#[test]
+fn trie_in_order() {
+    run_trie_tests("tests/trie/trietest*.json");
+}
+
+#[test]
+fn hex_encoded_secure_trie() {
+    run_trie_tests("tests/trie/hex_encoded_securetrie_test.json");
+}